@@ -1,6 +1,37 @@
-use crate::utils::parse_u256;
-use clap::Parser;
+use crate::utils::{parse_u256, parse_eth_amount, parse_date};
+use chrono::NaiveDate;
+use clap::{Parser, ValueEnum};
 use ethers::types::U256;
+use crate::contract::purse_roles::Role;
+
+/// Function the owner sets the allowance with
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ApproveFunction {
+    /// `erc20Approve`
+    Erc20Approve,
+    /// Hybrid `approve`, amounts up to the max token ID are treated as an NFT token ID
+    Approve,
+}
+
+/// Function the spender spends the allowance with
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum SpendFunction {
+    /// `erc20TransferFrom`
+    Erc20TransferFrom,
+    /// Hybrid `transferFrom`, amounts up to the max token ID are treated as an NFT token ID
+    TransferFrom,
+    /// `burnFrom`
+    BurnFrom,
+}
+
+/// Output format of reporting commands
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Plain text table
+    Table,
+    /// Pretty printed JSON
+    Json,
+}
 
 #[derive(Debug, Clone, Parser, PartialEq)]
 pub struct ContractCliArgs {
@@ -12,13 +43,15 @@ pub struct ContractCliArgs {
     #[clap(long, required = true)]
     pub function: String,
 
-    /// Executed function arguments
+    /// Executed function arguments.
+    /// ERC20 amounts are in wei, or given with a unit, eg: "1.5ether", "250gwei", "100e18" or "100 PURSE"
     #[clap(long, num_args = 1.., requires("function"))]
     pub calldata: Option<Vec<String>>,
 
-    /// Msg.value for the function call
-    #[clap(long, value_parser=parse_u256, default_value="0", requires("function"))]
-    pub msg_value: U256,
+    /// Msg.value for the function call, in wei or with a unit, eg: "0.01ether".
+    /// For mintERC721 it defaults to the minting cost of the minted units, and zero otherwise
+    #[clap(long, value_parser=parse_eth_amount, requires("function"))]
+    pub msg_value: Option<U256>,
 
     /// Chain Id: 1 for mainnet, 11155111 for sepolia
     #[clap(long, required = true)]
@@ -27,6 +60,766 @@ pub struct ContractCliArgs {
     /// File path to store the csv output
     #[clap(long, required = true)]
     pub file_path: String,
+
+    /// Allow appending to a csv file written with an older log schema
+    #[clap(long)]
+    pub allow_older_schema: bool,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct ReconcileCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// File path of the csv log, its journal is stored alongside it
+    #[clap(long, required = true)]
+    pub file_path: String,
+
+    /// Rebroadcast journaled transactions that are not known to the network
+    #[clap(long)]
+    pub rebroadcast: bool,
+
+    /// Allow appending to a csv file written with an older log schema
+    #[clap(long)]
+    pub allow_older_schema: bool,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct ReportCliArgs {
+    /// File path of the csv log
+    #[clap(long, required = true)]
+    pub file_path: String,
+
+    /// Only include transactions sent from these derivation numbers
+    #[clap(long, num_args = 1..)]
+    pub derivation: Option<Vec<u32>>,
+
+    /// Only include calls to these functions
+    #[clap(long, num_args = 1..)]
+    pub function: Option<Vec<String>>,
+
+    /// Only include transactions on or after this date (YYYY-MM-DD, UTC)
+    #[clap(long, value_parser=parse_date)]
+    pub from_date: Option<NaiveDate>,
+
+    /// Only include transactions on or before this date (YYYY-MM-DD, UTC)
+    #[clap(long, value_parser=parse_date)]
+    pub to_date: Option<NaiveDate>,
+
+    /// Output format
+    #[clap(long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct VerifyLogCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// File path of the csv log
+    #[clap(long, required = true)]
+    pub file_path: String,
+
+    /// Output format
+    #[clap(long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct IndexSyncCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// File path of the SQLite event index
+    #[clap(long, default_value = "purse_index.db")]
+    pub db_path: String,
+
+    /// First block to index, defaults to the block after the last indexed block, and may not be later than it
+    #[clap(long)]
+    pub from_block: Option<u64>,
+
+    /// Last block to index, defaults to and may not be later than the latest confirmed block
+    #[clap(long)]
+    pub to_block: Option<u64>,
+
+    /// Number of blocks behind the latest block that are indexed, so events of reorged blocks are not stored
+    #[clap(long, default_value = "12")]
+    pub confirmations: u64,
+
+    /// Number of blocks per `eth_getLogs` request
+    #[clap(long, default_value = "2000")]
+    pub page_size: u64,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct IndexHeldCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// File path of the SQLite event index
+    #[clap(long, default_value = "purse_index.db")]
+    pub db_path: String,
+
+    /// Derivation number of the holder
+    #[clap(long, required_unless_present = "address", conflicts_with = "address")]
+    pub derivation: Option<u32>,
+
+    /// Address of the holder
+    #[clap(long)]
+    pub address: Option<String>,
+
+    /// Output format
+    #[clap(long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct IndexHoldersCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// File path of the SQLite event index
+    #[clap(long, default_value = "purse_index.db")]
+    pub db_path: String,
+
+    /// Block to list the holders at, defaults to the last indexed block
+    #[clap(long)]
+    pub block: Option<u64>,
+
+    /// Output format
+    #[clap(long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct WatchCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Highest derivation number watched, wallets from derivation 0 up to it are watched
+    #[clap(long, default_value = "10")]
+    pub max_derivation: u32,
+
+    /// Append transactions touching the watched wallets to this csv log
+    #[clap(long)]
+    pub file_path: Option<String>,
+
+    /// Allow appending to a csv file written with an older log schema
+    #[clap(long, requires = "file_path")]
+    pub allow_older_schema: bool,
+
+    /// Poll for new blocks instead of subscribing
+    #[clap(long)]
+    pub poll: bool,
+
+    /// Seconds between polls
+    #[clap(long, default_value = "12")]
+    pub poll_interval: u64,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct WalletsListCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Highest derivation number listed, wallets from derivation 0 up to it are listed
+    #[clap(long, default_value = "10")]
+    pub max_derivation: u32,
+
+    /// Block to read the balances at, defaults to the latest block
+    #[clap(long)]
+    pub block: Option<u64>,
+
+    /// Also list the owned NFT token IDs
+    #[clap(long)]
+    pub owned: bool,
+
+    /// Output format
+    #[clap(long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct MulticallDeployCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Derivation number of the deploying wallet
+    #[clap(long, default_value = "0")]
+    pub derivation: u32,
+
+    /// File path of the Multicall3 creation bytecode, hex encoded, eg: the compiled `Multicall3.bin`
+    #[clap(long, required = true)]
+    pub bytecode_path: String,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct InspectCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Derivation number of the account to inspect
+    #[clap(long, conflicts_with = "address")]
+    pub derivation: Option<u32>,
+
+    /// Address of the account to inspect, only the global state is shown without an account
+    #[clap(long)]
+    pub address: Option<String>,
+
+    /// Position in the ERC721 queue of the first listed token ID
+    #[clap(long, default_value = "0")]
+    pub queue_start: u64,
+
+    /// Number of ERC721 queue token IDs listed
+    #[clap(long, default_value = "20")]
+    pub queue_count: u64,
+
+    /// Output format
+    #[clap(long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct AllowanceFlowCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Derivation number of the owner, who sets the allowance
+    #[clap(long, required = true)]
+    pub owner_derivation: u32,
+
+    /// Derivation number of the spender, who spends the allowance
+    #[clap(long, required = true)]
+    pub spender_derivation: u32,
+
+    /// Allowance set, and then spent, in wei or with a unit, eg: "1.5ether", "100e18" or "100 PURSE"
+    #[clap(long, required = true)]
+    pub amount: String,
+
+    /// Function the owner sets the allowance with
+    #[clap(long, value_enum, default_value = "erc20-approve")]
+    pub approve_with: ApproveFunction,
+
+    /// Function the spender spends the allowance with
+    #[clap(long, value_enum, default_value = "erc20-transfer-from")]
+    pub spend_with: SpendFunction,
+
+    /// Recipient of the spent tokens, defaults to the spender. Not used by burnFrom
+    #[clap(long)]
+    pub recipient: Option<String>,
+
+    /// File path of the csv log both transactions are appended to
+    #[clap(long, required = true)]
+    pub file_path: String,
+
+    /// Allow appending to a csv file written with an older log schema
+    #[clap(long)]
+    pub allow_older_schema: bool,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct PermitSignCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Derivation number of the owner, who signs the permit
+    #[clap(long, required = true)]
+    pub owner_derivation: u32,
+
+    /// Address of the spender
+    #[clap(long, required_unless_present = "spender_derivation", conflicts_with = "spender_derivation")]
+    pub spender: Option<String>,
+
+    /// Derivation number of the spender
+    #[clap(long)]
+    pub spender_derivation: Option<u32>,
+
+    /// Allowance granted by the permit, in wei or with a unit, eg: "1.5ether", "100e18" or "100 PURSE"
+    #[clap(long, required = true)]
+    pub value: String,
+
+    /// Unix timestamp (seconds) the permit is valid until, may be in the past to test expired permits
+    #[clap(long, conflicts_with = "valid_for")]
+    pub deadline: Option<u64>,
+
+    /// Seconds from now the permit is valid for, when no deadline is given
+    #[clap(long, default_value = "3600")]
+    pub valid_for: u64,
+
+    /// File path to write the signed permit JSON to, it is printed otherwise
+    #[clap(long)]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct PermitSubmitCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Derivation number of the relayer, who sends the permit and pays the gas
+    #[clap(long, required = true)]
+    pub relayer_derivation: u32,
+
+    /// File path of the signed permit JSON, see `permit sign`
+    #[clap(long, required = true)]
+    pub permit_path: String,
+
+    /// Submit the permit for another owner than the signer, to test `ERC2612InvalidSigner`
+    #[clap(long)]
+    pub owner: Option<String>,
+
+    /// File path of the csv log the permit transaction is appended to
+    #[clap(long)]
+    pub file_path: Option<String>,
+
+    /// Allow appending to a csv file written with an older log schema
+    #[clap(long, requires = "file_path")]
+    pub allow_older_schema: bool,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct RolesListCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Address to list the roles of
+    #[clap(long, conflicts_with_all = ["derivation", "max_derivation"])]
+    pub address: Option<String>,
+
+    /// Derivation number of the wallet to list the roles of
+    #[clap(long, conflicts_with = "max_derivation")]
+    pub derivation: Option<u32>,
+
+    /// Highest derivation number listed, when no address or derivation is given
+    #[clap(long, default_value = "10")]
+    pub max_derivation: u32,
+
+    /// Output format
+    #[clap(long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct RolesUpdateCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Derivation number of the admin sending the transaction
+    #[clap(long, default_value = "0")]
+    pub admin_derivation: u32,
+
+    /// Role to grant or revoke
+    #[clap(long, value_enum, required = true)]
+    pub role: Role,
+
+    /// Address of the account
+    #[clap(long, required_unless_present = "account_derivation", conflicts_with = "account_derivation")]
+    pub account: Option<String>,
+
+    /// Derivation number of the account
+    #[clap(long)]
+    pub account_derivation: Option<u32>,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct RolesRenounceCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Derivation number of the wallet giving up the role
+    #[clap(long, required = true)]
+    pub derivation: u32,
+
+    /// Role to renounce
+    #[clap(long, value_enum, required = true)]
+    pub role: Role,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct RolesCheckCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Derivation number of the wallet that would make the call
+    #[clap(long, required = true)]
+    pub derivation: u32,
+
+    /// Role-gated function, eg: mint, pause, unpause, setMint721Cost
+    #[clap(long, required = true)]
+    pub function: String,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct AdminCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Derivation number of the wallet sending the transaction, holding the role the function is gated by
+    #[clap(long, default_value = "0")]
+    pub derivation: u32,
+
+    /// Send without asking for confirmation
+    #[clap(long)]
+    pub yes: bool,
+
+    /// File path of the csv log the transaction is appended to
+    #[clap(long)]
+    pub file_path: Option<String>,
+
+    /// Allow appending to a csv file written with an older log schema
+    #[clap(long, requires = "file_path")]
+    pub allow_older_schema: bool,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct AdminSetMintCostCliArgs {
+    #[clap(flatten)]
+    pub admin: AdminCliArgs,
+
+    /// Msg value paid per minted NFT, in wei or with a unit, eg: "0.01ether"
+    #[clap(long, value_parser=parse_eth_amount, required = true)]
+    pub cost: U256,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct AdminSetMaxTokenIdCliArgs {
+    #[clap(flatten)]
+    pub admin: AdminCliArgs,
+
+    /// Max NFT token ID
+    #[clap(long, value_parser=parse_u256, required = true)]
+    pub cap: U256,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct AdminSetTreasuryCliArgs {
+    #[clap(flatten)]
+    pub admin: AdminCliArgs,
+
+    /// Address of the new treasury
+    #[clap(long, required = true)]
+    pub treasury: String,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct AdminSetBaseUriCliArgs {
+    #[clap(flatten)]
+    pub admin: AdminCliArgs,
+
+    /// Base URI of the NFT metadata
+    #[clap(long, required = true)]
+    pub uri: String,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct AdminRecoverEthCliArgs {
+    #[clap(flatten)]
+    pub admin: AdminCliArgs,
+
+    /// ETH recovered from the contract, in wei or with a unit, eg: "0.5ether"
+    #[clap(long, value_parser=parse_eth_amount, required = true)]
+    pub amount: U256,
+
+    /// Address of the recipient
+    #[clap(long, required = true)]
+    pub recipient: String,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct AdminRecoverTokenCliArgs {
+    #[clap(flatten)]
+    pub admin: AdminCliArgs,
+
+    /// Address of the ERC20 token recovered from the contract
+    #[clap(long, required = true)]
+    pub token: String,
+
+    /// Tokens recovered from the contract, in wei
+    #[clap(long, value_parser=parse_u256, required = true)]
+    pub amount: U256,
+
+    /// Address of the recipient
+    #[clap(long, required = true)]
+    pub recipient: String,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct UpgradeCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Derivation number of the wallet sending the upgrade, holding `UPGRADER_ROLE`
+    #[clap(long, default_value = "0")]
+    pub derivation: u32,
+
+    /// Address of the new implementation
+    #[clap(long, required = true)]
+    pub implementation: String,
+
+    /// Signature of an initializer called with the upgrade, eg: "initializeV2(uint256)"
+    #[clap(long, conflicts_with = "init_data")]
+    pub init_signature: Option<String>,
+
+    /// Argument of the initializer, repeated in the order of the signature
+    #[clap(long, requires = "init_signature")]
+    pub init_arg: Vec<String>,
+
+    /// Hex encoded calldata called with the upgrade, instead of an initializer signature
+    #[clap(long)]
+    pub init_data: Option<String>,
+
+    /// Only simulate the upgrade
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Send without asking for confirmation
+    #[clap(long)]
+    pub yes: bool,
+
+    /// File path of the upgrade record, a JSON line with the previous and new implementation is appended per upgrade
+    #[clap(long, default_value = "upgrades.jsonl")]
+    pub record_path: String,
+
+    /// File path of the csv log the transaction is appended to
+    #[clap(long)]
+    pub file_path: Option<String>,
+
+    /// Allow appending to a csv file written with an older log schema
+    #[clap(long, requires = "file_path")]
+    pub allow_older_schema: bool,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct DeployCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia, 31337 or 1337 for a local devnet
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Derivation number of the deploying wallet
+    #[clap(long, default_value = "0")]
+    pub derivation: u32,
+
+    /// File path of the compiled Purse404 artifact (Foundry or Hardhat JSON)
+    #[clap(long, required = true)]
+    pub artifact: String,
+
+    /// File path of the compiled ERC1967Proxy artifact (Foundry or Hardhat JSON)
+    #[clap(long, required = true)]
+    pub proxy_artifact: String,
+
+    /// Derivation number granted `DEFAULT_ADMIN_ROLE`, defaults to the deploying wallet. Also sends `init404`
+    #[clap(long)]
+    pub admin_derivation: Option<u32>,
+
+    /// Derivation number granted `PAUSER_ROLE`, defaults to the deploying wallet
+    #[clap(long)]
+    pub pauser_derivation: Option<u32>,
+
+    /// Derivation number granted `MINTER_ROLE`, defaults to the deploying wallet
+    #[clap(long)]
+    pub minter_derivation: Option<u32>,
+
+    /// Derivation number granted `UPGRADER_ROLE`, defaults to the deploying wallet
+    #[clap(long)]
+    pub upgrader_derivation: Option<u32>,
+
+    /// Decimals of the ERC20 amount backing one NFT, passed to `init404`
+    #[clap(long, value_parser=parse_u256, default_value = "18")]
+    pub unit404_decimals: U256,
+
+    /// File path of the dotenv file the proxy address is written to, as `<CHAIN>_PURSE_ADDRESS`
+    #[clap(long, default_value = ".env")]
+    pub env_path: String,
+
+    /// Do not write the proxy address to the dotenv file, only to the deployment registry
+    #[clap(long)]
+    pub no_env: bool,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct OfflineSignCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Derivation number of the signing wallet
+    #[clap(long, default_value = "0")]
+    pub derivation: u32,
+
+    /// Contract function to sign a transaction for, state changing functions only
+    #[clap(long, required = true)]
+    pub function: String,
+
+    /// Executed function arguments.
+    /// ERC20 amounts are in wei, or given with a unit, eg: "1.5ether", "100e18" or "100 PURSE"
+    #[clap(long, num_args = 1..)]
+    pub calldata: Option<Vec<String>>,
+
+    /// Msg.value for the function call, in wei or with a unit, eg: "0.01ether".
+    /// Required for mintERC721 since the minting cost cannot be read offline
+    #[clap(long, value_parser=parse_eth_amount)]
+    pub msg_value: Option<U256>,
+
+    /// Token symbol accepted as the unit of ERC20 amounts, `symbol()` cannot be read offline
+    #[clap(long, default_value = "PURSE")]
+    pub symbol: String,
+
+    /// Token decimals of ERC20 amounts given with the token symbol, `decimals()` cannot be read offline
+    #[clap(long, default_value = "18")]
+    pub decimals: u8,
+
+    /// Nonce of the signing wallet, the transaction count it has sent on the chain
+    #[clap(long, value_parser=parse_u256, required = true)]
+    pub nonce: U256,
+
+    /// Gas limit
+    #[clap(long, value_parser=parse_u256, required = true)]
+    pub gas_limit: U256,
+
+    /// Max fee per gas, in wei or with a unit, eg: "30gwei". Signs an EIP-1559 transaction
+    #[clap(long, value_parser=parse_eth_amount, required_unless_present = "gas_price", requires = "max_priority_fee_per_gas")]
+    pub max_fee_per_gas: Option<U256>,
+
+    /// Max priority fee per gas, in wei or with a unit, for an EIP-1559 transaction
+    #[clap(long, value_parser=parse_eth_amount, requires = "max_fee_per_gas")]
+    pub max_priority_fee_per_gas: Option<U256>,
+
+    /// Gas price, in wei or with a unit, eg: "30gwei". Signs a legacy transaction
+    #[clap(long, value_parser=parse_eth_amount, conflicts_with = "max_fee_per_gas")]
+    pub gas_price: Option<U256>,
+
+    /// File path to write the signed transaction JSON to, it is printed otherwise
+    #[clap(long)]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct OfflineBroadcastCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// File path of the signed transaction JSON, see `offline sign`
+    #[clap(long, required = true)]
+    pub tx_path: String,
+
+    /// File path of the csv log to append the transaction to, it is not logged otherwise
+    #[clap(long)]
+    pub file_path: Option<String>,
+
+    /// Allow appending to a csv file written with an older log schema
+    #[clap(long)]
+    pub allow_older_schema: bool,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct CalldataEncodeCliArgs {
+    /// Function name, or its full signature to select an overload, eg: "safeTransferFrom(address,address,uint256)"
+    #[clap(required = true)]
+    pub function: String,
+
+    /// Function arguments: integers in decimal, addresses and bytes as 0x prefixed hex, arrays as "[1,2]"
+    #[clap(num_args = 0.., allow_hyphen_values = true)]
+    pub args: Vec<String>,
+
+    /// File path of the ABI JSON or a compiled artifact, defaults to the Purse404 ABI
+    #[clap(long)]
+    pub abi: Option<String>,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct CalldataDecodeCliArgs {
+    /// Hex encoded calldata, starting with the function selector
+    #[clap(required_unless_present = "tx_hash", conflicts_with = "tx_hash")]
+    pub data: Option<String>,
+
+    /// Hash of a sent transaction to decode the input of
+    #[clap(long, requires = "chain_id")]
+    pub tx_hash: Option<String>,
+
+    /// Chain Id of the transaction: 1 for mainnet, 11155111 for sepolia
+    #[clap(long)]
+    pub chain_id: Option<u32>,
+
+    /// File path of the ABI JSON or a compiled artifact, defaults to the Purse404 ABI
+    #[clap(long)]
+    pub abi: Option<String>,
+
+    /// Output format
+    #[clap(long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct SafeProposeCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Address of the Safe executing the transaction
+    #[clap(long, required = true)]
+    pub safe: String,
+
+    /// Purse404 function call, the function and its arguments separated by spaces, eg: "setMaxTokenId 5000".
+    /// Repeat to batch several calls through MultiSend
+    #[clap(long, required = true, num_args = 1)]
+    pub call: Vec<String>,
+
+    /// Msg.value sent from the Safe with a single call, in wei or with a unit, eg: "0.01ether"
+    #[clap(long, value_parser=parse_eth_amount)]
+    pub msg_value: Option<U256>,
+
+    /// Safe nonce to propose at, defaults to the current nonce of the Safe
+    #[clap(long, value_parser=parse_u256)]
+    pub nonce: Option<U256>,
+
+    /// File path to write the Safe transaction JSON to, it is printed otherwise
+    #[clap(long)]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct SafeSignCliArgs {
+    /// Chain Id: 1 for mainnet, 11155111 for sepolia
+    #[clap(long, required = true)]
+    pub chain_id: u32,
+
+    /// Derivation number of the signing Safe owner
+    #[clap(long, required = true)]
+    pub derivation: u32,
+
+    /// File path of the Safe transaction JSON, see `safe propose`. The signature is added to it
+    #[clap(long, required = true)]
+    pub tx_path: String,
+}
+
+#[derive(Debug, Clone, Parser, PartialEq)]
+pub struct LogMigrateCliArgs {
+    /// File path of the csv log to migrate
+    #[clap(long, required = true)]
+    pub file_path: String,
+
+    /// File path to write the migrated csv log to, defaults to rewriting in place
+    #[clap(long)]
+    pub output_path: Option<String>,
 }
 
 #[cfg(test)]
@@ -60,9 +853,10 @@ mod tests {
                     "aaabbbccc".to_string(),
                     "0xbadc0ffee".to_string(),
                 ]),
-                msg_value: U256::from_dec_str("1000000000000000000").unwrap(),
+                msg_value: Some(U256::from_dec_str("1000000000000000000").unwrap()),
                 chain_id: 1,
                 file_path: "test.csv".to_string(),
+                allow_older_schema: false,
             },
             ContractCliArgs::try_parse_from(args).unwrap()
         );
@@ -94,9 +888,10 @@ mod tests {
                     "aaabbbccc".to_string(),
                     "0xbadc0ffee".to_string(),
                 ]),
-                msg_value: U256::from_dec_str("1000000000000000000").unwrap(),
+                msg_value: Some(U256::from_dec_str("1000000000000000000").unwrap()),
                 chain_id: 11155111,
                 file_path: "test.csv".to_string(),
+                allow_older_schema: false,
             },
             ContractCliArgs::try_parse_from(args).unwrap()
         );
@@ -118,9 +913,10 @@ mod tests {
                 derivation_number: None,
                 function: "minted".to_string(),
                 calldata: None,
-                msg_value: U256::from_dec_str("0").unwrap(),
+                msg_value: None,
                 chain_id: 11155111,
                 file_path: "test.csv".to_string(),
+                allow_older_schema: false,
             },
             ContractCliArgs::try_parse_from(args).unwrap()
         );
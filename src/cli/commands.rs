@@ -1,21 +1,84 @@
+mod admin;
+mod allowance_flow;
+mod calldata;
+mod deploy;
+mod index;
+mod inspect;
+mod log;
+mod multicall;
+mod offline;
+mod permit;
+mod reconcile;
+mod report;
+mod roles;
+mod safe;
+mod upgrade;
+mod verify_log;
+mod wallets;
+mod watch;
+
+pub use admin::AdminCommand;
+pub use allowance_flow::AllowanceFlowCommand;
+pub use calldata::CalldataCommand;
+pub use deploy::DeployCommand;
+pub use index::IndexCommand;
+pub use inspect::InspectCommand;
+pub use log::LogCommand;
+pub use multicall::MulticallCommand;
+pub use offline::OfflineCommand;
+pub use permit::PermitCommand;
+pub use reconcile::ReconcileCommand;
+pub use report::ReportCommand;
+pub use roles::RolesCommand;
+pub use safe::SafeCommand;
+pub use upgrade::UpgradeCommand;
+pub use verify_log::VerifyLogCommand;
+pub use wallets::WalletsCommand;
+pub use watch::WatchCommand;
+
 use dotenv::dotenv;
 use std::sync::Arc;
 use clap::Parser;
+use ethers::{
+    providers::Middleware,
+    types::{BlockId, Transaction, TransactionReceipt, H256, U256},
+};
 use super::args::ContractCliArgs;
 use crate::{
     file::{
         read_from_csv, 
         write_to_csv,
+        LogEntry,
+    },
+    journal::{
+        Journal,
+        JournalStatus,
     },
     utils::{
-        to_address_type, 
-        get_provider, 
-        get_native_balance,
+        get_purse_address, 
+        get_chain_provider, 
+        get_block_number,
+        get_block_timestamp,
+        get_tx_hash,
+        get_gas_price,
+        get_gas_used,
+        calc_tx_fee,
+        format_amount,
+        parse_amount,
     },
     wallet::Wallet,
     contract::{
         purse_contract::Purse404Contract,
+        purse_events::{
+            decode_receipt_events,
+            NftMovements,
+        },
         purse_executor::{
+            allowance_parties,
+            allowance_third_party,
+            amount_calldata_index,
+            decode_purse_calldata,
+            erc721_token_id,
             validate_purse_calldata,
             transfer_or_mint_recipient_n_calldata,
             Purse404FunctionCall,
@@ -23,7 +86,6 @@ use crate::{
             Purse404Results,
         },
     },
-    constants::PURSE_ETH_ADDRESS,
 };
 
 #[derive(Debug, Parser)]
@@ -82,17 +144,7 @@ impl PurseCommand {
             }
         }
 
-        let prov = match cid {
-            1 => get_provider(
-                std::env::var("MAINNET_RPC").unwrap().as_str()
-            ).await?,
-            11155111 => get_provider(
-                std::env::var("SEPOLIA_RPC").unwrap().as_str()
-            ).await?,
-            _ => {
-                return Err(eyre::eyre!("Unsupported chain id: {}. Halting...", cid))
-            }
-        };
+        let prov = get_chain_provider(cid).await?;
 
         let wallet = Wallet::from_phrase(
             phrase.as_str(),
@@ -101,43 +153,60 @@ impl PurseCommand {
         ).unwrap();
         let msg_sender_address = wallet.address();
 
-        let msg_value = self.cli_args.msg_value;
         let call_fn = self.cli_args.function;
         let calldata = self.cli_args.calldata;
         validate_purse_calldata(&call_fn, &calldata)?;
-        let cdata_vec = calldata.unwrap_or_default();
+        let mut cdata_vec = calldata.unwrap_or_default();
 
+        // Every transaction is journaled before it is broadcast, so that a transaction
+        // that was sent but could not be logged can be recovered with `reconcile`
+        let journal = Journal::for_log(&file_path);
+        let purse_token = Purse404Contract::new(
+            get_purse_address(cid)?,
+            &Arc::new(prov.clone()),
+        ).with_journal(journal.clone());
+
+        if amount_calldata_index(&call_fn).is_some() {
+            let (symbol, decimals) = (purse_token.symbol().await?, purse_token.decimals().await?);
+            normalize_calldata_amount(&call_fn, &mut cdata_vec, &symbol, decimals)?;
+        }
         let (msg_recipient_address, calldata_value) = transfer_or_mint_recipient_n_calldata(
             &call_fn, 
             &cdata_vec
-        );
-
-        let purse_token = Purse404Contract::new(
-            to_address_type(PURSE_ETH_ADDRESS),
-            &Arc::new(prov.clone()),
-        );
+        )?;
         
         let function_call = Purse404FunctionCall::from_data(
             &call_fn, 
-            &msg_value, 
+            self.cli_args.msg_value, 
             cdata_vec.clone(), 
             wallet
         )?;
+        // Refuses calls that would revert for a known reason, and sets the msg value of mintERC721
+        let function_call = Purse404Executor::preflight(&purse_token, function_call).await?;
+        let msg_value = function_call.msg_value();
+        if !msg_value.is_zero() {
+            println!("> Msg value: {} wei ({} ETH)", msg_value, format_amount(msg_value, 18));
+        }
 
-        let sender_eth_bal_bef = get_native_balance(&prov, &msg_sender_address).await?;
-        let sender_erc20_bal_bef = purse_token.balance_of(&msg_sender_address).await?;
-        let recipient_eth_bal_bef = get_native_balance(&prov, &msg_recipient_address).await?;
-        let recipient_erc20_bal_bef = purse_token.balance_of(&msg_recipient_address).await?;
-
-        let tx_result = Purse404Executor::execute_fn(
-            &purse_token, 
-            function_call
-        ).await?;
+        let tx_result = match Purse404Executor::execute_fn(&purse_token, function_call).await {
+            Ok(res) => res,
+            Err(e) => {
+                if !journal.unsettled()?.is_empty() {
+                    eprintln!("> The journal has transactions that were sent but not logged");
+                    eprintln!("> Run `reconcile --chain-id {} --file-path {}` to complete the log", cid, file_path);
+                }
+                return Err(e)
+            }
+        };
         
         match tx_result {
-            Purse404Results::Address(addr) => {
+            Purse404Results::Address(addr) if call_fn == "address" => {
                 println!("> Purse404 contract address: {:?}", addr);
             },
+            Purse404Results::Address(addr) => {
+                println!("> Function call: {} \n> Calldata: {}", call_fn, cdata_vec.join(", "));
+                println!("> Result: {:?}", addr);
+            },
             Purse404Results::U256Result(res) => {
                 println!("> Function call: {} \n> Calldata: {}", call_fn, cdata_vec.join(", "));
                 println!("> Result: {}", res);
@@ -154,6 +223,10 @@ impl PurseCommand {
                 println!("> Function call: {} \n> Calldata: {}", call_fn, cdata_vec.join(", "));
                 println!("> Result: {:?}", res);
             },
+            Purse404Results::BoolResult(res) => {
+                println!("> Function call: {} \n> Calldata: {}", call_fn, cdata_vec.join(", "));
+                println!("> Result: {}", res);
+            },
             Purse404Results::StateChangeResult((
                 tx_hash,
                 gas_price,
@@ -161,38 +234,245 @@ impl PurseCommand {
                 tx_fees,
                 tx_receipt_json
             )) => {
-                let msg_sender_owned_token_ids = purse_token.owned(&msg_sender_address).await.unwrap();
-                let sender_eth_bal_aft = get_native_balance(&prov, &msg_sender_address).await.unwrap();
-                let sender_erc20_bal_aft = purse_token.balance_of(&msg_sender_address).await.unwrap();
-                let recipient_eth_bal_aft = get_native_balance(&prov, &msg_recipient_address).await.unwrap();
-                let recipient_erc20_bal_aft = purse_token.balance_of(&msg_recipient_address).await.unwrap();
-            
-                let _ = write_to_csv(
-                    &file_path,
-                    &tx_hash,
-                    &gas_price,
-                    &gas_used,
-                    &tx_fees,
-                    &tx_receipt_json,
-                    &call_fn,
-                    derivation_num_set,
-                    msg_sender_address,
-                    Some(sender_eth_bal_bef),
-                    Some(sender_eth_bal_aft),
-                    Some(sender_erc20_bal_bef),
-                    Some(sender_erc20_bal_aft),
-                    msg_recipient_address,
-                    Some(recipient_eth_bal_bef),
-                    Some(recipient_eth_bal_aft),
-                    Some(recipient_erc20_bal_bef),
-                    Some(recipient_erc20_bal_aft),
-                    Some(msg_value),
-                    Some(calldata_value),
-                    Some(msg_sender_owned_token_ids)
-                );
+                let logged: eyre::Result<()> = async {
+                    let receipt: TransactionReceipt = serde_json::from_str(&tx_receipt_json)?;
+                    let events = decode_receipt_events(&receipt, purse_token.address());
+                    for event in events.iter() {
+                        println!("> Event: {}", event);
+                    }
+
+                    let mut entry = LogEntry {
+                        tx_hash: tx_hash.clone(),
+                        gas_price,
+                        gas_used,
+                        tx_fee: tx_fees,
+                        receipt_json: tx_receipt_json.clone(),
+                        call_function: call_fn.clone(),
+                        derivation_number: derivation_num_set,
+                        msg_sender: msg_sender_address,
+                        msg_recipient: msg_recipient_address,
+                        msg_value: Some(msg_value),
+                        calldata_value: Some(calldata_value),
+                        token_id: erc721_token_id(&call_fn, &cdata_vec),
+                        third_party: allowance_third_party(&call_fn, &cdata_vec),
+                        nft_movements: Some(NftMovements::from_events(&events)),
+                        ..Default::default()
+                    };
+                    read_block_snapshots(
+                        &prov,
+                        &purse_token,
+                        &mut entry,
+                        get_block_number(&tx_receipt_json)
+                    ).await?;
+                    write_to_csv(&file_path, &entry, self.cli_args.allow_older_schema)?;
+                    journal.update_status(&tx_hash.parse::<H256>()?, JournalStatus::Logged, None)
+                }.await;
+
+                if let Err(e) = logged {
+                    eprintln!("> Transaction {} was sent, but could not be logged: {}", tx_hash, e);
+                    eprintln!("> Run `reconcile --chain-id {} --file-path {}` to complete the log", cid, file_path);
+                    return Err(e)
+                }
             }
         }
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Reads the balance snapshots around a mined transaction into its log entry.
+/// 
+/// The "before" balances are read at the block before the transaction (`block_number - 1`), and the
+/// "after" balances and the sender's owned token IDs at the block of the transaction (`block_number`),
+/// so transactions sent by others in between do not affect the logged deltas. Both block numbers
+/// and the block timestamp are recorded in the entry.
+/// The third party balances, and the allowance set or spent, are read the same way if the
+/// transaction touches an allowance.
+/// ### Arguments
+/// * `prov` - Provider
+/// * `purse_token` - Purse404 contract
+/// * `entry` - Log entry, with `msg_sender`, `msg_recipient`, `call_function` and `third_party` set
+/// * `block_number` - Block the transaction was included in
+/// 
+/// ### Returns
+/// * `Result<()>` - Result
+async fn read_block_snapshots<M: Middleware + 'static>(
+    prov: &M,
+    purse_token: &Purse404Contract<M>,
+    entry: &mut LogEntry,
+    block_number: u64,
+) -> eyre::Result<()> {
+    let block_num_bef = block_number.saturating_sub(1);
+    let sender = entry.msg_sender;
+    let recipient = entry.msg_recipient;
+
+    // One batched read per block, see `Purse404Contract::balances_at`
+    let mut addresses = vec![sender, recipient];
+    addresses.extend(entry.third_party);
+    let (before, _) = purse_token.balances_at(&addresses, Some(block_num_bef), false).await?;
+    let (after, _) = purse_token.balances_at(&addresses, Some(block_number), true).await?;
+
+    entry.sender_eth_balance_bef = Some(before[0].eth);
+    entry.sender_eth_balance_aft = Some(after[0].eth);
+    entry.sender_erc20_balance_bef = Some(before[0].erc20);
+    entry.sender_erc20_balance_aft = Some(after[0].erc20);
+    entry.recipient_eth_balance_bef = Some(before[1].eth);
+    entry.recipient_eth_balance_aft = Some(after[1].eth);
+    entry.recipient_erc20_balance_bef = Some(before[1].erc20);
+    entry.recipient_erc20_balance_aft = Some(after[1].erc20);
+    entry.msg_sender_owned_token_ids = after[0].owned.clone();
+    if entry.third_party.is_some() {
+        entry.third_party_erc20_balance_bef = Some(before[2].erc20);
+        entry.third_party_erc20_balance_aft = Some(after[2].erc20);
+    }
+    if let Some((owner, spender)) = allowance_parties(&entry.call_function, sender, recipient, entry.third_party) {
+        entry.allowance_bef = Some(purse_token.allowance(&owner, &spender, Some(BlockId::from(block_num_bef))).await?);
+        entry.allowance_aft = Some(purse_token.allowance(&owner, &spender, Some(BlockId::from(block_number))).await?);
+    }
+
+    entry.block_timestamp = Some(get_block_timestamp(prov, block_number).await?);
+    entry.balances_block_bef = Some(block_num_bef);
+    entry.balances_block_aft = Some(block_number);
+
+    Ok(())
+}
+
+/// Writes the csv log row of a mined transaction to the Purse404 contract.
+/// The function and its arguments are decoded from the transaction input, and the
+/// balances are read at the block before, and the block of, the transaction.
+/// ### Arguments
+/// * `prov` - Provider
+/// * `purse_token` - Purse404 contract
+/// * `derivation` - Derivation number to log the transaction under
+/// * `tx` - Mined transaction
+/// * `receipt` - Receipt of the transaction
+/// * `file_path` - File path of the csv log
+/// * `allow_older_schema` - Allow appending to a csv file written with an older log schema
+/// 
+/// ### Returns
+/// * `Result<()>` - Result
+async fn log_mined_transaction<M: Middleware + 'static>(
+    prov: &M,
+    purse_token: &Purse404Contract<M>,
+    derivation: u32,
+    tx: &Transaction,
+    receipt: &TransactionReceipt,
+    file_path: &str,
+    allow_older_schema: bool,
+) -> eyre::Result<()> {
+    let (call_fn, calldata) = decode_purse_calldata(&tx.input)?;
+    let token_id = erc721_token_id(&call_fn, &calldata);
+    let third_party = allowance_third_party(&call_fn, &calldata);
+    let (msg_recipient_address, calldata_value) = transfer_or_mint_recipient_n_calldata(
+        &call_fn,
+        &calldata
+    )?;
+
+    let block_number = match receipt.block_number {
+        Some(n) => n.as_u64(),
+        None => return Err(eyre::eyre!("Receipt of {:?} has no block number", tx.hash))
+    };
+
+    let json_str = serde_json::to_string(receipt)?;
+    let mut log_entry = LogEntry {
+        tx_hash: get_tx_hash(&json_str),
+        gas_price: get_gas_price(&json_str),
+        gas_used: get_gas_used(&json_str),
+        tx_fee: calc_tx_fee(&json_str),
+        receipt_json: json_str,
+        call_function: call_fn,
+        derivation_number: derivation,
+        msg_sender: tx.from,
+        msg_recipient: msg_recipient_address,
+        msg_value: Some(tx.value),
+        calldata_value: Some(calldata_value),
+        token_id,
+        third_party,
+        nft_movements: Some(NftMovements::from_events(
+            &decode_receipt_events(receipt, purse_token.address())
+        )),
+        ..Default::default()
+    };
+    read_block_snapshots(prov, purse_token, &mut log_entry, block_number).await?;
+    write_to_csv(file_path, &log_entry, allow_older_schema)
+}
+
+/// Converts the amount in the calldata of the function to wei, in place, and prints it in wei and in token units.
+/// The amount may be given with a unit (see `parse_amount`), a plain number is in wei.
+/// ### Arguments
+/// * `func` - Function name
+/// * `calldata` - Calldata
+/// * `symbol` - Symbol of the token
+/// * `decimals` - Decimals of the token
+/// 
+/// ### Returns
+/// * `Result<()>` - Result
+fn normalize_calldata_amount(func: &str, calldata: &mut [String], symbol: &str, decimals: u8) -> eyre::Result<()> {
+    let amount = match amount_calldata_index(func).and_then(|i| calldata.get_mut(i)) {
+        Some(amount) => amount,
+        None => return Ok(()),
+    };
+    let label = match func {
+        "approve" | "transferFrom" => "Amount or token ID",
+        _ => "Amount",
+    };
+    *amount = parse_token_amount(amount, symbol, decimals, label)?.to_string();
+    Ok(())
+}
+
+/// Parses an ERC20 amount into wei, and prints it in wei and in token units.
+/// The amount may be given with a unit (see `parse_amount`), a plain number is in wei.
+/// ### Arguments
+/// * `amount` - Amount
+/// * `symbol` - Symbol of the token
+/// * `decimals` - Decimals of the token
+/// * `label` - Label of the printed amount
+/// 
+/// ### Returns
+/// * `Result<U256>` - The amount in wei
+fn parse_token_amount(amount: &str, symbol: &str, decimals: u8, label: &str) -> eyre::Result<U256> {
+    let wei = parse_amount(amount, Some((symbol, decimals)))?;
+    println!("> {}: {} wei ({} {})", label, wei, format_amount(wei, decimals), symbol);
+    Ok(wei)
+}
+
+/// Logs a transaction sent by a command to the csv log, and marks it as logged in the journal.
+/// If it cannot be logged, the `reconcile` command to complete the log is printed.
+/// ### Arguments
+/// * `purse_token` - Purse404 contract, the transaction was sent through
+/// * `journal` - Journal of the csv log
+/// * `derivation` - Derivation number to log the transaction under
+/// * `receipt_json` - Receipt JSON of the transaction
+/// * `file_path` - File path of the csv log
+/// * `allow_older_schema` - Allow appending to a csv file written with an older log schema
+/// * `chain_id` - Chain Id
+/// 
+/// ### Returns
+/// * `Result<()>` - Result
+async fn log_sent_transaction<M: Middleware + 'static>(
+    purse_token: &Purse404Contract<M>,
+    journal: &Journal,
+    derivation: u32,
+    receipt_json: &str,
+    file_path: &str,
+    allow_older_schema: bool,
+    chain_id: u32,
+) -> eyre::Result<()> {
+    let logged: eyre::Result<()> = async {
+        let prov = purse_token.provider();
+        let receipt: TransactionReceipt = serde_json::from_str(receipt_json)?;
+        let tx = prov
+            .get_transaction(receipt.transaction_hash)
+            .await
+            .map_err(|e| eyre::eyre!("{}", e))?
+            .ok_or_else(|| eyre::eyre!("Transaction {:?} not found", receipt.transaction_hash))?;
+        log_mined_transaction(prov.as_ref(), purse_token, derivation, &tx, &receipt, file_path, allow_older_schema).await?;
+        journal.update_status(&receipt.transaction_hash, JournalStatus::Logged, None)
+    }.await;
+
+    if let Err(e) = &logged {
+        eprintln!("> Transaction was sent, but could not be logged: {}", e);
+        eprintln!("> Run `reconcile --chain-id {} --file-path {}` to complete the log", chain_id, file_path);
+    }
+    logged
+}
@@ -0,0 +1,265 @@
+use dotenv::dotenv;
+use std::sync::Arc;
+use clap::{Parser, Subcommand};
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use crate::{
+    cli::args::{
+        AdminCliArgs,
+        AdminRecoverEthCliArgs,
+        AdminRecoverTokenCliArgs,
+        AdminSetBaseUriCliArgs,
+        AdminSetMaxTokenIdCliArgs,
+        AdminSetMintCostCliArgs,
+        AdminSetTreasuryCliArgs,
+    },
+    journal::Journal,
+    utils::{
+        get_purse_address,
+        get_chain_provider,
+        get_native_balance,
+        confirm_prompt,
+    },
+    wallet::Wallet,
+    contract::{
+        purse_contract::Purse404Contract,
+        purse_roles::Role,
+    },
+};
+use super::log_sent_transaction;
+
+#[derive(Debug, Parser)]
+pub struct AdminCommand {
+    /// Admin subcommand
+    #[clap(subcommand)]
+    command: AdminSubcommand,
+}
+
+/// Admin subcommands, each sends one role-gated transaction after confirmation
+#[derive(Debug, Subcommand)]
+pub enum AdminSubcommand {
+    /// Pause the contract (`pause`)
+    #[command(name = "pause")]
+    Pause(AdminCliArgs),
+
+    /// Unpause the contract (`unpause`)
+    #[command(name = "unpause")]
+    Unpause(AdminCliArgs),
+
+    /// Set the msg value paid per minted NFT (`setMint721Cost`)
+    #[command(name = "set-mint-cost")]
+    SetMintCost(AdminSetMintCostCliArgs),
+
+    /// Set the max NFT token ID (`setMaxTokenId`)
+    #[command(name = "set-max-token-id")]
+    SetMaxTokenId(AdminSetMaxTokenIdCliArgs),
+
+    /// Set the treasury address (`setTreasuryAddress`)
+    #[command(name = "set-treasury")]
+    SetTreasury(AdminSetTreasuryCliArgs),
+
+    /// Set the base URI of the NFT metadata (`setBaseURI`)
+    #[command(name = "set-base-uri")]
+    SetBaseUri(AdminSetBaseUriCliArgs),
+
+    /// Send ETH held by the contract to a recipient (`recoverEth`)
+    #[command(name = "recover-eth")]
+    RecoverEth(AdminRecoverEthCliArgs),
+
+    /// Send ERC20 tokens held by the contract to a recipient (`recoverToken`)
+    #[command(name = "recover-token")]
+    RecoverToken(AdminRecoverTokenCliArgs),
+}
+
+/// Admin function call, with its arguments
+enum AdminCall {
+    Pause,
+    Unpause,
+    SetMintCost(U256),
+    SetMaxTokenId(U256),
+    SetTreasury(Address),
+    SetBaseUri(String),
+    /// Amount and recipient
+    RecoverEth(U256, Address),
+    /// Token, recipient and amount
+    RecoverToken(Address, Address, U256),
+}
+
+impl AdminCommand {
+    /// Execute the command
+    pub async fn execute(self) -> eyre::Result<()> {
+        let (args, call) = match self.command {
+            AdminSubcommand::Pause(args) => (args, AdminCall::Pause),
+            AdminSubcommand::Unpause(args) => (args, AdminCall::Unpause),
+            AdminSubcommand::SetMintCost(args) => (args.admin, AdminCall::SetMintCost(args.cost)),
+            AdminSubcommand::SetMaxTokenId(args) => (args.admin, AdminCall::SetMaxTokenId(args.cap)),
+            AdminSubcommand::SetTreasury(args) => (args.admin, AdminCall::SetTreasury(args.treasury.parse()?)),
+            AdminSubcommand::SetBaseUri(args) => (args.admin, AdminCall::SetBaseUri(args.uri)),
+            AdminSubcommand::RecoverEth(args) => {
+                (args.admin, AdminCall::RecoverEth(args.amount, args.recipient.parse()?))
+            },
+            AdminSubcommand::RecoverToken(args) => {
+                (args.admin, AdminCall::RecoverToken(args.token.parse()?, args.recipient.parse()?, args.amount))
+            },
+        };
+        run(args, call).await
+    }
+}
+
+/// Checks the sender holds the role the function is gated by and that the call would not revert
+/// for a known reason, then sends it once confirmed. The affected state is read back before and after.
+async fn run(args: AdminCliArgs, call: AdminCall) -> eyre::Result<()> {
+    println!("> Executing Admin {} command \n", call.function());
+
+    dotenv().ok();
+    let phrase = std::env::var("MNEMONIC")?;
+    let wallet = Wallet::from_phrase(&phrase, args.derivation, args.chain_id)?;
+
+    let prov = get_chain_provider(args.chain_id).await?;
+    let mut purse_token = Purse404Contract::new(
+        get_purse_address(args.chain_id)?,
+        &Arc::new(prov),
+    );
+    let journal = args.file_path.as_deref().map(Journal::for_log);
+    if let Some(journal) = &journal {
+        purse_token = purse_token.with_journal(journal.clone());
+    }
+
+    if let Some(role) = Role::gating(call.function()) {
+        purse_token.require_role(role, &wallet.address(), call.function()).await?;
+    }
+    call.preflight(&purse_token).await?;
+    println!("> Before: {}", call.read_back(&purse_token).await?);
+
+    let question = format!(
+        "> Send {} from {:?} (derivation {}) on chain {}?",
+        call.describe(),
+        wallet.address(),
+        args.derivation,
+        args.chain_id
+    );
+    if !args.yes && !confirm_prompt(&question)? {
+        println!("> Cancelled, nothing was sent");
+        return Ok(())
+    }
+
+    let (_, _, _, _, receipt_json) = call.send(&purse_token, &wallet).await?;
+    if let (Some(journal), Some(file_path)) = (&journal, &args.file_path) {
+        log_sent_transaction(
+            &purse_token,
+            journal,
+            args.derivation,
+            &receipt_json,
+            file_path,
+            args.allow_older_schema,
+            args.chain_id
+        ).await?;
+    }
+    println!("> After: {}", call.read_back(&purse_token).await?);
+
+    Ok(())
+}
+
+impl AdminCall {
+    /// Returns the name of the contract function
+    fn function(&self) -> &'static str {
+        match self {
+            AdminCall::Pause => "pause",
+            AdminCall::Unpause => "unpause",
+            AdminCall::SetMintCost(_) => "setMint721Cost",
+            AdminCall::SetMaxTokenId(_) => "setMaxTokenId",
+            AdminCall::SetTreasury(_) => "setTreasuryAddress",
+            AdminCall::SetBaseUri(_) => "setBaseURI",
+            AdminCall::RecoverEth(_, _) => "recoverEth",
+            AdminCall::RecoverToken(_, _, _) => "recoverToken",
+        }
+    }
+
+    /// Returns the function call with its arguments, eg: "setMint721Cost(1000)"
+    fn describe(&self) -> String {
+        let arguments = match self {
+            AdminCall::Pause | AdminCall::Unpause => String::new(),
+            AdminCall::SetMintCost(cost) => cost.to_string(),
+            AdminCall::SetMaxTokenId(cap) => cap.to_string(),
+            AdminCall::SetTreasury(treasury) => format!("{:?}", treasury),
+            AdminCall::SetBaseUri(uri) => format!("\"{}\"", uri),
+            AdminCall::RecoverEth(amount, recipient) => format!("{}, {:?}", amount, recipient),
+            AdminCall::RecoverToken(token, recipient, amount) => format!("{:?}, {:?}, {}", token, recipient, amount),
+        };
+        format!("{}({})", self.function(), arguments)
+    }
+
+    /// Refuses calls that would revert because of the current state of the contract
+    async fn preflight<M: Middleware + 'static>(&self, purse_token: &Purse404Contract<M>) -> eyre::Result<()> {
+        match self {
+            AdminCall::Pause if purse_token.paused().await? => {
+                Err(eyre::eyre!("> pause: the contract is already paused, the transaction would revert with EnforcedPause()"))
+            },
+            AdminCall::Unpause if !purse_token.paused().await? => {
+                Err(eyre::eyre!("> unpause: the contract is not paused, the transaction would revert with ExpectedPause()"))
+            },
+            AdminCall::RecoverEth(amount, _) => {
+                let balance = get_native_balance(purse_token.provider().as_ref(), &purse_token.address()).await?;
+                if *amount > balance {
+                    return Err(eyre::eyre!("> recoverEth: the contract only holds {} wei, cannot recover {} wei", balance, amount))
+                }
+                Ok(())
+            },
+            AdminCall::RecoverToken(token, _, amount) => {
+                let balance = purse_token.token_balance_of(token, &purse_token.address()).await?;
+                if *amount > balance {
+                    return Err(eyre::eyre!("> recoverToken: the contract only holds {} wei of {:?}, cannot recover {} wei", balance, token, amount))
+                }
+                Ok(())
+            },
+            _ => Ok(())
+        }
+    }
+
+    /// Reads the getter of the state the function changes
+    async fn read_back<M: Middleware + 'static>(&self, purse_token: &Purse404Contract<M>) -> eyre::Result<String> {
+        let state = match self {
+            AdminCall::Pause | AdminCall::Unpause => format!("paused: {}", purse_token.paused().await?),
+            AdminCall::SetMintCost(_) => format!("mintingCost (wei): {}", purse_token.minting_cost().await?),
+            AdminCall::SetMaxTokenId(_) => format!("erc721MaxTokenId: {}", purse_token.erc721_max_token_id().await?),
+            AdminCall::SetTreasury(_) => format!("treasury: {:?}", purse_token.treasury().await?),
+            AdminCall::SetBaseUri(_) => format!("baseTokenURI: {}", purse_token.base_token_uri().await?),
+            AdminCall::RecoverEth(_, recipient) => {
+                let prov = purse_token.provider();
+                format!(
+                    "contract ETH balance (wei): {}, recipient ETH balance (wei): {}",
+                    get_native_balance(prov.as_ref(), &purse_token.address()).await?,
+                    get_native_balance(prov.as_ref(), recipient).await?
+                )
+            },
+            AdminCall::RecoverToken(token, recipient, _) => format!(
+                "contract token balance (wei): {}, recipient token balance (wei): {}",
+                purse_token.token_balance_of(token, &purse_token.address()).await?,
+                purse_token.token_balance_of(token, recipient).await?
+            ),
+        };
+        Ok(state)
+    }
+
+    /// Sends the function call from the given wallet
+    async fn send<M: Middleware + 'static>(
+        &self,
+        purse_token: &Purse404Contract<M>,
+        wallet: &Wallet,
+    ) -> eyre::Result<(String, String, String, String, String)> {
+        match self {
+            AdminCall::Pause => purse_token.pause(wallet).await,
+            AdminCall::Unpause => purse_token.unpause(wallet).await,
+            AdminCall::SetMintCost(cost) => purse_token.set_mint721_cost(wallet, cost).await,
+            AdminCall::SetMaxTokenId(cap) => purse_token.set_max_token_id(wallet, cap).await,
+            AdminCall::SetTreasury(treasury) => purse_token.set_treasury_address(wallet, treasury).await,
+            AdminCall::SetBaseUri(uri) => purse_token.set_base_uri(wallet, uri).await,
+            AdminCall::RecoverEth(amount, recipient) => purse_token.recover_eth(wallet, amount, recipient).await,
+            AdminCall::RecoverToken(token, recipient, amount) => {
+                purse_token.recover_token(wallet, token, recipient, amount).await
+            },
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use dotenv::dotenv;
+use std::sync::Arc;
+use clap::Parser;
+use ethers::{
+    providers::Middleware,
+    types::U256,
+};
+use crate::{
+    cli::args::{AllowanceFlowCliArgs, ApproveFunction, SpendFunction},
+    journal::Journal,
+    utils::{
+        get_purse_address,
+        get_chain_provider,
+    },
+    wallet::Wallet,
+    contract::{
+        purse_contract::Purse404Contract,
+        purse_executor::{
+            Purse404Executor,
+            Purse404FunctionCall,
+            Purse404Results,
+        },
+    },
+};
+use super::{log_sent_transaction, parse_token_amount};
+
+#[derive(Debug, Parser)]
+pub struct AllowanceFlowCommand {
+    /// All Cli args
+    #[clap(flatten)]
+    cli_args: AllowanceFlowCliArgs,
+}
+
+impl AllowanceFlowCommand {
+    /// Execute the command.
+    /// The owner derivation approves the spender derivation for the amount, then the spender spends it
+    /// with `erc20TransferFrom`, `transferFrom` or `burnFrom`. Both transactions are logged, and the
+    /// allowance is checked after each of them. Amounts the hybrid `approve` or `transferFrom` would treat
+    /// as an NFT token ID are refused before anything is sent.
+    pub async fn execute(self) -> eyre::Result<()> {
+        println!("> Executing Allowance flow command \n");
+
+        dotenv().ok();
+        let args = self.cli_args;
+        let cid = args.chain_id;
+        let phrase = std::env::var("MNEMONIC")?;
+        let owner = Wallet::from_phrase(&phrase, args.owner_derivation, cid)?;
+        let spender = Wallet::from_phrase(&phrase, args.spender_derivation, cid)?;
+        let (owner_address, spender_address) = (owner.address(), spender.address());
+        let recipient = match &args.recipient {
+            Some(recipient) => recipient.parse()?,
+            None => spender_address,
+        };
+        println!("> Owner: {:?}, derivation: {}", owner_address, args.owner_derivation);
+        println!("> Spender: {:?}, derivation: {}", spender_address, args.spender_derivation);
+
+        let prov = get_chain_provider(cid).await?;
+        let journal = Journal::for_log(&args.file_path);
+        let purse_token = Purse404Contract::new(
+            get_purse_address(cid)?,
+            &Arc::new(prov),
+        ).with_journal(journal.clone());
+        let (symbol, decimals) = (purse_token.symbol().await?, purse_token.decimals().await?);
+        let amount = parse_token_amount(&args.amount, &symbol, decimals, "Amount")?;
+        // The hybrid approve and transferFrom treat amounts up to the max token ID as an NFT token ID
+        let hybrid = matches!(args.approve_with, ApproveFunction::Approve) || matches!(args.spend_with, SpendFunction::TransferFrom);
+        if hybrid {
+            let max_token_id = purse_token.erc721_max_token_id().await?;
+            if amount <= max_token_id {
+                return Err(eyre::eyre!(
+                    "> Amount {} is at most the max token ID {}, the hybrid approve and transferFrom would treat it as an NFT token ID. \
+                    Use --approve-with erc20-approve and --spend-with erc20-transfer-from",
+                    amount, max_token_id
+                ))
+            }
+        }
+
+        let allowance = purse_token.allowance(&owner_address, &spender_address, None).await?;
+        println!("> Allowance before: {} \n", allowance);
+
+        let approve = match args.approve_with {
+            ApproveFunction::Erc20Approve => Purse404FunctionCall::Erc20Approve(owner, spender_address, amount),
+            ApproveFunction::Approve => Purse404FunctionCall::Approve(owner, spender_address, amount),
+        };
+        let receipt_json = send(&purse_token, approve).await?;
+        log_sent_transaction(&purse_token, &journal, args.owner_derivation, &receipt_json, &args.file_path, args.allow_older_schema, cid).await?;
+
+        let allowance = purse_token.allowance(&owner_address, &spender_address, None).await?;
+        println!("> Allowance after approval: {} \n", allowance);
+        if allowance != amount {
+            return Err(eyre::eyre!("> Allowance after approval is {}, expected {}", allowance, amount))
+        }
+
+        let spend = match args.spend_with {
+            SpendFunction::Erc20TransferFrom => Purse404FunctionCall::Erc20TransferFrom(spender, owner_address, recipient, amount),
+            SpendFunction::TransferFrom => Purse404FunctionCall::TransferFrom(spender, owner_address, recipient, amount),
+            SpendFunction::BurnFrom => Purse404FunctionCall::BurnFrom(spender, owner_address, amount),
+        };
+        let receipt_json = send(&purse_token, spend).await?;
+        log_sent_transaction(&purse_token, &journal, args.spender_derivation, &receipt_json, &args.file_path, args.allow_older_schema, cid).await?;
+
+        // An unlimited allowance is not spent down
+        let expected = if amount == U256::MAX { U256::MAX } else { U256::zero() };
+        let allowance = purse_token.allowance(&owner_address, &spender_address, None).await?;
+        println!("> Allowance after spending: {}", allowance);
+        if allowance != expected {
+            return Err(eyre::eyre!("> Allowance after spending is {}, expected {}", allowance, expected))
+        }
+
+        Ok(())
+    }
+}
+
+/// Sends a state changing call
+/// ### Returns
+/// * `Result<String>` - The transaction receipt JSON
+async fn send<M: Middleware + 'static>(
+    purse_token: &Purse404Contract<M>,
+    call: Purse404FunctionCall,
+) -> eyre::Result<String> {
+    match Purse404Executor::execute_fn(purse_token, call).await? {
+        Purse404Results::StateChangeResult((_, _, _, _, receipt_json)) => Ok(receipt_json),
+        _ => Err(eyre::eyre!("> Expected a state changing call")),
+    }
+}
@@ -0,0 +1,156 @@
+use dotenv::dotenv;
+use clap::{Parser, Subcommand};
+use serde_json::json;
+use ethers::{
+    providers::Middleware,
+    types::{Bytes, H256},
+};
+use crate::{
+    cli::args::{CalldataDecodeCliArgs, CalldataEncodeCliArgs, OutputFormat},
+    utils::{
+        get_purse_address,
+        get_chain_provider,
+        format_table,
+        token_to_string,
+    },
+    contract::{
+        purse_calldata::{decode_calldata, encode_calldata, find_function, function_signature, load_abi},
+        purse_executor::validate_purse_calldata,
+    },
+};
+
+#[derive(Debug, Parser)]
+pub struct CalldataCommand {
+    /// Calldata subcommand
+    #[clap(subcommand)]
+    command: CalldataSubcommand,
+}
+
+/// Calldata subcommands, against the Purse404 ABI or the given ABI. No transaction is sent.
+#[derive(Debug, Subcommand)]
+pub enum CalldataSubcommand {
+    /// Encode calldata for a function call, eg: for a multisig transaction
+    #[command(name = "encode")]
+    Encode(CalldataEncodeCliArgs),
+
+    /// Decode calldata, or the input of a sent transaction
+    #[command(name = "decode")]
+    Decode(CalldataDecodeCliArgs),
+}
+
+impl CalldataCommand {
+    /// Execute the command
+    pub async fn execute(self) -> eyre::Result<()> {
+        match self.command {
+            CalldataSubcommand::Encode(args) => encode(args),
+            CalldataSubcommand::Decode(args) => decode(args).await,
+        }
+    }
+}
+
+/// Encode the calldata of the function call
+fn encode(args: CalldataEncodeCliArgs) -> eyre::Result<()> {
+    let abi = load_abi(args.abi.as_deref())?;
+    let function = find_function(&abi, &args.function, args.args.len())?;
+    let data = encode_calldata(&function, &args.args)?;
+
+    println!("> Function: {}", function_signature(&function));
+    println!("> Selector: 0x{}", ethers::utils::hex::encode(function.short_signature()));
+    println!("> Calldata: {}", data);
+
+    Ok(())
+}
+
+/// Decode the given calldata, or the input of the given transaction.
+/// Arguments are printed the way they are passed on the command line, and for Purse404 functions
+/// supported by the `purse` command, the command repeating the call is printed.
+async fn decode(args: CalldataDecodeCliArgs) -> eyre::Result<()> {
+    let abi = load_abi(args.abi.as_deref())?;
+    let mut transaction = None;
+    let data: Bytes = match (&args.data, &args.tx_hash, args.chain_id) {
+        (Some(data), _, _) => data.parse().map_err(|e| eyre::eyre!("Invalid calldata {}: {}", data, e))?,
+        (None, Some(tx_hash), Some(cid)) => {
+            dotenv().ok();
+            let prov = get_chain_provider(cid).await?;
+            let tx = prov
+                .get_transaction(tx_hash.parse::<H256>()?)
+                .await
+                .map_err(|e| eyre::eyre!("{}", e))?
+                .ok_or_else(|| eyre::eyre!("Transaction {} not found on chain {}", tx_hash, cid))?;
+            if args.abi.is_none() && tx.to != Some(get_purse_address(cid)?) {
+                eprintln!("> Transaction {} is not sent to the Purse404 contract, pass --abi to decode with its ABI", tx_hash);
+            }
+            let input = tx.input.clone();
+            transaction = Some(tx);
+            input
+        },
+        _ => return Err(eyre::eyre!("> Either calldata, or --tx-hash with --chain-id is required")),
+    };
+
+    let (function, tokens) = decode_calldata(&abi, &data)?;
+    let values: Vec<String> = tokens.iter().map(token_to_string).collect();
+    // View functions are validated without calldata, see `validate_purse_calldata`
+    let calldata = match values.is_empty() {
+        true => None,
+        false => Some(values.clone()),
+    };
+    let purse_command = match (&args.abi, &calldata) {
+        (None, _) if validate_purse_calldata(&function.name, &calldata).is_err() => None,
+        (None, None) => Some(format!("purse --function {}", function.name)),
+        (None, Some(calldata)) => Some(format!("purse --function {} --calldata {}", function.name, calldata.join(" "))),
+        (Some(_), _) => None,
+    };
+
+    match args.format {
+        OutputFormat::Json => {
+            let arguments: Vec<serde_json::Value> = function
+                .inputs
+                .iter()
+                .zip(values.iter())
+                .map(|(param, value)| json!({
+                    "name": param.name,
+                    "type": param.kind.to_string(),
+                    "value": value,
+                }))
+                .collect();
+            let decoded = json!({
+                "tx_hash": transaction.as_ref().map(|tx| tx.hash),
+                "from": transaction.as_ref().map(|tx| tx.from),
+                "to": transaction.as_ref().and_then(|tx| tx.to),
+                "value": transaction.as_ref().map(|tx| tx.value.to_string()),
+                "function": function.name,
+                "signature": function_signature(&function),
+                "selector": format!("0x{}", ethers::utils::hex::encode(function.short_signature())),
+                "arguments": arguments,
+                "purse_command": purse_command,
+            });
+            println!("{}", serde_json::to_string_pretty(&decoded)?);
+        },
+        OutputFormat::Table => {
+            if let Some(tx) = &transaction {
+                println!("> Transaction: {:?}, block: {:?}", tx.hash, tx.block_number);
+                println!("> From: {:?}, to: {:?}, msg value (wei): {}", tx.from, tx.to, tx.value);
+            }
+            println!("> Function: {}", function_signature(&function));
+            println!("> Selector: 0x{} \n", ethers::utils::hex::encode(function.short_signature()));
+            let rows: Vec<Vec<String>> = function
+                .inputs
+                .iter()
+                .zip(values.iter())
+                .enumerate()
+                .map(|(i, (param, value))| vec![
+                    i.to_string(),
+                    param.name.clone(),
+                    param.kind.to_string(),
+                    value.clone(),
+                ])
+                .collect();
+            println!("{}", format_table(&["#", "Name", "Type", "Value"], &rows));
+            if let Some(command) = &purse_command {
+                println!("> Purse command: {}", command);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use dotenv::dotenv;
+use std::sync::Arc;
+use clap::Parser;
+use ethers::{
+    abi::{encode, AbiEncode, Token},
+    prelude::SignerMiddleware,
+    providers::Middleware,
+    signers::LocalWallet,
+    types::{Address, Bytes, TransactionReceipt, TransactionRequest, H256},
+};
+use crate::{
+    cli::args::DeployCliArgs,
+    deployments::{
+        read_artifact_bytecode,
+        registry_path,
+        upsert_env_var,
+        Deployment,
+        DeploymentRegistry,
+    },
+    utils::{
+        get_chain_provider,
+        purse_address_var,
+        unix_timestamp,
+    },
+    wallet::Wallet,
+    contract::{
+        purse_contract::{InitializeCall, Purse404Contract},
+        purse_roles::Role,
+    },
+};
+
+#[derive(Debug, Parser)]
+pub struct DeployCommand {
+    /// All Cli args
+    #[clap(flatten)]
+    cli_args: DeployCliArgs,
+}
+
+impl DeployCommand {
+    /// Execute the command.
+    /// Deploys the Purse404 implementation, then an ERC1967 proxy to it whose constructor calls
+    /// `initialize(defaultAdmin, pauser, minter, upgrader)`, then calls `init404(unit404Decimals)` from the admin.
+    /// The deployment is written to the deployment registry once the proxy is checked, and updated with the
+    /// `init404` transaction once it is mined. The proxy address is written to the dotenv file,
+    /// so the other commands use it on this chain (see `get_purse_address`).
+    pub async fn execute(self) -> eyre::Result<()> {
+        println!("> Executing Deploy command \n");
+
+        dotenv().ok();
+        let args = self.cli_args;
+        let cid = args.chain_id;
+        let phrase = std::env::var("MNEMONIC")?;
+        let deployer = Wallet::from_phrase(&phrase, args.derivation, cid)?;
+        let derived = |derivation: Option<u32>| -> eyre::Result<Wallet> {
+            match derivation {
+                Some(derivation) => Wallet::from_phrase(&phrase, derivation, cid),
+                None => Ok(deployer.clone()),
+            }
+        };
+        let admin = derived(args.admin_derivation)?;
+        let initialize = InitializeCall {
+            default_admin: admin.address(),
+            pauser: derived(args.pauser_derivation)?.address(),
+            minter: derived(args.minter_derivation)?.address(),
+            upgrader: derived(args.upgrader_derivation)?.address(),
+        };
+        let implementation_bytecode = read_artifact_bytecode(&args.artifact)?;
+        let proxy_bytecode = read_artifact_bytecode(&args.proxy_artifact)?;
+        println!("> Deployer: {:?}, derivation: {}", deployer.address(), args.derivation);
+
+        let prov = get_chain_provider(cid).await?;
+        let client = SignerMiddleware::new(prov.clone(), deployer.signer.clone());
+
+        let (implementation, implementation_receipt) = deploy_bytecode(&client, implementation_bytecode).await?;
+        println!("> Implementation deployed at: {:?}, tx: {:?}", implementation, implementation_receipt.transaction_hash);
+
+        // ERC1967Proxy(address implementation, bytes data), `data` is delegate called on construction
+        let constructor_args = encode(&[
+            Token::Address(implementation),
+            Token::Bytes(initialize.clone().encode()),
+        ]);
+        let proxy_creation: Bytes = [proxy_bytecode.to_vec(), constructor_args].concat().into();
+        let (proxy, proxy_receipt) = deploy_bytecode(&client, proxy_creation).await?;
+        println!("> Proxy deployed and initialized at: {:?}, tx: {:?}", proxy, proxy_receipt.transaction_hash);
+
+        let purse_token = Purse404Contract::new(proxy, &Arc::new(prov));
+        let behind_proxy = purse_token.implementation().await?;
+        if behind_proxy != implementation {
+            return Err(eyre::eyre!("> Proxy {:?} points to {:?}, expected {:?}", proxy, behind_proxy, implementation))
+        }
+        let roles = [
+            (Role::DefaultAdmin, initialize.default_admin),
+            (Role::Pauser, initialize.pauser),
+            (Role::Minter, initialize.minter),
+            (Role::Upgrader, initialize.upgrader),
+        ];
+        for (role, account) in roles {
+            if !purse_token.has_role(role, &account).await? {
+                return Err(eyre::eyre!("> {:?} does not hold {} after initialize", account, role))
+            }
+            println!("> {}: {:?}", role, account);
+        }
+
+        // Recorded before init404, so the deployment is not lost if it fails
+        let mut registry = DeploymentRegistry::load(&registry_path())?;
+        let mut deployment = Deployment {
+            proxy,
+            implementation,
+            deployer: deployer.address(),
+            block_number: proxy_receipt.block_number.map(|n| n.as_u64()),
+            deployed_at: unix_timestamp(),
+            implementation_tx: implementation_receipt.transaction_hash,
+            proxy_tx: proxy_receipt.transaction_hash,
+            init404_tx: None,
+        };
+        registry.insert(u64::from(cid), deployment.clone());
+        registry.save(&registry_path())?;
+        println!("> Deployment recorded in {}", registry_path());
+
+        purse_token.require_role(Role::DefaultAdmin, &admin.address(), "init404").await?;
+        let (init404_hash, _, _, _, _) = purse_token.init404(&admin, &args.unit404_decimals).await?;
+        deployment.init404_tx = Some(init404_hash.parse::<H256>()?);
+        registry.insert(u64::from(cid), deployment);
+        registry.save(&registry_path())?;
+        println!("> init404 recorded in {}", registry_path());
+
+        if !args.no_env {
+            let var = purse_address_var(cid)?;
+            upsert_env_var(&args.env_path, &var, &format!("{:?}", proxy))?;
+            println!("> {}={:?} written to {}", var, proxy, args.env_path);
+        }
+
+        Ok(())
+    }
+}
+
+/// Sends a contract creation transaction and waits for its receipt
+/// ### Returns
+/// * `Result<(Address, TransactionReceipt)>` - The deployed contract address, and the receipt
+async fn deploy_bytecode<M: Middleware + 'static>(
+    client: &SignerMiddleware<M, LocalWallet>,
+    bytecode: Bytes,
+) -> eyre::Result<(Address, TransactionReceipt)> {
+    let tx = TransactionRequest::new().from(client.address()).data(bytecode);
+    let receipt = client
+        .send_transaction(tx, None)
+        .await
+        .map_err(|e| eyre::eyre!("Failed to send transaction: {}", e))?
+        .await?
+        .ok_or_else(|| eyre::eyre!("Deployment transaction was dropped from the mempool"))?;
+    if receipt.status.map(|status| status.is_zero()).unwrap_or(false) {
+        return Err(eyre::eyre!("Deployment transaction {:?} reverted", receipt.transaction_hash))
+    }
+    let address = receipt
+        .contract_address
+        .ok_or_else(|| eyre::eyre!("Receipt of {:?} has no contract address", receipt.transaction_hash))?;
+    Ok((address, receipt))
+}
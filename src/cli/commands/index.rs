@@ -0,0 +1,167 @@
+use dotenv::dotenv;
+use clap::{Parser, Subcommand};
+use serde_json::json;
+use crate::{
+    cli::args::{
+        IndexHeldCliArgs,
+        IndexHoldersCliArgs,
+        IndexSyncCliArgs,
+        OutputFormat,
+    },
+    indexer::{confirmed_block, sync_index, EventIndex},
+    utils::{
+        get_purse_address,
+        get_chain_provider,
+        format_table,
+        str_wei_to_eth,
+    },
+    wallet::Wallet,
+};
+
+#[derive(Debug, Parser)]
+pub struct IndexCommand {
+    /// Event index subcommand
+    #[clap(subcommand)]
+    command: IndexSubcommand,
+}
+
+/// Event index subcommands
+#[derive(Debug, Subcommand)]
+pub enum IndexSubcommand {
+    /// Index the Transfer and Approval events of the Purse404 contract, resuming from the last indexed block
+    #[command(name = "sync")]
+    Sync(IndexSyncCliArgs),
+    /// List every NFT ID ever held by an account
+    #[command(name = "held")]
+    Held(IndexHeldCliArgs),
+    /// List the holders at a block
+    #[command(name = "holders")]
+    Holders(IndexHoldersCliArgs),
+}
+
+impl IndexCommand {
+    /// Execute the command
+    pub async fn execute(self) -> eyre::Result<()> {
+        match self.command {
+            IndexSubcommand::Sync(args) => sync(args).await,
+            IndexSubcommand::Held(args) => held(args),
+            IndexSubcommand::Holders(args) => holders(args),
+        }
+    }
+}
+
+/// Index the events up to the given (or latest confirmed) block
+async fn sync(args: IndexSyncCliArgs) -> eyre::Result<()> {
+    println!("> Executing Index sync command \n");
+
+    dotenv().ok();
+    let prov = get_chain_provider(args.chain_id).await?;
+    let contract = get_purse_address(args.chain_id)?;
+    let mut index = EventIndex::open(&args.db_path, args.chain_id, contract)?;
+
+    let from_block = match (args.from_block, index.last_indexed_block()?) {
+        (Some(block), _) => block,
+        (None, Some(last)) => last + 1,
+        (None, None) => return Err(eyre::eyre!(
+            "> Index: {} is empty, pass --from-block with the deployment block of the contract",
+            args.db_path
+        )),
+    };
+    let confirmed = confirmed_block(&prov, args.confirmations).await?;
+    let to_block = match args.to_block {
+        Some(block) if block > confirmed => {
+            println!("> Index: block {} has fewer than {} confirmations, indexing up to block {}", block, args.confirmations, confirmed);
+            confirmed
+        },
+        Some(block) => block,
+        None => confirmed,
+    };
+    if from_block > to_block {
+        println!("> Index: {} is up to date at block {}", args.db_path, to_block);
+        return Ok(())
+    }
+
+    let added = sync_index(&prov, &mut index, contract, from_block, to_block, args.page_size).await?;
+    println!("\n> Indexed blocks {} to {}, {} new events, written to: {}", from_block, to_block, added, args.db_path);
+
+    Ok(())
+}
+
+/// List every NFT ID ever held by the given derivation number or address
+fn held(args: IndexHeldCliArgs) -> eyre::Result<()> {
+    let address = match (args.derivation, args.address) {
+        (Some(derivation), _) => {
+            dotenv().ok();
+            let phrase = std::env::var("MNEMONIC")?;
+            Wallet::from_phrase(&phrase, derivation, args.chain_id)?.address()
+        },
+        (None, Some(address)) => address.parse()?,
+        (None, None) => return Err(eyre::eyre!("> Either --derivation or --address is required")),
+    };
+    let index = EventIndex::open(&args.db_path, args.chain_id, get_purse_address(args.chain_id)?)?;
+    let ids = index.nft_ids_ever_held(&address)?;
+
+    match args.format {
+        OutputFormat::Json => {
+            let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+            println!("{}", serde_json::to_string_pretty(&json!({
+                "address": format!("{:?}", address),
+                "last_indexed_block": index.last_indexed_block()?,
+                "token_ids": ids,
+            }))?);
+        },
+        OutputFormat::Table => {
+            println!("> NFT IDs ever held by {:?}: {}", address, ids.len());
+            for id in ids {
+                println!("{}", id);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// List the holders at the given (or last indexed) block
+fn holders(args: IndexHoldersCliArgs) -> eyre::Result<()> {
+    let index = EventIndex::open(&args.db_path, args.chain_id, get_purse_address(args.chain_id)?)?;
+    let last_indexed = index
+        .last_indexed_block()?
+        .ok_or_else(|| eyre::eyre!("> Index: {} is empty, run `index sync` first", args.db_path))?;
+    let block = args.block.unwrap_or(last_indexed);
+    if block > last_indexed {
+        return Err(eyre::eyre!("> Block {} is past the last indexed block {}", block, last_indexed))
+    }
+    let holders = index.holders_at(block)?;
+
+    match args.format {
+        OutputFormat::Json => {
+            let holders: Vec<serde_json::Value> = holders
+                .iter()
+                .map(|holder| json!({
+                    "address": format!("{:?}", holder.address),
+                    "balance": holder.balance.to_string(),
+                    "token_ids": holder.token_ids.iter().map(|id| id.to_string()).collect::<Vec<String>>(),
+                }))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&json!({
+                "block": block,
+                "holders": holders,
+            }))?);
+        },
+        OutputFormat::Table => {
+            let rows: Vec<Vec<String>> = holders
+                .iter()
+                .map(|holder| vec![
+                    format!("{:?}", holder.address),
+                    str_wei_to_eth(&holder.balance.to_string()),
+                    holder.token_ids.len().to_string(),
+                    holder.token_ids.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(","),
+                ])
+                .collect();
+            println!("> Holders at block {}: {} \n", block, holders.len());
+            println!("{}", format_table(&["Address", "Balance (ERC20)", "NFTs", "Token IDs"], &rows));
+        }
+    }
+
+    Ok(())
+}
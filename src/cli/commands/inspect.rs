@@ -0,0 +1,141 @@
+use dotenv::dotenv;
+use std::sync::Arc;
+use clap::Parser;
+use ethers::types::U256;
+use serde_json::json;
+use crate::{
+    cli::args::{InspectCliArgs, OutputFormat},
+    utils::{
+        get_purse_address,
+        get_chain_provider,
+        str_wei_to_eth,
+    },
+    wallet::Wallet,
+    contract::purse_contract::Purse404Contract,
+};
+
+#[derive(Debug, Parser)]
+pub struct InspectCommand {
+    /// All Cli args
+    #[clap(flatten)]
+    cli_args: InspectCliArgs,
+}
+
+impl InspectCommand {
+    /// Execute the command.
+    /// Shows the hybrid ERC20/ERC721 state of an account, if one is given, and the global state
+    /// of the Purse404 contract, including a page of the queue of burned NFT token IDs awaiting reuse.
+    pub async fn execute(self) -> eyre::Result<()> {
+        let args = self.cli_args;
+        if args.format == OutputFormat::Table {
+            println!("> Executing Inspect command \n");
+        }
+
+        dotenv().ok();
+        let address = match (args.derivation, &args.address) {
+            (Some(derivation), _) => {
+                let phrase = std::env::var("MNEMONIC")?;
+                Some(Wallet::from_phrase(&phrase, derivation, args.chain_id)?.address())
+            },
+            (None, Some(address)) => Some(address.parse()?),
+            (None, None) => None,
+        };
+
+        let prov = get_chain_provider(args.chain_id).await?;
+        let purse_token = Purse404Contract::new(
+            get_purse_address(args.chain_id)?,
+            &Arc::new(prov),
+        );
+
+        let units = purse_token.units().await?;
+        let minted = purse_token.minted().await?;
+        let minting_cost = purse_token.minting_cost().await?;
+        let total_supply = purse_token.erc721_total_supply().await?;
+        let max_token_id = purse_token.erc721_max_token_id().await?;
+        let queue_length = purse_token.erc721_queue_length().await?;
+        // The contract reverts on pages past the end of the queue
+        let queue_start = U256::from(args.queue_start).min(queue_length);
+        let queue_count = U256::from(args.queue_count).min(queue_length - queue_start);
+        let queue = if queue_count.is_zero() {
+            Vec::new()
+        } else {
+            purse_token.erc721_tokens_in_queue(queue_start, queue_count).await?
+        };
+
+        let account = match address {
+            Some(address) => purse_token
+                .balances_at(&[address], None, true)
+                .await?
+                .0
+                .pop(),
+            None => None,
+        };
+        let per_unit = |amount: U256| if units.is_zero() { U256::zero() } else { amount / units };
+        let ids = |ids: &[U256]| ids.iter().map(|id| id.to_string()).collect::<Vec<String>>();
+
+        match args.format {
+            OutputFormat::Json => {
+                let account = account.as_ref().map(|account| json!({
+                    "address": format!("{:?}", account.address),
+                    "eth": str_wei_to_eth(&account.eth.to_string()),
+                    "erc20": str_wei_to_eth(&account.erc20.to_string()),
+                    "active": str_wei_to_eth(&account.active.to_string()),
+                    "inactive": str_wei_to_eth(&account.inactive.to_string()),
+                    "nfts": account.erc721.to_string(),
+                    "token_ids": ids(account.owned.as_deref().unwrap_or_default()),
+                    "implied_nfts": per_unit(account.erc20).to_string(),
+                    "nfts_mintable_from_inactive": per_unit(account.inactive).to_string(),
+                }));
+                println!("{}", serde_json::to_string_pretty(&json!({
+                    "account": account,
+                    "units": str_wei_to_eth(&units.to_string()),
+                    "minted": minted.to_string(),
+                    "minting_cost": minting_cost.to_string(),
+                    "erc721_total_supply": total_supply.to_string(),
+                    "erc721_max_token_id": max_token_id.to_string(),
+                    "queue_length": queue_length.to_string(),
+                    "queue_start": queue_start.to_string(),
+                    "queue": ids(&queue),
+                }))?);
+            },
+            OutputFormat::Table => {
+                if let Some(account) = &account {
+                    let owned = account.owned.as_deref().unwrap_or_default();
+                    println!("> Account: {:?}", account.address);
+                    println!("> ETH balance: {}", str_wei_to_eth(&account.eth.to_string()));
+                    println!("> ERC20 balance: {}", str_wei_to_eth(&account.erc20.to_string()));
+                    println!("> - Active (backing NFTs): {}", str_wei_to_eth(&account.active.to_string()));
+                    println!("> - Inactive: {}", str_wei_to_eth(&account.inactive.to_string()));
+                    println!("> NFTs owned: {}", account.erc721);
+                    println!("> Token IDs: {}", ids(owned).join(","));
+                    println!("> Implied NFT units (balance / units): {}", per_unit(account.erc20));
+                    println!(
+                        "> NFTs mintable from the inactive balance: {}, mintERC721 of more reverts with InsufficientInactiveBalance()",
+                        per_unit(account.inactive)
+                    );
+                    if account.active != account.erc721 * units {
+                        println!(
+                            "> Note: active balance does not match NFTs owned * units ({})",
+                            str_wei_to_eth(&(account.erc721 * units).to_string())
+                        );
+                    }
+                    println!();
+                }
+                println!("> Units (ERC20 per NFT): {}", str_wei_to_eth(&units.to_string()));
+                println!("> Minted: {}", minted);
+                println!("> Minting cost (wei): {}", minting_cost);
+                println!("> ERC721 total supply: {}", total_supply);
+                println!("> ERC721 max token ID: {}", max_token_id);
+                println!("> ERC721 queue length: {}", queue_length);
+                println!(
+                    "> ERC721 queue, positions {} to {}: {}",
+                    queue_start,
+                    queue_start + queue_count,
+                    ids(&queue).join(",")
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
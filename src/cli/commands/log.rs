@@ -0,0 +1,61 @@
+use clap::{Parser, Subcommand};
+use crate::{
+    cli::args::LogMigrateCliArgs,
+    file::{
+        migrate_csv,
+        read_schema_version,
+        SchemaVersion,
+    },
+};
+
+#[derive(Debug, Parser)]
+pub struct LogCommand {
+    /// Log maintenance subcommand
+    #[clap(subcommand)]
+    command: LogSubcommand,
+}
+
+/// Log maintenance subcommands
+#[derive(Debug, Subcommand)]
+pub enum LogSubcommand {
+    /// Rewrite a csv log into the current log schema
+    #[command(name = "migrate")]
+    Migrate(LogMigrateCliArgs),
+}
+
+impl LogCommand {
+    /// Execute the command
+    pub async fn execute(self) -> eyre::Result<()> {
+        match self.command {
+            LogSubcommand::Migrate(args) => migrate(args),
+        }
+    }
+}
+
+/// Migrate the given csv log into the current log schema
+fn migrate(args: LogMigrateCliArgs) -> eyre::Result<()> {
+    println!("> Executing Log migrate command \n");
+
+    let file_path = args.file_path;
+    let output_path = args.output_path;
+
+    match read_schema_version(&file_path)? {
+        None => return Err(eyre::eyre!("> File: {} not found. Halting...", file_path)),
+        Some(version) if version == SchemaVersion::CURRENT && output_path.is_none() => {
+            println!("> File: {} already uses the current log schema {}", file_path, version);
+            return Ok(())
+        },
+        Some(_) => {}
+    }
+
+    let (from_version, rows) = migrate_csv(&file_path, output_path.as_deref())?;
+    println!(
+        "> Migrated {} rows from log schema {} to {}, written to: {}",
+        rows,
+        from_version,
+        SchemaVersion::CURRENT,
+        output_path.as_deref().unwrap_or(&file_path)
+    );
+
+    Ok(())
+}
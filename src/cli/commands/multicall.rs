@@ -0,0 +1,67 @@
+use dotenv::dotenv;
+use clap::{Parser, Subcommand};
+use ethers::{
+    prelude::SignerMiddleware,
+    providers::Middleware,
+    types::{Bytes, TransactionRequest},
+};
+use crate::{
+    cli::args::MulticallDeployCliArgs,
+    utils::get_chain_provider,
+    wallet::Wallet,
+};
+
+#[derive(Debug, Parser)]
+pub struct MulticallCommand {
+    /// Multicall subcommand
+    #[clap(subcommand)]
+    command: MulticallSubcommand,
+}
+
+/// Multicall subcommands
+#[derive(Debug, Subcommand)]
+pub enum MulticallSubcommand {
+    /// Deploy a Multicall3 contract, eg: on a local devnet, for batched reads
+    #[command(name = "deploy")]
+    Deploy(MulticallDeployCliArgs),
+}
+
+impl MulticallCommand {
+    /// Execute the command
+    pub async fn execute(self) -> eyre::Result<()> {
+        match self.command {
+            MulticallSubcommand::Deploy(args) => deploy(args).await,
+        }
+    }
+}
+
+/// Deploy Multicall3 from its creation bytecode, and print the address to set as `MULTICALL_ADDRESS`
+async fn deploy(args: MulticallDeployCliArgs) -> eyre::Result<()> {
+    println!("> Executing Multicall deploy command \n");
+
+    dotenv().ok();
+    let phrase = std::env::var("MNEMONIC")?;
+    let wallet = Wallet::from_phrase(&phrase, args.derivation, args.chain_id)?;
+    let bytecode: Bytes = std::fs::read_to_string(&args.bytecode_path)?
+        .trim()
+        .parse()
+        .map_err(|e| eyre::eyre!("Invalid bytecode in {}: {}", args.bytecode_path, e))?;
+
+    let prov = get_chain_provider(args.chain_id).await?;
+    let client = SignerMiddleware::new(prov, wallet.signer.clone());
+    let tx = TransactionRequest::new().from(wallet.address()).data(bytecode);
+    let receipt = client
+        .send_transaction(tx, None)
+        .await
+        .map_err(|e| eyre::eyre!("Failed to send transaction: {}", e))?
+        .await?
+        .ok_or_else(|| eyre::eyre!("Deployment transaction was dropped from the mempool"))?;
+    let address = receipt
+        .contract_address
+        .ok_or_else(|| eyre::eyre!("Receipt of {:?} has no contract address", receipt.transaction_hash))?;
+
+    println!("> Multicall3 deployed at: {:?}, tx: {:?}", address, receipt.transaction_hash);
+    println!("> Set MULTICALL_ADDRESS={:?} to batch reads through it", address);
+
+    Ok(())
+}
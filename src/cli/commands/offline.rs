@@ -0,0 +1,190 @@
+use dotenv::dotenv;
+use std::sync::Arc;
+use clap::{Parser, Subcommand};
+use ethers::providers::Middleware;
+use crate::{
+    cli::args::{OfflineBroadcastCliArgs, OfflineSignCliArgs},
+    journal::Journal,
+    utils::{
+        get_purse_address,
+        get_chain_provider,
+        get_native_balance,
+        format_amount,
+    },
+    wallet::Wallet,
+    contract::{
+        purse_contract::Purse404Contract,
+        purse_executor::{validate_purse_calldata, Purse404FunctionCall},
+        purse_offline::{sign_offline, OfflineTransaction, TxFees},
+    },
+};
+use super::{log_sent_transaction, normalize_calldata_amount};
+
+#[derive(Debug, Parser)]
+pub struct OfflineCommand {
+    /// Offline subcommand
+    #[clap(subcommand)]
+    command: OfflineSubcommand,
+}
+
+/// Offline subcommands, to sign on a machine without network access and broadcast from another
+#[derive(Debug, Subcommand)]
+pub enum OfflineSubcommand {
+    /// Sign a function call transaction with explicit nonce, gas and fees, no provider is used
+    #[command(name = "sign")]
+    Sign(Box<OfflineSignCliArgs>),
+
+    /// Broadcast a transaction signed with `offline sign`
+    #[command(name = "broadcast")]
+    Broadcast(OfflineBroadcastCliArgs),
+}
+
+impl OfflineCommand {
+    /// Execute the command
+    pub async fn execute(self) -> eyre::Result<()> {
+        match self.command {
+            OfflineSubcommand::Sign(args) => sign(*args).await,
+            OfflineSubcommand::Broadcast(args) => broadcast(args).await,
+        }
+    }
+}
+
+/// Sign the function call with the given derivation. The contract address is read from the environment
+/// or the deployment registry (see `get_purse_address`), so nothing is read from the network.
+/// The signed transaction is written as JSON to the output file, or printed.
+async fn sign(args: OfflineSignCliArgs) -> eyre::Result<()> {
+    println!("> Executing Offline sign command \n");
+
+    dotenv().ok();
+    let phrase = std::env::var("MNEMONIC")?;
+    let wallet = Wallet::from_phrase(&phrase, args.derivation, args.chain_id)?;
+
+    validate_purse_calldata(&args.function, &args.calldata)?;
+    let mut calldata = args.calldata.unwrap_or_default();
+    normalize_calldata_amount(&args.function, &mut calldata, &args.symbol, args.decimals)?;
+    if let Some(msg_value) = args.msg_value {
+        println!("> Msg value: {} wei ({} ETH)", msg_value, format_amount(msg_value, 18));
+    }
+    let call = Purse404FunctionCall::from_data(
+        &args.function,
+        args.msg_value,
+        calldata,
+        wallet
+    )?;
+    let fees = match (args.max_fee_per_gas, args.max_priority_fee_per_gas, args.gas_price) {
+        (Some(max_fee_per_gas), Some(max_priority_fee_per_gas), _) => {
+            if max_priority_fee_per_gas > max_fee_per_gas {
+                return Err(eyre::eyre!("> The max priority fee per gas cannot be more than the max fee per gas"))
+            }
+            TxFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas }
+        },
+        (None, None, Some(gas_price)) => TxFees::Legacy { gas_price },
+        _ => return Err(eyre::eyre!("> Either --max-fee-per-gas with --max-priority-fee-per-gas, or --gas-price is required")),
+    };
+    let max_fee = fees.max_cost(args.gas_limit)?;
+
+    let offline = sign_offline(
+        get_purse_address(args.chain_id)?,
+        &call,
+        args.nonce,
+        args.gas_limit,
+        fees
+    ).await?;
+    println!(
+        "> Signed {}({}), from: {:?}, derivation: {}, nonce: {}, chain: {}",
+        offline.function,
+        offline.calldata.join(", "),
+        offline.from,
+        offline.derivation,
+        offline.nonce,
+        offline.chain_id
+    );
+    println!("> Msg value (wei): {}, max fee (wei): {}", offline.value, max_fee);
+    println!("> Transaction hash: {:?}", offline.hash);
+
+    let json_str = serde_json::to_string_pretty(&offline)?;
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, json_str)?;
+            println!("> Signed transaction written to {}", path);
+        },
+        None => println!("{}", json_str),
+    }
+
+    Ok(())
+}
+
+/// Broadcast a transaction signed with `offline sign`, optionally appending it to the csv log.
+/// The raw transaction is checked against its metadata, and the nonce and balance of the signer
+/// against the chain, before it is sent.
+async fn broadcast(args: OfflineBroadcastCliArgs) -> eyre::Result<()> {
+    println!("> Executing Offline broadcast command \n");
+
+    dotenv().ok();
+    let offline: OfflineTransaction = serde_json::from_str(&std::fs::read_to_string(&args.tx_path)?)?;
+    offline.verify()?;
+    if offline.chain_id != u64::from(args.chain_id) {
+        return Err(eyre::eyre!("> Transaction was signed for chain {}, not {}", offline.chain_id, args.chain_id))
+    }
+    let purse_address = get_purse_address(args.chain_id)?;
+    if offline.contract != purse_address {
+        return Err(eyre::eyre!("> Transaction was signed for contract {:?}, not {:?}", offline.contract, purse_address))
+    }
+    println!(
+        "> {}({}), from: {:?}, derivation: {}, nonce: {}",
+        offline.function,
+        offline.calldata.join(", "),
+        offline.from,
+        offline.derivation,
+        offline.nonce
+    );
+
+    let prov = get_chain_provider(args.chain_id).await?;
+    let nonce = prov
+        .get_transaction_count(offline.from, None)
+        .await
+        .map_err(|e| eyre::eyre!("{}", e))?;
+    if nonce > offline.nonce {
+        return Err(eyre::eyre!(
+            "> Nonce {} of {:?} is already used, the next nonce is {}. Was the transaction already broadcast?",
+            offline.nonce, offline.from, nonce
+        ))
+    }
+    if nonce < offline.nonce {
+        println!("> The next nonce of {:?} is {}, the transaction waits until nonce {} is mined", offline.from, nonce, offline.nonce);
+    }
+    let required = offline.value
+        .checked_add(offline.fees.max_cost(offline.gas_limit)?)
+        .ok_or_else(|| eyre::eyre!("> Msg value {} wei plus the max fee overflows", offline.value))?;
+    let balance = get_native_balance(&prov, &offline.from).await?;
+    if balance < required {
+        return Err(eyre::eyre!(
+            "> {:?} holds {} wei, the msg value and max fee need {} wei",
+            offline.from, balance, required
+        ))
+    }
+
+    let mut purse_token = Purse404Contract::new(
+        purse_address,
+        &Arc::new(prov),
+    );
+    let journal = args.file_path.as_deref().map(Journal::for_log);
+    if let Some(journal) = &journal {
+        purse_token = purse_token.with_journal(journal.clone());
+    }
+
+    let (_, _, _, _, receipt_json) = purse_token.send_signed_transaction(&offline.signed()).await?;
+    if let (Some(journal), Some(file_path)) = (&journal, &args.file_path) {
+        log_sent_transaction(
+            &purse_token,
+            journal,
+            offline.derivation,
+            &receipt_json,
+            file_path,
+            args.allow_older_schema,
+            args.chain_id
+        ).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use dotenv::dotenv;
+use std::sync::Arc;
+use clap::{Parser, Subcommand};
+use ethers::types::U256;
+use crate::{
+    cli::args::{PermitSignCliArgs, PermitSubmitCliArgs},
+    journal::Journal,
+    utils::{
+        get_purse_address,
+        get_chain_provider,
+        unix_timestamp,
+    },
+    wallet::Wallet,
+    contract::{
+        purse_contract::Purse404Contract,
+        purse_permit::{sign_permit, SignedPermit},
+    },
+};
+use super::{log_sent_transaction, parse_token_amount};
+
+#[derive(Debug, Parser)]
+pub struct PermitCommand {
+    /// Permit subcommand
+    #[clap(subcommand)]
+    command: PermitSubcommand,
+}
+
+/// Permit subcommands
+#[derive(Debug, Subcommand)]
+pub enum PermitSubcommand {
+    /// Sign an EIP-2612 permit with the owner derivation, no transaction is sent
+    #[command(name = "sign")]
+    Sign(PermitSignCliArgs),
+
+    /// Relay a signed permit on-chain, the relayer derivation pays the gas
+    #[command(name = "submit")]
+    Submit(PermitSubmitCliArgs),
+}
+
+impl PermitCommand {
+    /// Execute the command
+    pub async fn execute(self) -> eyre::Result<()> {
+        match self.command {
+            PermitSubcommand::Sign(args) => sign(args).await,
+            PermitSubcommand::Submit(args) => submit(args).await,
+        }
+    }
+}
+
+/// Sign a permit for the spender, with the domain and the owner nonce read on-chain.
+/// The signed permit is written as JSON to the output file, or printed.
+async fn sign(args: PermitSignCliArgs) -> eyre::Result<()> {
+    println!("> Executing Permit sign command \n");
+
+    dotenv().ok();
+    let phrase = std::env::var("MNEMONIC")?;
+    let owner = Wallet::from_phrase(&phrase, args.owner_derivation, args.chain_id)?;
+    let spender = match (&args.spender, args.spender_derivation) {
+        (Some(spender), _) => spender.parse()?,
+        (None, Some(derivation)) => Wallet::from_phrase(&phrase, derivation, args.chain_id)?.address(),
+        (None, None) => return Err(eyre::eyre!("> Either --spender or --spender-derivation is required")),
+    };
+    let deadline = args.deadline.unwrap_or_else(|| unix_timestamp() + args.valid_for);
+    if deadline < unix_timestamp() {
+        println!("> Deadline {} is in the past, the permit will be rejected as expired", deadline);
+    }
+
+    let prov = get_chain_provider(args.chain_id).await?;
+    let purse_token = Purse404Contract::new(
+        get_purse_address(args.chain_id)?,
+        &Arc::new(prov),
+    );
+    let (symbol, decimals) = (purse_token.symbol().await?, purse_token.decimals().await?);
+    let value = parse_token_amount(&args.value, &symbol, decimals, "Value")?;
+    let permit = sign_permit(&purse_token, &owner, spender, value, U256::from(deadline)).await?;
+    println!(
+        "> Signed permit, owner: {:?}, spender: {:?}, value (wei): {}, nonce: {}, deadline: {}",
+        permit.owner,
+        permit.spender,
+        permit.value,
+        permit.nonce,
+        permit.deadline
+    );
+
+    let json_str = serde_json::to_string_pretty(&permit)?;
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, json_str)?;
+            println!("> Permit written to {}", path);
+        },
+        None => println!("{}", json_str),
+    }
+
+    Ok(())
+}
+
+/// Submit a signed permit from the relayer derivation, optionally appending the transaction to the csv log.
+/// The permit is simulated before it is sent, see `Purse404Contract::permit`.
+async fn submit(args: PermitSubmitCliArgs) -> eyre::Result<()> {
+    println!("> Executing Permit submit command \n");
+
+    dotenv().ok();
+    let phrase = std::env::var("MNEMONIC")?;
+    let relayer = Wallet::from_phrase(&phrase, args.relayer_derivation, args.chain_id)?;
+
+    let mut permit: SignedPermit = serde_json::from_str(&std::fs::read_to_string(&args.permit_path)?)?;
+    if permit.chain_id != u64::from(args.chain_id) {
+        return Err(eyre::eyre!("> Permit was signed for chain {}, not {}", permit.chain_id, args.chain_id))
+    }
+    let purse_address = get_purse_address(args.chain_id)?;
+    if permit.contract != purse_address {
+        return Err(eyre::eyre!("> Permit was signed for contract {:?}, not {:?}", permit.contract, purse_address))
+    }
+    if let Some(owner) = &args.owner {
+        permit.owner = owner.parse()?;
+        println!("> Submitting for owner {:?}, instead of the signer", permit.owner);
+    }
+    println!("> Relayer: {:?}, derivation: {}", relayer.address(), args.relayer_derivation);
+
+    let prov = get_chain_provider(args.chain_id).await?;
+    let mut purse_token = Purse404Contract::new(
+        purse_address,
+        &Arc::new(prov),
+    );
+    let journal = args.file_path.as_deref().map(Journal::for_log);
+    if let Some(journal) = &journal {
+        purse_token = purse_token.with_journal(journal.clone());
+    }
+
+    let allowance = purse_token.allowance(&permit.owner, &permit.spender, None).await?;
+    println!("> Allowance before: {} \n", allowance);
+
+    let (_, _, _, _, receipt_json) = purse_token.permit(&relayer, &permit).await?;
+    if let (Some(journal), Some(file_path)) = (&journal, &args.file_path) {
+        log_sent_transaction(
+            &purse_token,
+            journal,
+            args.relayer_derivation,
+            &receipt_json,
+            file_path,
+            args.allow_older_schema,
+            args.chain_id
+        ).await?;
+    }
+
+    let allowance = purse_token.allowance(&permit.owner, &permit.spender, None).await?;
+    println!("> Allowance after: {}", allowance);
+
+    Ok(())
+}
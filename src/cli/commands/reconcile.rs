@@ -0,0 +1,124 @@
+use dotenv::dotenv;
+use std::sync::Arc;
+use clap::Parser;
+use ethers::providers::Middleware;
+use crate::{
+    cli::args::ReconcileCliArgs,
+    file::read_from_csv,
+    journal::{
+        Journal,
+        JournalEntry,
+        JournalStatus,
+    },
+    utils::{
+        get_purse_address,
+        get_chain_provider,
+    },
+    contract::purse_contract::Purse404Contract,
+};
+use super::log_mined_transaction;
+
+#[derive(Debug, Parser)]
+pub struct ReconcileCommand {
+    /// All Cli args
+    #[clap(flatten)]
+    cli_args: ReconcileCliArgs,
+}
+
+impl ReconcileCommand {
+    /// Execute the command.
+    /// Completes the csv log from the journal: every journaled transaction that was sent but not logged
+    /// is looked up on chain, and is either logged, marked as dropped, or reported as still pending.
+    pub async fn execute(self) -> eyre::Result<()> {
+        println!("> Executing Reconcile command \n");
+
+        dotenv().ok();
+        let cid = self.cli_args.chain_id;
+        let file_path = self.cli_args.file_path;
+        let journal = Journal::for_log(&file_path);
+
+        let entries: Vec<JournalEntry> = journal
+            .unsettled()?
+            .into_iter()
+            .filter(|entry| entry.chain_id == cid as u64)
+            .collect();
+        if entries.is_empty() {
+            println!("> No unsettled transactions in journal: {:?}", journal.path());
+            return Ok(())
+        }
+        println!("> Found {} unsettled transactions in journal: {:?} \n", entries.len(), journal.path());
+
+        let prov = get_chain_provider(cid).await?;
+        let purse_token = Purse404Contract::new(
+            get_purse_address(cid)?,
+            &Arc::new(prov.clone()),
+        );
+        let logged_hashes: Vec<String> = read_from_csv(&file_path)
+            .map(|records| records.into_iter().map(|record| record.transaction_hash).collect())
+            .unwrap_or_default();
+
+        for entry in entries {
+            println!(
+                "> Transaction {:?}, derivation: {}, nonce: {}, status: {}",
+                entry.tx_hash, entry.derivation, entry.nonce, entry.status
+            );
+
+            if logged_hashes.contains(&format!("{:?}", entry.tx_hash)) {
+                println!("> Already in the csv log, marking as logged \n");
+                journal.update_status(&entry.tx_hash, JournalStatus::Logged, None)?;
+                continue;
+            }
+
+            match prov.get_transaction_receipt(entry.tx_hash).await? {
+                Some(receipt) if receipt.status.map(|status| status.is_zero()).unwrap_or(false) => {
+                    println!("> Reverted, marking as reverted \n");
+                    journal.update_status(
+                        &entry.tx_hash, 
+                        JournalStatus::Reverted, 
+                        receipt.block_number.map(|n| n.as_u64())
+                    )?;
+                },
+                Some(receipt) => {
+                    let tx = prov
+                        .get_transaction(entry.tx_hash)
+                        .await?
+                        .ok_or_else(|| eyre::eyre!("Transaction {:?} has a receipt, but was not found", entry.tx_hash))?;
+                    log_mined_transaction(
+                        &prov, 
+                        &purse_token, 
+                        entry.derivation, 
+                        &tx, 
+                        &receipt, 
+                        &file_path, 
+                        self.cli_args.allow_older_schema
+                    ).await?;
+                    journal.update_status(
+                        &entry.tx_hash, 
+                        JournalStatus::Logged, 
+                        receipt.block_number.map(|n| n.as_u64())
+                    )?;
+                    println!("> Mined and logged \n");
+                },
+                None => {
+                    if prov.get_transaction(entry.tx_hash).await?.is_some() {
+                        println!("> Still pending in the mempool \n");
+                        continue;
+                    }
+
+                    let account_nonce = prov.get_transaction_count(entry.from, None).await?;
+                    if account_nonce > entry.nonce {
+                        println!("> Nonce {} was used by another transaction, marking as dropped \n", entry.nonce);
+                        journal.update_status(&entry.tx_hash, JournalStatus::Dropped, None)?;
+                    } else if self.cli_args.rebroadcast {
+                        prov.send_raw_transaction(entry.raw_tx.clone()).await?;
+                        println!("> Not known to the network, rebroadcast. Run reconcile again once it is mined \n");
+                    } else {
+                        println!("> Not known to the network. Rerun with --rebroadcast to send it again \n");
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
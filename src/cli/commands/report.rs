@@ -0,0 +1,86 @@
+use clap::Parser;
+use crate::{
+    cli::args::{OutputFormat, ReportCliArgs},
+    file::read_from_csv,
+    report::{build_report, ReportFilter},
+    utils::format_table,
+};
+
+#[derive(Debug, Parser)]
+pub struct ReportCommand {
+    /// All Cli args
+    #[clap(flatten)]
+    cli_args: ReportCliArgs,
+}
+
+impl ReportCommand {
+    /// Execute the command
+    pub async fn execute(self) -> eyre::Result<()> {
+        let file_path = self.cli_args.file_path;
+        let records = read_from_csv(&file_path)?;
+
+        let filter = ReportFilter {
+            derivations: self.cli_args.derivation,
+            functions: self.cli_args.function,
+            from_date: self.cli_args.from_date,
+            to_date: self.cli_args.to_date,
+        };
+        let report = build_report(&records, &filter);
+
+        if self.cli_args.format == OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(())
+        }
+
+        println!("> Report for: {} \n", file_path);
+        println!("> Transactions: {}", report.transactions);
+        println!("> Total tx fees (ETH): {}", report.total_tx_fee_eth);
+        println!(
+            "> Time range (UTC): {} - {} \n",
+            report.first_timestamp.as_deref().unwrap_or("n/a"),
+            report.last_timestamp.as_deref().unwrap_or("n/a")
+        );
+
+        let function_rows: Vec<Vec<String>> = report.functions
+            .iter()
+            .map(|f| vec![
+                f.function.clone(),
+                f.transactions.to_string(),
+                f.total_tx_fee_eth.to_string(),
+                format!("{:.0}", f.avg_gas_used),
+                format!("{:.4}", f.avg_gas_price_gwei),
+                f.nfts_gained.to_string(),
+                f.first_timestamp.clone().unwrap_or_default(),
+                f.last_timestamp.clone().unwrap_or_default(),
+            ])
+            .collect();
+        println!("> Per function:");
+        println!("{} \n", format_table(
+            &["Function", "Txs", "Tx Fees (ETH)", "Avg Gas Used", "Avg Gas Price (gwei)", "NFTs Gained", "First", "Last"],
+            &function_rows
+        ));
+
+        let sender_rows: Vec<Vec<String>> = report.senders
+            .iter()
+            .map(|s| vec![
+                s.derivation.to_string(),
+                s.sender.clone(),
+                s.transactions.to_string(),
+                s.total_tx_fee_eth.to_string(),
+                s.net_eth_delta.to_string(),
+                s.net_erc20_delta.to_string(),
+                s.nfts_gained.to_string(),
+                s.nfts_owned.to_string(),
+                s.first_timestamp.clone().unwrap_or_default(),
+                s.last_timestamp.clone().unwrap_or_default(),
+            ])
+            .collect();
+        println!("> Per sender:");
+        println!("{}", format_table(
+            &["Derivation", "Sender", "Txs", "Tx Fees (ETH)", "Net ETH", "Net ERC20", "NFTs Gained", "NFTs Owned", "First", "Last"],
+            &sender_rows
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+use dotenv::dotenv;
+use std::sync::Arc;
+use clap::{Parser, Subcommand};
+use serde_json::json;
+use ethers::{
+    providers::Middleware,
+    types::Address,
+};
+use crate::{
+    cli::args::{
+        OutputFormat,
+        RolesCheckCliArgs,
+        RolesListCliArgs,
+        RolesRenounceCliArgs,
+        RolesUpdateCliArgs,
+    },
+    utils::{
+        get_purse_address,
+        get_chain_provider,
+        format_table,
+    },
+    wallet::Wallet,
+    contract::{
+        purse_contract::Purse404Contract,
+        purse_roles::Role,
+    },
+};
+
+#[derive(Debug, Parser)]
+pub struct RolesCommand {
+    /// Roles subcommand
+    #[clap(subcommand)]
+    command: RolesSubcommand,
+}
+
+/// Roles subcommands
+#[derive(Debug, Subcommand)]
+pub enum RolesSubcommand {
+    /// List the roles held by an address, or by a range of derived wallets
+    #[command(name = "list")]
+    List(RolesListCliArgs),
+
+    /// Grant a role to an account, sent by an admin of the role
+    #[command(name = "grant")]
+    Grant(RolesUpdateCliArgs),
+
+    /// Revoke a role from an account, sent by an admin of the role
+    #[command(name = "revoke")]
+    Revoke(RolesUpdateCliArgs),
+
+    /// Give up a role held by a derived wallet
+    #[command(name = "renounce")]
+    Renounce(RolesRenounceCliArgs),
+
+    /// Check that a derived wallet holds the role a function is gated by
+    #[command(name = "check")]
+    Check(RolesCheckCliArgs),
+}
+
+impl RolesCommand {
+    /// Execute the command
+    pub async fn execute(self) -> eyre::Result<()> {
+        match self.command {
+            RolesSubcommand::List(args) => list(args).await,
+            RolesSubcommand::Grant(args) => update(args, true).await,
+            RolesSubcommand::Revoke(args) => update(args, false).await,
+            RolesSubcommand::Renounce(args) => renounce(args).await,
+            RolesSubcommand::Check(args) => check(args).await,
+        }
+    }
+}
+
+/// List the roles held by the given address, the given derivation,
+/// or the wallets from derivation 0 up to the max derivation.
+async fn list(args: RolesListCliArgs) -> eyre::Result<()> {
+    if args.format == OutputFormat::Table {
+        println!("> Executing Roles list command \n");
+    }
+
+    dotenv().ok();
+    let accounts: Vec<(Option<u32>, Address)> = match (&args.address, args.derivation) {
+        (Some(address), _) => vec![(None, address.parse()?)],
+        (None, Some(derivation)) => {
+            let phrase = std::env::var("MNEMONIC")?;
+            let wallet = Wallet::from_phrase(&phrase, derivation, args.chain_id)?;
+            vec![(Some(derivation), wallet.address())]
+        },
+        (None, None) => {
+            let phrase = std::env::var("MNEMONIC")?;
+            Wallet::generate_wallets(&phrase, args.max_derivation, args.chain_id)?
+                .iter()
+                .map(|wallet| (Some(wallet.derivation), wallet.address()))
+                .collect()
+        }
+    };
+
+    let prov = get_chain_provider(args.chain_id).await?;
+    let purse_token = Purse404Contract::new(
+        get_purse_address(args.chain_id)?,
+        &Arc::new(prov),
+    );
+    let mut held = Vec::with_capacity(accounts.len());
+    for (_, address) in &accounts {
+        held.push(roles_of(&purse_token, address).await?);
+    }
+    let derivation = |derivation: &Option<u32>| derivation.map(|d| d.to_string()).unwrap_or_default();
+    let names = |roles: &[Role]| roles.iter().map(|role| role.name()).collect::<Vec<&str>>();
+
+    match args.format {
+        OutputFormat::Json => {
+            let rows: Vec<serde_json::Value> = accounts
+                .iter()
+                .zip(held.iter())
+                .map(|((d, address), roles)| json!({
+                    "derivation": d,
+                    "address": format!("{:?}", address),
+                    "roles": names(roles),
+                }))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&rows)?);
+        },
+        OutputFormat::Table => {
+            let rows: Vec<Vec<String>> = accounts
+                .iter()
+                .zip(held.iter())
+                .map(|((d, address), roles)| vec![
+                    derivation(d),
+                    format!("{:?}", address),
+                    names(roles).join(","),
+                ])
+                .collect();
+            println!("{}", format_table(&["Derivation", "Address", "Roles"], &rows));
+        }
+    }
+
+    Ok(())
+}
+
+/// Grant (or revoke) a role, after checking the admin holds the admin role of the role.
+/// Nothing is sent if the account already holds (or does not hold) the role.
+async fn update(args: RolesUpdateCliArgs, grant: bool) -> eyre::Result<()> {
+    let func = if grant { "grantRole" } else { "revokeRole" };
+    println!("> Executing Roles {} command \n", if grant { "grant" } else { "revoke" });
+
+    dotenv().ok();
+    let phrase = std::env::var("MNEMONIC")?;
+    let admin = Wallet::from_phrase(&phrase, args.admin_derivation, args.chain_id)?;
+    let account: Address = match (&args.account, args.account_derivation) {
+        (Some(account), _) => account.parse()?,
+        (None, Some(derivation)) => Wallet::from_phrase(&phrase, derivation, args.chain_id)?.address(),
+        (None, None) => return Err(eyre::eyre!("> Either --account or --account-derivation is required")),
+    };
+
+    let prov = get_chain_provider(args.chain_id).await?;
+    let purse_token = Purse404Contract::new(
+        get_purse_address(args.chain_id)?,
+        &Arc::new(prov),
+    );
+
+    if purse_token.has_role(args.role, &account).await? == grant {
+        println!(
+            "> {:?} {} {}, nothing to do",
+            account,
+            if grant { "already holds" } else { "does not hold" },
+            args.role
+        );
+        return Ok(())
+    }
+    let admin_role_id = purse_token.get_role_admin(args.role).await?;
+    match Role::from_id(&admin_role_id) {
+        Some(admin_role) => purse_token.require_role(admin_role, &admin.address(), func).await?,
+        None => println!(
+            "> Admin role of {} is not a known role (0x{}), sending without checking it",
+            args.role,
+            ethers::utils::hex::encode(admin_role_id)
+        ),
+    }
+
+    if grant {
+        purse_token.grant_role(&admin, args.role, &account).await?;
+    } else {
+        purse_token.revoke_role(&admin, args.role, &account).await?;
+    }
+    println!("> {:?} holds {}: {}", account, args.role, purse_token.has_role(args.role, &account).await?);
+
+    Ok(())
+}
+
+/// Renounce a role held by the given derivation
+async fn renounce(args: RolesRenounceCliArgs) -> eyre::Result<()> {
+    println!("> Executing Roles renounce command \n");
+
+    dotenv().ok();
+    let phrase = std::env::var("MNEMONIC")?;
+    let wallet = Wallet::from_phrase(&phrase, args.derivation, args.chain_id)?;
+
+    let prov = get_chain_provider(args.chain_id).await?;
+    let purse_token = Purse404Contract::new(
+        get_purse_address(args.chain_id)?,
+        &Arc::new(prov),
+    );
+    if !purse_token.has_role(args.role, &wallet.address()).await? {
+        println!("> {:?} does not hold {}, nothing to do", wallet.address(), args.role);
+        return Ok(())
+    }
+
+    purse_token.renounce_role(&wallet, args.role).await?;
+    println!("> {:?} holds {}: {}", wallet.address(), args.role, purse_token.has_role(args.role, &wallet.address()).await?);
+
+    Ok(())
+}
+
+/// Check that the given derivation holds the role the function is gated by
+async fn check(args: RolesCheckCliArgs) -> eyre::Result<()> {
+    println!("> Executing Roles check command \n");
+
+    let role = match Role::gating(&args.function) {
+        Some(role) => role,
+        None => {
+            println!("> {} is not gated by a role", args.function);
+            return Ok(())
+        }
+    };
+
+    dotenv().ok();
+    let phrase = std::env::var("MNEMONIC")?;
+    let wallet = Wallet::from_phrase(&phrase, args.derivation, args.chain_id)?;
+
+    let prov = get_chain_provider(args.chain_id).await?;
+    let purse_token = Purse404Contract::new(
+        get_purse_address(args.chain_id)?,
+        &Arc::new(prov),
+    );
+    purse_token.require_role(role, &wallet.address(), &args.function).await?;
+    println!("> {:?} holds {}, {} may be called", wallet.address(), role, args.function);
+
+    Ok(())
+}
+
+/// Reads the roles held by the given address
+async fn roles_of<M: Middleware + 'static>(
+    purse_token: &Purse404Contract<M>,
+    address: &Address,
+) -> eyre::Result<Vec<Role>> {
+    let mut roles = Vec::new();
+    for role in Role::ALL {
+        if purse_token.has_role(role, address).await? {
+            roles.push(role);
+        }
+    }
+    Ok(roles)
+}
@@ -0,0 +1,152 @@
+use dotenv::dotenv;
+use std::sync::Arc;
+use clap::{Parser, Subcommand};
+use ethers::{
+    providers::Middleware,
+    types::Address,
+};
+use crate::{
+    cli::args::{SafeProposeCliArgs, SafeSignCliArgs},
+    utils::{
+        get_purse_address,
+        get_chain_provider,
+        get_multisend_address,
+    },
+    wallet::Wallet,
+    contract::{
+        purse_calldata::{encode_calldata, find_function, load_abi},
+        purse_contract::Purse404Contract,
+        purse_roles::Role,
+        purse_safe::{read_safe_state, read_safe_tx_hash, SafeCall, SafeTransaction},
+    },
+};
+
+#[derive(Debug, Parser)]
+pub struct SafeCommand {
+    /// Safe subcommand
+    #[clap(subcommand)]
+    command: SafeSubcommand,
+}
+
+/// Safe subcommands, to propose Purse404 calls made by a Safe and collect owner signatures
+#[derive(Debug, Subcommand)]
+pub enum SafeSubcommand {
+    /// Write a Safe transaction for one or more Purse404 function calls, at the Safe nonce
+    #[command(name = "propose")]
+    Propose(SafeProposeCliArgs),
+
+    /// Sign a proposed Safe transaction with a derived wallet that owns the Safe, no provider is used
+    #[command(name = "sign")]
+    Sign(SafeSignCliArgs),
+}
+
+impl SafeCommand {
+    /// Execute the command
+    pub async fn execute(self) -> eyre::Result<()> {
+        match self.command {
+            SafeSubcommand::Propose(args) => propose(args).await,
+            SafeSubcommand::Sign(args) => sign(args),
+        }
+    }
+}
+
+/// Encode the calls against the Purse404 ABI, and write the Safe transaction making them.
+/// Several calls are batched through MultiSend. The Safe transaction hash is checked against the one
+/// the Safe computes, and calls gated by a role the Safe does not hold are reported.
+async fn propose(args: SafeProposeCliArgs) -> eyre::Result<()> {
+    println!("> Executing Safe propose command \n");
+
+    dotenv().ok();
+    let safe: Address = args.safe.parse()?;
+    if args.msg_value.is_some() && args.call.len() > 1 {
+        return Err(eyre::eyre!("> --msg-value can only be given with a single --call"))
+    }
+
+    let prov = Arc::new(get_chain_provider(args.chain_id).await?);
+    let purse_token = Purse404Contract::new(
+        get_purse_address(args.chain_id)?,
+        &prov,
+    );
+    let abi = load_abi(None)?;
+    let mut calls = Vec::with_capacity(args.call.len());
+    for call in &args.call {
+        let mut parts = call.split_whitespace().map(String::from);
+        let name = parts.next().ok_or_else(|| eyre::eyre!("> Empty --call"))?;
+        let call_args: Vec<String> = parts.collect();
+        let function = find_function(&abi, &name, call_args.len())?;
+        if let Some(role) = Role::gating(&function.name) {
+            if !purse_token.has_role(role, &safe).await? {
+                eprintln!("> The Safe does not hold {}, {} will revert unless it is granted first", role, function.name);
+            }
+        }
+        calls.push(SafeCall {
+            to: purse_token.address(),
+            value: args.msg_value.unwrap_or_default(),
+            data: encode_calldata(&function, &call_args)?,
+            description: format!("{}({})", function.name, call_args.join(", ")),
+        });
+    }
+
+    let multisend = get_multisend_address()?;
+    if calls.len() > 1 && prov.get_code(multisend, None).await?.is_empty() {
+        return Err(eyre::eyre!("> No MultiSendCallOnly contract at {:?}, set MULTISEND_ADDRESS", multisend))
+    }
+    let mut state = read_safe_state(&prov, safe).await?;
+    if let Some(nonce) = args.nonce {
+        if nonce < state.nonce {
+            return Err(eyre::eyre!("> Safe nonce {} is already used, the current nonce is {}", nonce, state.nonce))
+        }
+        state.nonce = nonce;
+    }
+
+    let tx = SafeTransaction::new(safe, u64::from(args.chain_id), &state, &calls, multisend)?;
+    let safe_tx_hash = read_safe_tx_hash(&prov, &tx).await?;
+    if safe_tx_hash != tx.safe_tx_hash {
+        return Err(eyre::eyre!(
+            "> The Safe computes the transaction hash {:?}, not {:?}, is Safe version {} supported?",
+            safe_tx_hash, tx.safe_tx_hash, state.version
+        ))
+    }
+    println!("> Safe: {:?}, version: {}, nonce: {}, threshold: {} of {} owner(s)", safe, state.version, tx.nonce, state.threshold, state.owners.len());
+    for call in &tx.calls {
+        println!("> Call: {}", call);
+    }
+    println!("> Safe transaction hash: {:?}", tx.safe_tx_hash);
+
+    let json_str = serde_json::to_string_pretty(&tx)?;
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, json_str)?;
+            println!("> Safe transaction written to {}", path);
+        },
+        None => println!("{}", json_str),
+    }
+
+    Ok(())
+}
+
+/// Add the signature of the derived owner to the Safe transaction file
+fn sign(args: SafeSignCliArgs) -> eyre::Result<()> {
+    println!("> Executing Safe sign command \n");
+
+    dotenv().ok();
+    let phrase = std::env::var("MNEMONIC")?;
+    let owner = Wallet::from_phrase(&phrase, args.derivation, args.chain_id)?;
+
+    let mut tx: SafeTransaction = serde_json::from_str(&std::fs::read_to_string(&args.tx_path)?)?;
+    if tx.chain_id != u64::from(args.chain_id) {
+        return Err(eyre::eyre!("> Safe transaction is for chain {}, not {}", tx.chain_id, args.chain_id))
+    }
+    for call in &tx.calls {
+        println!("> Call: {}", call);
+    }
+    tx.sign(&owner)?;
+    std::fs::write(&args.tx_path, serde_json::to_string_pretty(&tx)?)?;
+    println!("> Signed {:?} as {:?}, derivation: {}", tx.safe_tx_hash, owner.address(), args.derivation);
+    println!("> Signatures: {} of {} required", tx.signatures.len(), tx.threshold);
+    if tx.signatures.len() as u64 >= tx.threshold {
+        println!("> Threshold reached, execTransaction signatures: {}", tx.packed_signatures());
+    }
+
+    Ok(())
+}
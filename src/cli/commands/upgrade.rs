@@ -0,0 +1,144 @@
+use dotenv::dotenv;
+use std::{str::FromStr, sync::Arc};
+use clap::Parser;
+use ethers::{
+    providers::Middleware,
+    types::{Address, Bytes, TransactionReceipt, H256},
+};
+use crate::{
+    cli::args::UpgradeCliArgs,
+    journal::Journal,
+    utils::{
+        get_purse_address,
+        get_chain_provider,
+        confirm_prompt,
+        unix_timestamp,
+    },
+    wallet::Wallet,
+    contract::{
+        purse_contract::Purse404Contract,
+        purse_roles::Role,
+        purse_upgrade::{
+            append_upgrade_record,
+            encode_initializer,
+            UpgradeRecord,
+            ERC1967_IMPLEMENTATION_SLOT,
+        },
+    },
+};
+use super::log_sent_transaction;
+
+#[derive(Debug, Parser)]
+pub struct UpgradeCommand {
+    /// All Cli args
+    #[clap(flatten)]
+    cli_args: UpgradeCliArgs,
+}
+
+impl UpgradeCommand {
+    /// Execute the command.
+    /// The new implementation must have code and return the ERC1967 implementation slot from `proxiableUUID`,
+    /// and the sender must hold `UPGRADER_ROLE`. The upgrade is simulated, sent once confirmed,
+    /// and the previous and new implementation are appended to the upgrade record.
+    pub async fn execute(self) -> eyre::Result<()> {
+        println!("> Executing Upgrade command \n");
+
+        dotenv().ok();
+        let args = self.cli_args;
+        let phrase = std::env::var("MNEMONIC")?;
+        let upgrader = Wallet::from_phrase(&phrase, args.derivation, args.chain_id)?;
+        let implementation: Address = args.implementation.parse()?;
+        let data: Bytes = match (&args.init_signature, &args.init_data) {
+            (Some(signature), _) => encode_initializer(signature, &args.init_arg)?,
+            (None, Some(data)) => data.parse()?,
+            (None, None) => Bytes::new(),
+        };
+
+        let prov = get_chain_provider(args.chain_id).await?;
+        let mut purse_token = Purse404Contract::new(
+            get_purse_address(args.chain_id)?,
+            &Arc::new(prov),
+        );
+        let journal = args.file_path.as_deref().map(Journal::for_log);
+        if let Some(journal) = &journal {
+            purse_token = purse_token.with_journal(journal.clone());
+        }
+
+        let previous = purse_token.implementation().await?;
+        println!("> Proxy: {:?}", purse_token.address());
+        println!("> Current implementation: {:?}", previous);
+        println!("> Upgrade interface version: {}", purse_token.upgrade_interface_version().await?);
+        if previous == implementation {
+            return Err(eyre::eyre!("> {:?} is already the implementation", implementation))
+        }
+
+        let code = purse_token
+            .provider()
+            .get_code(implementation, None)
+            .await
+            .map_err(|e| eyre::eyre!("{}", e))?;
+        if code.is_empty() {
+            return Err(eyre::eyre!("> {:?} has no code, the upgrade would revert with ERC1967InvalidImplementation()", implementation))
+        }
+        let uuid = H256::from(purse_token.proxiable_uuid_of(&implementation).await?);
+        if uuid != H256::from_str(ERC1967_IMPLEMENTATION_SLOT)? {
+            return Err(eyre::eyre!(
+                "> proxiableUUID of {:?} is {:?}, not the ERC1967 implementation slot, the upgrade would revert with UUPSUnsupportedProxiableUUID()",
+                implementation,
+                uuid
+            ))
+        }
+        println!("> proxiableUUID of {:?} matches the ERC1967 implementation slot", implementation);
+
+        purse_token.require_role(Role::Upgrader, &upgrader.address(), "upgradeToAndCall").await?;
+        purse_token.simulate_upgrade_to_and_call(&upgrader.address(), &implementation, &data).await?;
+        println!("> Simulated upgradeToAndCall({:?}, {}) successfully", implementation, data);
+        if args.dry_run {
+            return Ok(())
+        }
+
+        let question = format!(
+            "> Upgrade {:?} from {:?} to {:?} on chain {}?",
+            purse_token.address(),
+            previous,
+            implementation,
+            args.chain_id
+        );
+        if !args.yes && !confirm_prompt(&question)? {
+            println!("> Cancelled, nothing was sent");
+            return Ok(())
+        }
+
+        let (_, _, _, _, receipt_json) = purse_token.upgrade_to_and_call(&upgrader, &implementation, &data).await?;
+        let receipt: TransactionReceipt = serde_json::from_str(&receipt_json)?;
+        let current = purse_token.implementation().await?;
+        append_upgrade_record(&args.record_path, &UpgradeRecord {
+            timestamp: unix_timestamp(),
+            chain_id: u64::from(args.chain_id),
+            proxy: purse_token.address(),
+            previous_implementation: previous,
+            new_implementation: current,
+            init_data: data,
+            tx_hash: receipt.transaction_hash,
+            block_number: receipt.block_number.map(|n| n.as_u64()),
+        })?;
+        println!("> Implementation: {:?} -> {:?}, recorded in {}", previous, current, args.record_path);
+
+        if let (Some(journal), Some(file_path)) = (&journal, &args.file_path) {
+            log_sent_transaction(
+                &purse_token,
+                journal,
+                args.derivation,
+                &receipt_json,
+                file_path,
+                args.allow_older_schema,
+                args.chain_id
+            ).await?;
+        }
+        if current != implementation {
+            return Err(eyre::eyre!("> The implementation is {:?} after the upgrade, expected {:?}", current, implementation))
+        }
+
+        Ok(())
+    }
+}
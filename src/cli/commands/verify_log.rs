@@ -0,0 +1,92 @@
+use dotenv::dotenv;
+use std::sync::Arc;
+use clap::Parser;
+use crate::{
+    cli::args::{OutputFormat, VerifyLogCliArgs},
+    file::read_from_csv,
+    utils::{
+        get_purse_address,
+        get_chain_provider,
+        format_table,
+    },
+    verify::verify_record,
+    contract::purse_contract::Purse404Contract,
+};
+
+#[derive(Debug, Parser)]
+pub struct VerifyLogCommand {
+    /// All Cli args
+    #[clap(flatten)]
+    cli_args: VerifyLogCliArgs,
+}
+
+impl VerifyLogCommand {
+    /// Execute the command.
+    /// Re-fetches every logged transaction and reports the logged values that do not match the chain state.
+    /// Returns an error if any record could not be verified.
+    pub async fn execute(self) -> eyre::Result<()> {
+        dotenv().ok();
+        let cid = self.cli_args.chain_id;
+        let file_path = self.cli_args.file_path;
+        let format = self.cli_args.format;
+        if format == OutputFormat::Table {
+            println!("> Executing Verify log command \n");
+        }
+
+        let records = read_from_csv(&file_path)?;
+        let prov = get_chain_provider(cid).await?;
+        let purse_token = Purse404Contract::new(
+            get_purse_address(cid)?,
+            &Arc::new(prov.clone()),
+        );
+
+        let mut verifications = Vec::new();
+        for record in records.iter() {
+            let verification = verify_record(&prov, &purse_token, record).await?;
+            if format == OutputFormat::Table {
+                let status = match (verification.block_number, verification.discrepancies.len()) {
+                    (None, _) => "not found".to_string(),
+                    (Some(_), 0) => "ok".to_string(),
+                    (Some(_), n) => format!("{} discrepancies", n),
+                };
+                println!("> {} ({}): {}", record.transaction_hash, record.function, status);
+            }
+            verifications.push(verification);
+        }
+        let failed = verifications.iter().filter(|v| !v.is_valid()).count();
+
+        match format {
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&verifications)?);
+            },
+            OutputFormat::Table => {
+                let rows: Vec<Vec<String>> = verifications
+                    .iter()
+                    .flat_map(|v| {
+                        let block = v.block_number.map(|n| n.to_string()).unwrap_or_default();
+                        v.discrepancies.iter().map(move |d| vec![
+                            v.transaction_hash.clone(),
+                            block.clone(),
+                            d.field.clone(),
+                            d.logged.clone(),
+                            d.on_chain.clone(),
+                        ])
+                    })
+                    .collect();
+                if !rows.is_empty() {
+                    println!("\n{}", format_table(
+                        &["Transaction Hash", "Block", "Column", "Logged", "On Chain"],
+                        &rows
+                    ));
+                }
+                println!("\n> Verified {} records, {} with discrepancies or not found", verifications.len(), failed);
+            }
+        }
+
+        if failed > 0 {
+            return Err(eyre::eyre!("{} of {} records in: {} do not match the chain state", failed, verifications.len(), file_path))
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use dotenv::dotenv;
+use std::sync::Arc;
+use clap::{Parser, Subcommand};
+use serde_json::json;
+use crate::{
+    cli::args::{OutputFormat, WalletsListCliArgs},
+    utils::{
+        get_purse_address,
+        get_chain_provider,
+        format_table,
+        str_wei_to_eth,
+    },
+    wallet::Wallet,
+    contract::purse_contract::Purse404Contract,
+};
+
+#[derive(Debug, Parser)]
+pub struct WalletsCommand {
+    /// Wallets subcommand
+    #[clap(subcommand)]
+    command: WalletsSubcommand,
+}
+
+/// Wallets subcommands
+#[derive(Debug, Subcommand)]
+pub enum WalletsSubcommand {
+    /// List the derived wallets with their ETH, ERC20 and NFT balances
+    #[command(name = "list")]
+    List(WalletsListCliArgs),
+}
+
+impl WalletsCommand {
+    /// Execute the command
+    pub async fn execute(self) -> eyre::Result<()> {
+        match self.command {
+            WalletsSubcommand::List(args) => list(args).await,
+        }
+    }
+}
+
+/// List the wallets from derivation 0 up to the given derivation number.
+/// The balances are read in batches through Multicall3.
+async fn list(args: WalletsListCliArgs) -> eyre::Result<()> {
+    if args.format == OutputFormat::Table {
+        println!("> Executing Wallets list command \n");
+    }
+
+    dotenv().ok();
+    let phrase = std::env::var("MNEMONIC")?;
+    let wallets = Wallet::generate_wallets(&phrase, args.max_derivation, args.chain_id)?;
+    let addresses: Vec<_> = wallets.iter().map(|wallet| wallet.address()).collect();
+
+    let prov = get_chain_provider(args.chain_id).await?;
+    let purse_token = Purse404Contract::new(
+        get_purse_address(args.chain_id)?,
+        &Arc::new(prov),
+    );
+    let (balances, without_multicall) = purse_token.balances_at(&addresses, args.block, args.owned).await?;
+    if without_multicall {
+        // Kept off stdout, so the JSON output stays parseable
+        eprintln!("> No Multicall3 contract deployed, balances were read one call at a time");
+    }
+    let owned_ids = |owned: &Option<Vec<ethers::types::U256>>| owned
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<String>>();
+
+    match args.format {
+        OutputFormat::Json => {
+            let rows: Vec<serde_json::Value> = wallets
+                .iter()
+                .zip(balances.iter())
+                .map(|(wallet, balance)| {
+                    let mut row = json!({
+                        "derivation": wallet.derivation,
+                        "address": format!("{:?}", balance.address),
+                        "eth": str_wei_to_eth(&balance.eth.to_string()),
+                        "erc20": str_wei_to_eth(&balance.erc20.to_string()),
+                        "active": str_wei_to_eth(&balance.active.to_string()),
+                        "inactive": str_wei_to_eth(&balance.inactive.to_string()),
+                        "nfts": balance.erc721.to_string(),
+                    });
+                    if args.owned {
+                        row["token_ids"] = json!(owned_ids(&balance.owned));
+                    }
+                    row
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&rows)?);
+        },
+        OutputFormat::Table => {
+            let mut headers = vec!["Derivation", "Address", "ETH", "ERC20", "Active", "Inactive", "NFTs"];
+            if args.owned {
+                headers.push("Token IDs");
+            }
+            let rows: Vec<Vec<String>> = wallets
+                .iter()
+                .zip(balances.iter())
+                .map(|(wallet, balance)| {
+                    let mut row = vec![
+                        wallet.derivation.to_string(),
+                        format!("{:?}", balance.address),
+                        str_wei_to_eth(&balance.eth.to_string()),
+                        str_wei_to_eth(&balance.erc20.to_string()),
+                        str_wei_to_eth(&balance.active.to_string()),
+                        str_wei_to_eth(&balance.inactive.to_string()),
+                        balance.erc721.to_string(),
+                    ];
+                    if args.owned {
+                        row.push(owned_ids(&balance.owned).join(","));
+                    }
+                    row
+                })
+                .collect();
+            println!("{}", format_table(&headers, &rows));
+        }
+    }
+
+    Ok(())
+}
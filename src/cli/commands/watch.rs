@@ -0,0 +1,280 @@
+use dotenv::dotenv;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use clap::Parser;
+use ethers::{
+    contract::EthEvent,
+    providers::{Middleware, Provider, StreamExt},
+    types::{Address, Filter, Log, H256},
+};
+use crate::{
+    cli::args::WatchCliArgs,
+    file::read_from_csv,
+    journal::Journal,
+    transport::{ResilientTransport, RpcTransport},
+    utils::{
+        get_purse_address,
+        get_chain_provider,
+        get_chain_ws_provider,
+        str_wei_to_eth,
+    },
+    wallet::Wallet,
+    contract::{
+        purse_contract::{Purse404Contract, TransferFilter},
+        purse_events::{decode_purse_log, Purse404Event},
+    },
+};
+use super::log_mined_transaction;
+
+#[derive(Debug, Parser)]
+pub struct WatchCommand {
+    /// All Cli args
+    #[clap(flatten)]
+    cli_args: WatchCliArgs,
+}
+
+impl WatchCommand {
+    /// Execute the command.
+    /// Watches the Purse404 contract for transfers, mints and burns touching the derived wallets.
+    /// Subscribes over the chain RPC if it is a WebSocket or IPC endpoint, else over `MAINNET_WS_RPC`/`SEPOLIA_WS_RPC`
+    /// if set, and polls otherwise.
+    /// Runs until interrupted.
+    pub async fn execute(self) -> eyre::Result<()> {
+        println!("> Executing Watch command \n");
+
+        dotenv().ok();
+        let cid = self.cli_args.chain_id;
+        let phrase = std::env::var("MNEMONIC")?;
+        let wallets: HashMap<Address, u32> = Wallet::generate_wallets(&phrase, self.cli_args.max_derivation, cid)?
+            .iter()
+            .map(|wallet| (wallet.address(), wallet.derivation))
+            .collect();
+        println!("> Watching {} wallets, derivation 0 to {}", wallets.len(), self.cli_args.max_derivation);
+
+        let prov = get_chain_provider(cid).await?;
+        let purse_token = Purse404Contract::new(
+            get_purse_address(cid)?,
+            &Arc::new(prov.clone()),
+        );
+        let filter = Filter::new()
+            .address(purse_token.address())
+            .topic0(TransferFilter::signature());
+
+        let logged: HashSet<H256> = match &self.cli_args.file_path {
+            Some(file_path) => {
+                println!("> Appending transactions to: {}", file_path);
+                read_from_csv(file_path)
+                    .map(|records| records
+                        .iter()
+                        .filter_map(|record| record.transaction_hash.parse().ok())
+                        .collect())
+                    .unwrap_or_default()
+            },
+            None => HashSet::new(),
+        };
+
+        let mut watcher = Watcher {
+            prov,
+            purse_token,
+            wallets,
+            journal: self.cli_args.file_path.as_deref().map(Journal::for_log),
+            file_path: self.cli_args.file_path,
+            allow_older_schema: self.cli_args.allow_older_schema,
+            logged,
+        };
+
+        // Polling resumes from the block at startup if the subscription ends before handling a block
+        let mut from_block = watcher.prov.get_block_number().await?.as_u64();
+        if !self.cli_args.poll {
+            match get_chain_ws_provider(cid).await {
+                Ok(pubsub_prov) => {
+                    if let Some(last_block) = watcher.subscribe(&pubsub_prov, &filter).await? {
+                        from_block = from_block.max(last_block);
+                    }
+                    println!("> Subscription ended, falling back to polling");
+                },
+                Err(e) => println!("> Subscriptions unavailable ({}), falling back to polling", e),
+            }
+        }
+        watcher.poll(&filter, from_block, self.cli_args.poll_interval).await
+    }
+}
+
+/// State of a running watch
+struct Watcher {
+    prov: Provider<ResilientTransport>,
+    purse_token: Purse404Contract<Provider<ResilientTransport>>,
+    /// Watched addresses and their derivation numbers
+    wallets: HashMap<Address, u32>,
+    /// Csv log to append transactions to (optional)
+    file_path: Option<String>,
+    /// Journal of the csv log, transactions sent by the `purse` command are logged by it instead
+    journal: Option<Journal>,
+    allow_older_schema: bool,
+    /// Transactions already in the csv log
+    logged: HashSet<H256>,
+}
+
+impl Watcher {
+    /// Handles new blocks and logs from subscriptions, until either subscription ends
+    /// ### Returns
+    /// * `Result<Option<u64>>` - The last block seen, to resume polling from
+    async fn subscribe(&mut self, ws: &Provider<RpcTransport>, filter: &Filter) -> eyre::Result<Option<u64>> {
+        let mut blocks = ws.subscribe_blocks().await?;
+        let mut logs = ws.subscribe_logs(filter).await?;
+        println!("> Subscribed to new blocks and logs \n");
+
+        let mut last_block = None;
+        loop {
+            tokio::select! {
+                block = blocks.next() => match block {
+                    Some(block) => if let Some(number) = block.number {
+                        println!("> Block {}", number);
+                        last_block = Some(number.as_u64());
+                    },
+                    None => break,
+                },
+                log = logs.next() => match log {
+                    Some(log) => self.handle_log(&log).await,
+                    None => break,
+                },
+            }
+        }
+
+        Ok(last_block)
+    }
+
+    /// Polls for new blocks and handles their logs, until interrupted
+    /// ### Arguments
+    /// * `filter` - Log filter
+    /// * `from_block` - Last block already handled
+    /// * `interval` - Seconds between polls
+    async fn poll(&mut self, filter: &Filter, from_block: u64, interval: u64) -> eyre::Result<()> {
+        let mut last_block = from_block;
+        println!("> Polling every {}s, from block {} \n", interval, last_block);
+
+        loop {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            let latest = match self.prov.get_block_number().await {
+                Ok(block) => block.as_u64(),
+                Err(e) => {
+                    eprintln!("> Failed to get block number: {}", e);
+                    continue;
+                }
+            };
+            if latest <= last_block {
+                continue;
+            }
+
+            let range = filter.clone().from_block(last_block + 1).to_block(latest);
+            match self.prov.get_logs(&range).await {
+                Ok(logs) => {
+                    println!("> Blocks {} to {}", last_block + 1, latest);
+                    for log in logs.iter() {
+                        self.handle_log(log).await;
+                    }
+                    last_block = latest;
+                },
+                Err(e) => eprintln!("> Failed to get logs for blocks {} to {}: {}", last_block + 1, latest, e),
+            }
+        }
+    }
+
+    /// Prints a transfer, mint or burn touching a watched wallet, and appends its transaction
+    /// to the csv log if one is set and it was sent by a watched wallet. Logs removed by a reorg
+    /// are reported and skipped. Errors are printed, so the watch keeps running.
+    async fn handle_log(&mut self, log: &Log) {
+        if log.removed == Some(true) {
+            println!(
+                "> Removed by reorg: block: {}, tx: {:?}",
+                log.block_number.unwrap_or_default(),
+                log.transaction_hash.unwrap_or_default()
+            );
+            return;
+        }
+        let (from, to, value) = match decode_purse_log(log) {
+            Some(Purse404Event::Erc20Transfer(e)) => (e.from, e.to, format!("ERC20 {}", str_wei_to_eth(&e.amount.to_string()))),
+            Some(Purse404Event::Erc721Transfer(e)) => (e.from, e.to, format!("ERC721 id {}", e.id)),
+            _ => return,
+        };
+        let derivation = match self.wallets.get(&from).or_else(|| self.wallets.get(&to)) {
+            Some(derivation) => *derivation,
+            None => return,
+        };
+        let action = match (from.is_zero(), to.is_zero()) {
+            (true, _) => "Mint",
+            (_, true) => "Burn",
+            _ => "Transfer",
+        };
+        println!(
+            "> {} {}: from {:?} to {:?}, derivation: {}, block: {}, tx: {:?}",
+            action,
+            value,
+            from,
+            to,
+            derivation,
+            log.block_number.unwrap_or_default(),
+            log.transaction_hash.unwrap_or_default()
+        );
+
+        if let Some(tx_hash) = log.transaction_hash {
+            if let Err(e) = self.log_transaction(tx_hash).await {
+                eprintln!("> Transaction {:?} could not be logged: {}", tx_hash, e);
+            }
+        }
+    }
+
+    /// Appends the transaction to the csv log under the derivation of its sender, unless no log is set,
+    /// it is already logged, or it was not sent by a watched wallet
+    async fn log_transaction(&mut self, tx_hash: H256) -> eyre::Result<()> {
+        let file_path = match &self.file_path {
+            Some(file_path) => file_path.clone(),
+            None => return Ok(()),
+        };
+        // A transaction emits several events, it is logged once
+        if self.logged.contains(&tx_hash) {
+            return Ok(())
+        }
+        if let Some(journal) = &self.journal {
+            if journal.entries()?.iter().any(|entry| entry.tx_hash == tx_hash) {
+                self.logged.insert(tx_hash);
+                return Ok(())
+            }
+        }
+
+        let tx = self.prov
+            .get_transaction(tx_hash)
+            .await?
+            .ok_or_else(|| eyre::eyre!("Transaction not found"))?;
+        // The log records the sender of the transaction, so transfers received from outside accounts are not logged
+        let derivation = match self.wallets.get(&tx.from) {
+            Some(derivation) => *derivation,
+            None => {
+                println!("> Transaction {:?} not logged, sent by an unwatched wallet {:?}", tx_hash, tx.from);
+                return Ok(())
+            }
+        };
+        if tx.to != Some(self.purse_token.address()) {
+            return Err(eyre::eyre!("Not a direct call to the Purse404 contract"))
+        }
+        let receipt = self.prov
+            .get_transaction_receipt(tx_hash)
+            .await?
+            .ok_or_else(|| eyre::eyre!("Transaction receipt not found"))?;
+
+        log_mined_transaction(
+            &self.prov,
+            &self.purse_token,
+            derivation,
+            &tx,
+            &receipt,
+            &file_path,
+            self.allow_older_schema
+        ).await?;
+        self.logged.insert(tx_hash);
+        Ok(())
+    }
+}
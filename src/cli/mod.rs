@@ -16,6 +16,42 @@ pub struct Cli {
 pub enum Commands {
     #[command(name = "purse")]
     Purse(commands::PurseCommand),
+    #[command(name = "log")]
+    Log(commands::LogCommand),
+    #[command(name = "reconcile")]
+    Reconcile(commands::ReconcileCommand),
+    #[command(name = "report")]
+    Report(commands::ReportCommand),
+    #[command(name = "verify-log")]
+    VerifyLog(commands::VerifyLogCommand),
+    #[command(name = "index")]
+    Index(commands::IndexCommand),
+    #[command(name = "watch")]
+    Watch(commands::WatchCommand),
+    #[command(name = "wallets")]
+    Wallets(commands::WalletsCommand),
+    #[command(name = "multicall")]
+    Multicall(commands::MulticallCommand),
+    #[command(name = "inspect")]
+    Inspect(commands::InspectCommand),
+    #[command(name = "allowance-flow")]
+    AllowanceFlow(commands::AllowanceFlowCommand),
+    #[command(name = "permit")]
+    Permit(commands::PermitCommand),
+    #[command(name = "roles")]
+    Roles(commands::RolesCommand),
+    #[command(name = "admin")]
+    Admin(commands::AdminCommand),
+    #[command(name = "upgrade")]
+    Upgrade(commands::UpgradeCommand),
+    #[command(name = "deploy")]
+    Deploy(commands::DeployCommand),
+    #[command(name = "offline")]
+    Offline(commands::OfflineCommand),
+    #[command(name = "calldata")]
+    Calldata(commands::CalldataCommand),
+    #[command(name = "safe")]
+    Safe(commands::SafeCommand),
 }
 
 pub fn run()  -> eyre::Result<()> {
@@ -33,6 +69,24 @@ pub fn run()  -> eyre::Result<()> {
             let task = async move {
                 match cli.command {
                     Commands::Purse(command) => command.execute().await,
+                    Commands::Log(command) => command.execute().await,
+                    Commands::Reconcile(command) => command.execute().await,
+                    Commands::Report(command) => command.execute().await,
+                    Commands::VerifyLog(command) => command.execute().await,
+                    Commands::Index(command) => command.execute().await,
+                    Commands::Watch(command) => command.execute().await,
+                    Commands::Wallets(command) => command.execute().await,
+                    Commands::Multicall(command) => command.execute().await,
+                    Commands::Inspect(command) => command.execute().await,
+                    Commands::AllowanceFlow(command) => command.execute().await,
+                    Commands::Permit(command) => command.execute().await,
+                    Commands::Roles(command) => command.execute().await,
+                    Commands::Admin(command) => command.execute().await,
+                    Commands::Upgrade(command) => command.execute().await,
+                    Commands::Deploy(command) => command.execute().await,
+                    Commands::Offline(command) => command.execute().await,
+                    Commands::Calldata(command) => command.execute().await,
+                    Commands::Safe(command) => command.execute().await,
                 }
             };
             
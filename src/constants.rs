@@ -1 +1,4 @@
 pub const PURSE_ETH_ADDRESS: &str = "0x50F49019DE9d7d33EBb7809803A25db4d8fC2ba0";
+
+/// MultiSendCallOnly 1.3.0, deployed at the same address on mainnet and sepolia
+pub const MULTISEND_CALL_ONLY_ADDRESS: &str = "0x40A2aCCbd92BCA938b02010E17A5b8929b49130D";
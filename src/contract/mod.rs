@@ -1,2 +1,9 @@
+pub mod purse_calldata;
 pub mod purse_contract;
-pub mod purse_executor;
\ No newline at end of file
+pub mod purse_events;
+pub mod purse_executor;
+pub mod purse_offline;
+pub mod purse_permit;
+pub mod purse_roles;
+pub mod purse_safe;
+pub mod purse_upgrade;
\ No newline at end of file
@@ -0,0 +1,161 @@
+use eyre::Result;
+use ethers::{
+    abi::{
+        token::{LenientTokenizer, Tokenizer},
+        Abi, AbiParser, Function, Token,
+    },
+    types::Bytes,
+};
+use crate::contract::purse_contract::PURSE404_ABI;
+
+/// Loads the ABI to encode and decode calldata with, the Purse404 ABI if no file path is given.
+/// The file may hold the ABI JSON array, or a Foundry or Hardhat artifact with an `abi` field.
+/// ### Arguments
+/// * `file_path` - File path of the ABI, `None` for the Purse404 ABI
+/// 
+/// ### Returns
+/// * `Result<Abi>` - The ABI
+pub fn load_abi(file_path: Option<&str>) -> Result<Abi> {
+    let file_path = match file_path {
+        Some(file_path) => file_path,
+        None => return Ok(PURSE404_ABI.clone()),
+    };
+    let content = std::fs::read_to_string(file_path)?;
+    let json: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| eyre::eyre!("Invalid ABI file {}: {}", file_path, e))?;
+    let abi = match json.get("abi") {
+        Some(abi) => abi.clone(),
+        None => json,
+    };
+    serde_json::from_value(abi).map_err(|e| eyre::eyre!("Invalid ABI in {}: {}", file_path, e))
+}
+
+/// Returns the signature of the function, eg: "transfer(address,uint256)"
+pub fn function_signature(function: &Function) -> String {
+    let types: Vec<String> = function.inputs.iter().map(|param| param.kind.to_string()).collect();
+    format!("{}({})", function.name, types.join(","))
+}
+
+/// Finds the function to encode calldata for.
+/// A full signature, eg: "safeTransferFrom(address,address,uint256)", selects an overload,
+/// and is used as is if the ABI does not have it. A name selects the function with that many arguments.
+/// ### Arguments
+/// * `abi` - ABI, see `load_abi`
+/// * `function` - Function name or signature
+/// * `arg_count` - Number of arguments given
+/// 
+/// ### Returns
+/// * `Result<Function>` - The function
+pub fn find_function(abi: &Abi, function: &str, arg_count: usize) -> Result<Function> {
+    if function.contains('(') {
+        let parsed = AbiParser::default()
+            .parse_function(function.trim_start_matches("function "))
+            .map_err(|e| eyre::eyre!("Invalid function signature {}: {}", function, e))?;
+        let found = abi.functions().find(|f| f.short_signature() == parsed.short_signature());
+        return Ok(found.cloned().unwrap_or(parsed))
+    }
+
+    let overloads = abi
+        .functions_by_name(function)
+        .map_err(|_| eyre::eyre!("Unknown function: {}", function))?;
+    let matching: Vec<&Function> = overloads.iter().filter(|f| f.inputs.len() == arg_count).collect();
+    match matching.as_slice() {
+        [found] => Ok((*found).clone()),
+        [] => {
+            let signatures: Vec<String> = overloads.iter().map(function_signature).collect();
+            Err(eyre::eyre!(
+                "{} does not take {} argument(s), known signatures: {}",
+                function,
+                arg_count,
+                signatures.join(", ")
+            ))
+        },
+        _ => {
+            let signatures: Vec<String> = matching.iter().map(|f| function_signature(f)).collect();
+            Err(eyre::eyre!(
+                "{} is overloaded with {} argument(s), pass the full signature, one of: {}",
+                function,
+                arg_count,
+                signatures.join(", ")
+            ))
+        }
+    }
+}
+
+/// Encodes calldata for the function from command line arguments.
+/// Integers are decimal, addresses and bytes 0x prefixed hex, and arrays and tuples bracketed, eg: "[1,2]"
+/// ### Arguments
+/// * `function` - Function, see `find_function`
+/// * `args` - Arguments, in the order of the function inputs
+/// 
+/// ### Returns
+/// * `Result<Bytes>` - The calldata, starting with the function selector
+pub fn encode_calldata(function: &Function, args: &[String]) -> Result<Bytes> {
+    if function.inputs.len() != args.len() {
+        return Err(eyre::eyre!(
+            "{} takes {} argument(s), {} given",
+            function_signature(function),
+            function.inputs.len(),
+            args.len()
+        ))
+    }
+    let tokens = function
+        .inputs
+        .iter()
+        .zip(args.iter())
+        .map(|(param, arg)| LenientTokenizer::tokenize(&param.kind, arg)
+            .map_err(|e| eyre::eyre!("Invalid {} argument {}: {}", param.kind, arg, e)))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(function.encode_input(&tokens)?.into())
+}
+
+/// Decodes calldata against the ABI, the function is found by its 4-byte selector
+/// ### Arguments
+/// * `abi` - ABI, see `load_abi`
+/// * `data` - Calldata
+/// 
+/// ### Returns
+/// * `Result<(Function, Vec<Token>)>` - The function and its decoded arguments
+pub fn decode_calldata(abi: &Abi, data: &[u8]) -> Result<(Function, Vec<Token>)> {
+    if data.len() < 4 {
+        return Err(eyre::eyre!("Calldata is too short to contain a function selector"))
+    }
+    let function = abi
+        .functions()
+        .find(|f| f.short_signature() == data[..4])
+        .ok_or_else(|| eyre::eyre!("Unknown function selector: 0x{}", ethers::utils::hex::encode(&data[..4])))?;
+    let tokens = function.decode_input(&data[4..])?;
+
+    Ok((function.clone(), tokens))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::token_to_string;
+
+    #[test]
+    fn test_encode_decode_calldata() {
+        let abi = load_abi(None).unwrap();
+        let args = vec![
+            "0x2222222222222222222222222222222222222222".to_string(),
+            "1000000000000000000".to_string(),
+        ];
+        let transfer = find_function(&abi, "transfer", 2).unwrap();
+        let data = encode_calldata(&transfer, &args).unwrap();
+        assert_eq!(data[..4], [0xa9, 0x05, 0x9c, 0xbb]);
+
+        let (function, tokens) = decode_calldata(&abi, &data).unwrap();
+        assert_eq!(function_signature(&function), "transfer(address,uint256)");
+        assert_eq!(tokens.iter().map(token_to_string).collect::<Vec<_>>(), args);
+
+        // Overloads are selected by argument count, or by signature
+        let safe_transfer = find_function(&abi, "safeTransferFrom", 4).unwrap();
+        assert_eq!(function_signature(&safe_transfer), "safeTransferFrom(address,address,uint256,bytes)");
+        let safe_transfer = find_function(&abi, "safeTransferFrom(address,address,uint256)", 3).unwrap();
+        assert_eq!(safe_transfer.inputs.len(), 3);
+        assert!(find_function(&abi, "transfer", 3).is_err());
+        assert!(encode_calldata(&transfer, &args[..1]).is_err());
+    }
+}
@@ -1,35 +1,102 @@
 use eyre::Result;
-use std::sync::Arc;
+use std::{str::FromStr, sync::Arc};
 use ethers::{
+    abi::{AbiDecode, Token},
     prelude::SignerMiddleware, 
-    providers::Middleware, 
-    types::{ Address, U256 },
-    contract::abigen
+    providers::{Middleware, PendingTransaction}, 
+    signers::Signer,
+    types::{
+        Address, BlockId, BlockNumber, Bytes, H256, U256,
+        transaction::{eip2718::TypedTransaction, eip712::EIP712Domain},
+    },
+    contract::{abigen, ContractError, Multicall}
 };
 use crate::utils::{
     get_tx_hash, 
     get_gas_price, 
     get_gas_used,
     calc_tx_fee,
+    get_multicall_address,
+    get_native_balance_at,
+    unix_timestamp,
 };
 use crate::wallet::Wallet;
+use crate::contract::{
+    purse_permit::SignedPermit,
+    purse_roles::Role,
+    purse_upgrade::ERC1967_IMPLEMENTATION_SLOT,
+};
+use crate::journal::{
+    Journal, 
+    JournalEntry, 
+    JournalStatus,
+};
 
 abigen!(
     Purse404,
     "abi/purseTokenAbi.json",
 );
 
+abigen!(
+    IERC20,
+    r#"[function balanceOf(address account) external view returns (uint256)]"#,
+);
+
+/// A signed transaction, ready to be broadcast
+/// With traits `Clone` and `Debug`
+#[derive(Clone, Debug)]
+pub struct SignedTransaction {
+    /// Address of the signing wallet
+    pub from: Address,
+    /// Derivation number of the signing wallet
+    pub derivation: u32,
+    /// Nonce of the transaction
+    pub nonce: U256,
+    /// Chain Id the transaction was signed for
+    pub chain_id: u64,
+    /// Transaction hash
+    pub hash: H256,
+    /// Raw RLP encoded signed transaction
+    pub raw: Bytes,
+}
+
+/// Maximum number of addresses read per Multicall3 `eth_call`, keeps the call under node gas caps
+const MULTICALL_BATCH_SIZE: usize = 50;
+
+/// Balances of an address, read in one batch
+/// With traits `Clone`, `Debug`, `Default` and `PartialEq`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccountBalances {
+    pub address: Address,
+    /// Native balance
+    pub eth: U256,
+    /// ERC20 balance (`balanceOf`)
+    pub erc20: U256,
+    /// ERC20 balance backing owned NFTs (`activeBalance`)
+    pub active: U256,
+    /// ERC20 balance not backing NFTs (`inactiveBalance`)
+    pub inactive: U256,
+    /// Number of owned NFTs (`erc721BalanceOf`)
+    pub erc721: U256,
+    /// Owned NFT token IDs (`owned`), only if requested
+    pub owned: Option<Vec<U256>>,
+}
+
 /// Wrapper around Purse404 contract
 /// With traits `Clone` and `Debug`
 /// Fields:
 /// * `address` - Address in `Address` type
 /// * `contract` - Purse404 contract instance
 /// * `provider` - Provider
+/// * `journal` - Write-ahead journal for sent transactions (optional)
+/// * `multicall` - Address of the Multicall3 contract for batched reads (optional, see `get_multicall_address`)
 #[derive(Clone, Debug)]
 pub struct Purse404Contract<M: Middleware + 'static> {
     address: Address,
     contract: Purse404<M>,
     provider: Arc<M>,
+    journal: Option<Journal>,
+    multicall: Option<Address>,
 }
 
 impl<M: Middleware + 'static> Purse404Contract<M> {
@@ -45,7 +112,30 @@ impl<M: Middleware + 'static> Purse404Contract<M> {
             address, 
             Arc::clone(provider)
         );
-        Self { address, contract, provider: Arc::clone(provider) }
+        Self { address, contract, provider: Arc::clone(provider), journal: None, multicall: None }
+    }
+
+    /// Journal every transaction sent through this instance before it is broadcast
+    /// ### Arguments
+    /// * `journal` - Write-ahead journal
+    /// 
+    /// ### Returns
+    /// * `Self` - The `Purse404Contract` instance, with the journal set
+    pub fn with_journal(mut self, journal: Journal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Batch reads through the Multicall3 contract at the given address,
+    /// instead of the one configured in the environment
+    /// ### Arguments
+    /// * `multicall` - Address of the Multicall3 contract
+    /// 
+    /// ### Returns
+    /// * `Self` - The `Purse404Contract` instance, with the Multicall3 address set
+    pub fn with_multicall(mut self, multicall: Address) -> Self {
+        self.multicall = Some(multicall);
+        self
     }
 
     /// Returns the address of the contract: `Address`
@@ -76,6 +166,21 @@ impl<M: Middleware + 'static> Purse404Contract<M> {
         }
     }
 
+    /// Gets the balance of the given address, at the given block
+    /// ### Arguments
+    /// * `addr` - Address
+    /// * `block` - Block to read the balance at
+    /// 
+    /// ### Returns
+    /// * `Result<U256>` - A `U256` type
+    pub async fn balance_of_at(&self, addr: &Address, block: BlockId) -> Result<U256> {
+        let res = self.contract.balance_of(*addr).block(block).call().await;
+        match res {
+            Ok(balance) => Ok(balance),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
     /// Gets the current minted NFT amount
     /// 
     /// ### Returns
@@ -100,191 +205,1277 @@ impl<M: Middleware + 'static> Purse404Contract<M> {
         }
     }
 
-    /// Retrieves all NFT token IDs owned by the given address
+    /// Gets the ERC20 balance of the given address that does not back owned NFTs,
+    /// which is what `mintERC721` converts into NFTs
     /// ### Arguments
-    /// * `owner` - an `Address` reference
+    /// * `addr` - Address
     /// 
     /// ### Returns
-    /// * `Result<Vec<U256>>` - A vector of `U256` types
-    pub async fn owned(&self, owner: &Address) -> Result<Vec<U256>> {
-        let res = self.contract.owned(*owner).call().await;
+    /// * `Result<U256>` - A `U256` type
+    pub async fn inactive_balance(&self, addr: &Address) -> Result<U256> {
+        let res = self.contract.inactive_balance(*addr).call().await;
         match res {
-            Ok(owned) => Ok(owned),
+            Ok(balance) => Ok(balance),
             Err(e) => Err(eyre::Report::from(e))
         }
     }
 
-    /// Transfer the given amount (ERC20), from a `Wallet` to the given address.
+    /// Gets whether the contract is paused
+    /// 
+    /// ### Returns
+    /// * `Result<bool>` - `true` if paused
+    pub async fn paused(&self) -> Result<bool> {
+        let res = self.contract.paused().call().await;
+        match res {
+            Ok(paused) => Ok(paused),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Gets the treasury address, receiving the minting costs
+    /// 
+    /// ### Returns
+    /// * `Result<Address>` - The treasury address
+    pub async fn treasury(&self) -> Result<Address> {
+        let res = self.contract.treasury().call().await;
+        match res {
+            Ok(treasury) => Ok(treasury),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Gets the base URI of the NFT metadata
+    /// 
+    /// ### Returns
+    /// * `Result<String>` - The base token URI
+    pub async fn base_token_uri(&self) -> Result<String> {
+        let res = self.contract.base_token_uri().call().await;
+        match res {
+            Ok(uri) => Ok(uri),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Gets the ERC20 balance of the owner in the given token contract, eg: a token sent to this contract by mistake
     /// ### Arguments
-    /// * `from` - a `Wallet` reference, the sender of the transfer
-    /// * `to` - an `Address` reference, the recipient of the transfer
-    /// * `amount` - a `U256` reference, the amount to transfer
+    /// * `token` - Address of the ERC20 token contract
+    /// * `owner` - Owner of the tokens
     /// 
     /// ### Returns
-    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, 
-    /// gas price, gas used, transaction fees, and transaction receipt JSON
-    pub async fn transfer(
-        &self, 
-        from: &Wallet, 
-        to_address: &Address, 
-        amount: &U256
-    ) -> Result<(String, String, String, String, String)> {
-        let signer_middleware = SignerMiddleware::new(
-            self.provider.clone(),
-            from.signer.clone()
-        );
-        let contract_with_signer = Purse404::new(
-            self.address.clone(),
-            Arc::new(signer_middleware)
-        );
+    /// * `Result<U256>` - A `U256` type
+    pub async fn token_balance_of(&self, token: &Address, owner: &Address) -> Result<U256> {
+        let res = IERC20::new(*token, self.provider.clone()).balance_of(*owner).call().await;
+        match res {
+            Ok(balance) => Ok(balance),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
 
-        let tx = contract_with_signer.transfer(*to_address, *amount);
-        let pending_tx = match tx.send().await {
-            Ok(pending_tx) => {
-                println!(
-                    "Transaction sent, from: {:?}, to: {:?}, amount (wei): {} \n", 
-                    from.address(), 
-                    to_address, 
-                    amount
-                );
-                println!("Waiting...");
-                pending_tx
-            },
-            Err(e) => {
-                return Err(eyre::eyre!("Failed to send transaction: {}", e))
-            }
-        };
-        let receipt = match pending_tx.await {
-            Ok(receipt) => receipt,
-            Err(e) => {
-                return Err(eyre::eyre!("Unexpected error occurred: {}", e))
-            }
-        };
+    /// Gets the implementation the proxy delegates to, from its ERC1967 storage slot
+    /// 
+    /// ### Returns
+    /// * `Result<Address>` - The implementation address
+    pub async fn implementation(&self) -> Result<Address> {
+        let slot = H256::from_str(ERC1967_IMPLEMENTATION_SLOT)?;
+        let res = self.provider.get_storage_at(self.address, slot, None).await;
+        match res {
+            Ok(value) => Ok(Address::from(value)),
+            Err(e) => Err(eyre::eyre!("Failed to read the implementation slot: {}", e))
+        }
+    }
 
-        let json_str = serde_json::to_string(&receipt)?;
-        let tx_hash = get_tx_hash(&json_str);
-        let gas_price = get_gas_price(&json_str);
-        let gas_used = get_gas_used(&json_str);
-        let tx_fee = calc_tx_fee(&json_str);
-        
-        println!("Transaction hash: {}", tx_hash);
-        println!("Gas price (gwei): {}", gas_price);
-        println!("Gas used: {}", gas_used);
-        println!("Transaction fee (ETH): {}", tx_fee);
-        println!("Transfer transaction receipt: {} \n", json_str);
+    /// Gets the `proxiableUUID` of the given implementation. It is read from the implementation itself,
+    /// as the contract refuses the call through the proxy (`UUPSUnauthorizedCallContext`).
+    /// ### Arguments
+    /// * `implementation` - Address of the implementation
+    /// 
+    /// ### Returns
+    /// * `Result<[u8; 32]>` - The storage slot the implementation upgrades, see `ERC1967_IMPLEMENTATION_SLOT`
+    pub async fn proxiable_uuid_of(&self, implementation: &Address) -> Result<[u8; 32]> {
+        let res = Purse404::new(*implementation, self.provider.clone()).proxiable_uuid().call().await;
+        match res {
+            Ok(uuid) => Ok(uuid),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
 
-        Ok((tx_hash, gas_price, gas_used, tx_fee, json_str))
+    /// Gets the UUPS upgrade interface version (`UPGRADE_INTERFACE_VERSION`)
+    /// 
+    /// ### Returns
+    /// * `Result<String>` - The interface version, eg: "5.0.0"
+    pub async fn upgrade_interface_version(&self) -> Result<String> {
+        let res = self.contract.upgrade_interface_version().call().await;
+        match res {
+            Ok(version) => Ok(version),
+            Err(e) => Err(eyre::Report::from(e))
+        }
     }
 
-    /// Mint ERC721 token(s) to the given wallet.
+    /// Gets whether the account holds the given role
     /// ### Arguments
-    /// * `mint_to` - a `Wallet` reference, the sender of the transaction
-    /// * `mint_unit` - a `U256` reference, the amount to mint (treated as integer)
-    /// * `message_value` - a `U256` reference, the msg value to send with the transaction
+    /// * `role` - AccessControl role
+    /// * `account` - Account
     /// 
     /// ### Returns
-    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, 
-    /// gas price, gas used, transaction fees, and transaction receipt JSON
-    pub async fn mint_erc721(
-        &self,
-        mint_to: &Wallet, 
-        mint_units: &U256,
-        message_value: &U256 
-    ) -> Result<(String, String, String, String, String)> {
-        let signer_middleware = SignerMiddleware::new(
-            self.provider.clone(), 
-            mint_to.signer.clone()
-        );
-        let contract_with_signer = Purse404::new(
-            self.address.clone(), 
-            Arc::new(signer_middleware)
-        );
+    /// * `Result<bool>` - `true` if the account holds the role
+    pub async fn has_role(&self, role: Role, account: &Address) -> Result<bool> {
+        let res = self.contract.has_role(role.id(), *account).call().await;
+        match res {
+            Ok(has_role) => Ok(has_role),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
 
-        let tx = contract_with_signer.mint_erc721(*mint_units).value(*message_value);
-        let pending_tx = match tx.send().await {
-            Ok(pending_tx) => {
-                println!(
-                    "Transaction sent, from: {}, to: {}, amount (nfts): {} \n", 
-                    mint_to.address(), 
-                    self.address(), 
-                    mint_units
-                );
-                println!("Waiting...");
-                pending_tx
-            },
-            Err(e) => {
-                return Err(eyre::eyre!("Failed to send transaction: {}", e))
-            }
-        };
-        let receipt = match pending_tx.await {
-            Ok(receipt) => receipt,
-            Err(e) => {
-                return Err(eyre::eyre!("Unexpected error occurred: {}", e))
-            }
-        };
+    /// Gets the identifier of the role that administers the given role
+    /// ### Arguments
+    /// * `role` - AccessControl role
+    /// 
+    /// ### Returns
+    /// * `Result<[u8; 32]>` - The admin role identifier, see `Role::from_id`
+    pub async fn get_role_admin(&self, role: Role) -> Result<[u8; 32]> {
+        let res = self.contract.get_role_admin(role.id()).call().await;
+        match res {
+            Ok(admin_role) => Ok(admin_role),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
 
-        let json_str = serde_json::to_string(&receipt)?;
-        let tx_hash = get_tx_hash(&json_str);
-        let gas_price = get_gas_price(&json_str);
-        let gas_used = get_gas_used(&json_str);
-        let tx_fee = calc_tx_fee(&json_str);
+    /// Checks that the account holds the role a call is gated by,
+    /// so the call fails before it is sent instead of reverting with `AccessControlUnauthorizedAccount`
+    /// ### Arguments
+    /// * `role` - AccessControl role
+    /// * `account` - Account making the call
+    /// * `func` - Name of the gated function, for the error message
+    /// 
+    /// ### Returns
+    /// * `Result<()>` - Result
+    pub async fn require_role(&self, role: Role, account: &Address, func: &str) -> Result<()> {
+        if self.has_role(role, account).await? {
+            return Ok(())
+        }
+        Err(eyre::eyre!(
+            "> {}: {:?} does not hold {}, the transaction would revert with AccessControlUnauthorizedAccount()",
+            func,
+            account,
+            role
+        ))
+    }
 
-        println!("Transaction hash: {}", tx_hash);
-        println!("Gas price (gwei): {}", gas_price);
-        println!("Gas used: {}", gas_used);
-        println!("Transaction fee (ETH): {}", tx_fee);
-        println!("Transfer transaction receipt: {} \n", json_str);
+    /// Gets the decimals of the ERC20 amounts
+    /// 
+    /// ### Returns
+    /// * `Result<u8>` - The decimals
+    pub async fn decimals(&self) -> Result<u8> {
+        let res = self.contract.decimals().call().await;
+        match res {
+            Ok(decimals) => Ok(decimals),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
 
-        Ok((tx_hash, gas_price, gas_used, tx_fee, json_str))
+    /// Gets the symbol of the token
+    /// 
+    /// ### Returns
+    /// * `Result<String>` - The symbol
+    pub async fn symbol(&self) -> Result<String> {
+        let res = self.contract.symbol().call().await;
+        match res {
+            Ok(symbol) => Ok(symbol),
+            Err(e) => Err(eyre::Report::from(e))
+        }
     }
 
-    /// Mint ERC20 token(s) to an authorized address.
+    /// Gets the ERC20 amount that backs one NFT
+    /// 
+    /// ### Returns
+    /// * `Result<U256>` - A `U256` type
+    pub async fn units(&self) -> Result<U256> {
+        let res = self.contract.units().call().await;
+        match res {
+            Ok(units) => Ok(units),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Gets the number of NFTs in existence
+    /// 
+    /// ### Returns
+    /// * `Result<U256>` - A `U256` type
+    pub async fn erc721_total_supply(&self) -> Result<U256> {
+        let res = self.contract.erc_721_total_supply().call().await;
+        match res {
+            Ok(supply) => Ok(supply),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Gets the highest NFT token ID that can be minted
+    /// 
+    /// ### Returns
+    /// * `Result<U256>` - A `U256` type
+    pub async fn erc721_max_token_id(&self) -> Result<U256> {
+        let res = self.contract.erc_721_max_token_id().call().await;
+        match res {
+            Ok(max_id) => Ok(max_id),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Gets the number of burned NFT token IDs queued for reuse
+    /// 
+    /// ### Returns
+    /// * `Result<U256>` - A `U256` type
+    pub async fn erc721_queue_length(&self) -> Result<U256> {
+        let res = self.contract.get_erc721_queue_length().call().await;
+        match res {
+            Ok(length) => Ok(length),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Retrieves a page of the NFT token IDs queued for reuse
     /// ### Arguments
-    /// * `sender` - a `Wallet` reference, the msg.sender of the mint transaction.
-    /// * `to_address` - an `Address` reference, the address to mint the tokens to.
-    /// Note that if the wallet is not authorized, the transaction will fail.
-    /// * `amount` - a `U256` reference, the amount to mint
+    /// * `start` - Position in the queue of the first token ID
+    /// * `count` - Number of token IDs, must not go past the end of the queue
     /// 
     /// ### Returns
-    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, 
-    /// gas price, gas used, transaction fees, and transaction receipt JSON
-    pub async fn mint(
-        &self,
-        sender: &Wallet,
-        to_address: &Address,
-        amount: &U256
-    ) -> Result<(String, String, String, String, String)> {
-        let signer_middleware = SignerMiddleware::new(
-            self.provider.clone(), 
-            sender.signer.clone()
-        );
-        let contract_with_signer = Purse404::new(
-            self.address.clone(), 
-            Arc::new(signer_middleware)
-        );
+    /// * `Result<Vec<U256>>` - A vector of `U256` types
+    pub async fn erc721_tokens_in_queue(&self, start: U256, count: U256) -> Result<Vec<U256>> {
+        let res = self.contract.get_erc721_tokens_in_queue(start, count).call().await;
+        match res {
+            Ok(ids) => Ok(ids),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Retrieves all NFT token IDs owned by the given address
+    /// ### Arguments
+    /// * `owner` - an `Address` reference
+    /// 
+    /// ### Returns
+    /// * `Result<Vec<U256>>` - A vector of `U256` types
+    pub async fn owned(&self, owner: &Address) -> Result<Vec<U256>> {
+        let res = self.contract.owned(*owner).call().await;
+        match res {
+            Ok(owned) => Ok(owned),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Retrieves all NFT token IDs owned by the given address, at the given block
+    /// ### Arguments
+    /// * `owner` - an `Address` reference
+    /// * `block` - Block to read the token IDs at
+    /// 
+    /// ### Returns
+    /// * `Result<Vec<U256>>` - A vector of `U256` types
+    pub async fn owned_at(&self, owner: &Address, block: BlockId) -> Result<Vec<U256>> {
+        let res = self.contract.owned(*owner).block(block).call().await;
+        match res {
+            Ok(owned) => Ok(owned),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Reads the balances of the given addresses, at the given (or latest) block.
+    /// The reads are batched through Multicall3, in one `eth_call` per 50 addresses.
+    /// If no Multicall3 contract is deployed at the configured address, eg: on a local devnet,
+    /// every value is read with its own call instead.
+    /// ### Arguments
+    /// * `addresses` - Addresses to read the balances of
+    /// * `block` - Block to read the balances at, defaults to the latest block
+    /// * `with_owned` - Also read the owned NFT token IDs
+    /// 
+    /// ### Returns
+    /// * `Result<(Vec<AccountBalances>, bool)>` - The balances, in the order of the addresses,
+    /// and `true` if they were read one call at a time because no Multicall3 contract is deployed
+    pub async fn balances_at(
+        &self,
+        addresses: &[Address],
+        block: Option<u64>,
+        with_owned: bool
+    ) -> Result<(Vec<AccountBalances>, bool)> {
+        let multicall_address = match self.multicall {
+            Some(address) => address,
+            None => get_multicall_address()?,
+        };
+        let mut balances = Vec::with_capacity(addresses.len());
+        let mut without_multicall = false;
+        for batch in addresses.chunks(MULTICALL_BATCH_SIZE) {
+            match self.multicall_balances(multicall_address, batch, block, with_owned).await {
+                Ok(batch_balances) => balances.extend(batch_balances),
+                Err(e) => {
+                    let block_id = block.map(BlockId::from);
+                    let code = self.provider
+                        .get_code(multicall_address, block_id)
+                        .await
+                        .map_err(|e| eyre::eyre!("Failed to get code of {:?}: {}", multicall_address, e))?;
+                    if !code.is_empty() {
+                        return Err(e)
+                    }
+                    without_multicall = true;
+                    for address in batch {
+                        balances.push(self.single_balances(address, block_id, with_owned).await?);
+                    }
+                }
+            }
+        }
+        Ok((balances, without_multicall))
+    }
+
+    /// Reads the balances of the given addresses in one Multicall3 `eth_call`
+    async fn multicall_balances(
+        &self,
+        multicall_address: Address,
+        addresses: &[Address],
+        block: Option<u64>,
+        with_owned: bool
+    ) -> Result<Vec<AccountBalances>> {
+        let mut multicall = Multicall::new(self.provider.clone(), Some(multicall_address))
+            .await
+            .map_err(|e| eyre::eyre!("Failed to create multicall: {}", e))?;
+        if let Some(block) = block {
+            multicall = multicall.block(BlockNumber::Number(block.into()));
+        }
+        for address in addresses {
+            multicall
+                .add_get_eth_balance(*address, false)
+                .add_call(self.contract.balance_of(*address), false)
+                .add_call(self.contract.active_balance(*address), false)
+                .add_call(self.contract.inactive_balance(*address), false)
+                .add_call(self.contract.erc_721_balance_of(*address), false);
+            if with_owned {
+                multicall.add_call(self.contract.owned(*address), false);
+            }
+        }
+        let tokens = multicall
+            .call_raw()
+            .await
+            .map_err(|e| eyre::eyre!("Multicall failed: {}", e))?;
+
+        let values_per_address = if with_owned { 6 } else { 5 };
+        let uint = |i: usize| match tokens.get(i) {
+            Some(Ok(Token::Uint(value))) => Ok(*value),
+            other => Err(eyre::eyre!("Unexpected multicall result: {:?}", other)),
+        };
+        let mut balances = Vec::with_capacity(addresses.len());
+        for (i, address) in addresses.iter().enumerate() {
+            let base = i * values_per_address;
+            let owned = match (with_owned, tokens.get(base + 5)) {
+                (false, _) => None,
+                (true, Some(Ok(Token::Array(ids)))) => Some(ids
+                    .iter()
+                    .filter_map(|id| id.clone().into_uint())
+                    .collect()),
+                (true, other) => return Err(eyre::eyre!("Unexpected multicall result: {:?}", other)),
+            };
+            balances.push(AccountBalances {
+                address: *address,
+                eth: uint(base)?,
+                erc20: uint(base + 1)?,
+                active: uint(base + 2)?,
+                inactive: uint(base + 3)?,
+                erc721: uint(base + 4)?,
+                owned,
+            });
+        }
+        Ok(balances)
+    }
+
+    /// Reads the balances of the given address, one call per value
+    async fn single_balances(
+        &self,
+        address: &Address,
+        block: Option<BlockId>,
+        with_owned: bool
+    ) -> Result<AccountBalances> {
+        let block = block.unwrap_or(BlockId::Number(BlockNumber::Latest));
+        let owned = match with_owned {
+            true => Some(self.owned_at(address, block).await?),
+            false => None,
+        };
+        Ok(AccountBalances {
+            address: *address,
+            eth: get_native_balance_at(self.provider.as_ref(), address, block).await?,
+            erc20: self.balance_of_at(address, block).await?,
+            active: self.contract.active_balance(*address).block(block).call().await?,
+            inactive: self.contract.inactive_balance(*address).block(block).call().await?,
+            erc721: self.contract.erc_721_balance_of(*address).block(block).call().await?,
+            owned,
+        })
+    }
+
+    /// Gets the owner of the given NFT token ID
+    /// ### Arguments
+    /// * `id` - NFT token ID
+    /// 
+    /// ### Returns
+    /// * `Result<Address>` - The owner
+    pub async fn owner_of(&self, id: &U256) -> Result<Address> {
+        let res = self.contract.owner_of(*id).call().await;
+        match res {
+            Ok(owner) => Ok(owner),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Gets the URI of the metadata of the given NFT token ID
+    /// ### Arguments
+    /// * `id` - NFT token ID
+    /// 
+    /// ### Returns
+    /// * `Result<String>` - The token URI
+    pub async fn token_uri(&self, id: &U256) -> Result<String> {
+        let res = self.contract.token_uri(*id).call().await;
+        match res {
+            Ok(uri) => Ok(uri),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Gets the address approved to transfer the given NFT token ID
+    /// ### Arguments
+    /// * `id` - NFT token ID
+    /// 
+    /// ### Returns
+    /// * `Result<Address>` - The approved address, zero if none
+    pub async fn get_approved(&self, id: &U256) -> Result<Address> {
+        let res = self.contract.get_approved(*id).call().await;
+        match res {
+            Ok(approved) => Ok(approved),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Gets whether the operator may transfer every NFT of the owner
+    /// ### Arguments
+    /// * `owner` - Owner of the NFTs
+    /// * `operator` - Operator
+    /// 
+    /// ### Returns
+    /// * `Result<bool>` - `true` if approved
+    pub async fn is_approved_for_all(&self, owner: &Address, operator: &Address) -> Result<bool> {
+        let res = self.contract.is_approved_for_all(*owner, *operator).call().await;
+        match res {
+            Ok(approved) => Ok(approved),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Gets the ERC20 amount the spender may spend from the owner, at the given (or latest) block
+    /// ### Arguments
+    /// * `owner` - Owner of the tokens
+    /// * `spender` - Spender
+    /// * `block` - Block to read the allowance at, defaults to the latest block
+    /// 
+    /// ### Returns
+    /// * `Result<U256>` - A `U256` type
+    pub async fn allowance(&self, owner: &Address, spender: &Address, block: Option<BlockId>) -> Result<U256> {
+        let mut call = self.contract.allowance(*owner, *spender);
+        if let Some(block) = block {
+            call = call.block(block);
+        }
+        match call.call().await {
+            Ok(allowance) => Ok(allowance),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Gets the current EIP-2612 permit nonce of the owner
+    /// ### Arguments
+    /// * `owner` - Owner of the tokens
+    /// 
+    /// ### Returns
+    /// * `Result<U256>` - A `U256` type
+    pub async fn nonces(&self, owner: &Address) -> Result<U256> {
+        let res = self.contract.nonces(*owner).call().await;
+        match res {
+            Ok(nonce) => Ok(nonce),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Gets the EIP-712 domain separator (`DOMAIN_SEPARATOR`)
+    /// ### Returns
+    /// * `Result<[u8; 32]>` - The domain separator
+    pub async fn domain_separator(&self) -> Result<[u8; 32]> {
+        let res = self.contract.domain_separator().call().await;
+        match res {
+            Ok(separator) => Ok(separator),
+            Err(e) => Err(eyre::Report::from(e))
+        }
+    }
+
+    /// Gets the EIP-712 domain of the contract (EIP-5267 `eip712Domain`).
+    /// Only the fields flagged as used by the contract are set.
+    /// ### Returns
+    /// * `Result<EIP712Domain>` - The signing domain
+    pub async fn eip712_domain(&self) -> Result<EIP712Domain> {
+        let (fields, name, version, chain_id, verifying_contract, salt, _extensions) = match self.contract.eip_712_domain().call().await {
+            Ok(domain) => domain,
+            Err(e) => return Err(eyre::Report::from(e))
+        };
+        let used = |bit: u8| fields[0] & (1 << bit) != 0;
+
+        Ok(EIP712Domain {
+            name: used(0).then_some(name),
+            version: used(1).then_some(version),
+            chain_id: used(2).then_some(chain_id),
+            verifying_contract: used(3).then_some(verifying_contract),
+            salt: used(4).then_some(salt),
+        })
+    }
+
+    /// Transfer the given amount (ERC20), from a `Wallet` to the given address.
+    /// ### Arguments
+    /// * `from` - a `Wallet` reference, the sender of the transfer
+    /// * `to` - an `Address` reference, the recipient of the transfer
+    /// * `amount` - a `U256` reference, the amount to transfer
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, 
+    /// gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn transfer(
+        &self, 
+        from: &Wallet, 
+        to_address: &Address, 
+        amount: &U256
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.transfer(*to_address, *amount).tx;
+        let signed = self.sign_transaction(from, tx).await?;
+        let pending_tx = self.broadcast(&signed).await?;
+        println!(
+            "Transaction sent, from: {:?}, to: {:?}, amount (wei): {} \n", 
+            from.address(), 
+            to_address, 
+            amount
+        );
+        println!("Waiting...");
+
+        self.confirm(&signed, pending_tx).await
+    }
+
+    /// Mint ERC721 token(s) to the given wallet.
+    /// ### Arguments
+    /// * `mint_to` - a `Wallet` reference, the sender of the transaction
+    /// * `mint_unit` - a `U256` reference, the amount to mint (treated as integer)
+    /// * `message_value` - a `U256` reference, the msg value to send with the transaction
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, 
+    /// gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn mint_erc721(
+        &self,
+        mint_to: &Wallet, 
+        mint_units: &U256,
+        message_value: &U256 
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.mint_erc721(*mint_units).value(*message_value).tx;
+        let signed = self.sign_transaction(mint_to, tx).await?;
+        let pending_tx = self.broadcast(&signed).await?;
+        println!(
+            "Transaction sent, from: {}, to: {}, amount (nfts): {} \n", 
+            mint_to.address(), 
+            self.address(), 
+            mint_units
+        );
+        println!("Waiting...");
+
+        self.confirm(&signed, pending_tx).await
+    }
+
+    /// Mint ERC20 token(s) to an authorized address.
+    /// ### Arguments
+    /// * `sender` - a `Wallet` reference, the msg.sender of the mint transaction.
+    /// * `to_address` - an `Address` reference, the address to mint the tokens to.
+    /// Note that if the wallet is not authorized, the transaction will fail.
+    /// * `amount` - a `U256` reference, the amount to mint
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, 
+    /// gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn mint(
+        &self,
+        sender: &Wallet,
+        to_address: &Address,
+        amount: &U256
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.mint(*to_address, *amount).tx;
+        let signed = self.sign_transaction(sender, tx).await?;
+        let pending_tx = self.broadcast(&signed).await?;
+        println!(
+            "Transaction sent, from: {}, to: {}, amount (wei): {} \n", 
+            to_address, 
+            self.address(), 
+            amount
+        );
+        println!("Waiting...");
+
+        self.confirm(&signed, pending_tx).await
+    }
+
+    /// Transfer the given NFT token ID (ERC721), from the owner to the given address.
+    /// ### Arguments
+    /// * `sender` - a `Wallet` reference, the owner or an approved operator
+    /// * `from_address` - an `Address` reference, the owner of the NFT
+    /// * `to_address` - an `Address` reference, the recipient of the NFT
+    /// * `id` - a `U256` reference, the NFT token ID
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn erc721_transfer_from(
+        &self,
+        sender: &Wallet,
+        from_address: &Address,
+        to_address: &Address,
+        id: &U256
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.erc_721_transfer_from(*from_address, *to_address, *id).tx;
+        self.send_erc721_transfer(sender, from_address, to_address, id, tx).await
+    }
+
+    /// Safely transfer the given NFT token ID (ERC721), from the owner to the given address.
+    /// Reverts if the recipient is a contract that does not accept ERC721 tokens.
+    /// ### Arguments
+    /// * `sender` - a `Wallet` reference, the owner or an approved operator
+    /// * `from_address` - an `Address` reference, the owner of the NFT
+    /// * `to_address` - an `Address` reference, the recipient of the NFT
+    /// * `id` - a `U256` reference, the NFT token ID
+    /// * `data` - data passed to the recipient contract, uses the overload without data if `None`
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn safe_transfer_from(
+        &self,
+        sender: &Wallet,
+        from_address: &Address,
+        to_address: &Address,
+        id: &U256,
+        data: Option<&Bytes>
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = match data {
+            Some(data) => self.contract.safe_transfer_from_with_from_and_to_and_data(*from_address, *to_address, *id, data.clone()).tx,
+            None => self.contract.safe_transfer_from(*from_address, *to_address, *id).tx,
+        };
+        self.send_erc721_transfer(sender, from_address, to_address, id, tx).await
+    }
+
+    /// Approve the given address to transfer the given NFT token ID (ERC721).
+    /// ### Arguments
+    /// * `owner` - a `Wallet` reference, the owner of the NFT
+    /// * `spender` - an `Address` reference, the approved address
+    /// * `id` - a `U256` reference, the NFT token ID
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn erc721_approve(
+        &self,
+        owner: &Wallet,
+        spender: &Address,
+        id: &U256
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.erc_721_approve(*spender, *id).tx;
+        let signed = self.sign_transaction(owner, tx).await?;
+        let pending_tx = self.broadcast(&signed).await?;
+        println!(
+            "Transaction sent, owner: {:?}, spender: {:?}, id: {} \n", 
+            owner.address(), 
+            spender, 
+            id
+        );
+        println!("Waiting...");
+
+        self.confirm(&signed, pending_tx).await
+    }
+
+    /// Approve or revoke the given operator to transfer every NFT of the owner.
+    /// ### Arguments
+    /// * `owner` - a `Wallet` reference, the owner of the NFTs
+    /// * `operator` - an `Address` reference, the operator
+    /// * `approved` - `true` to approve, `false` to revoke
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn set_approval_for_all(
+        &self,
+        owner: &Wallet,
+        operator: &Address,
+        approved: bool
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.set_approval_for_all(*operator, approved).tx;
+        let signed = self.sign_transaction(owner, tx).await?;
+        let pending_tx = self.broadcast(&signed).await?;
+        println!(
+            "Transaction sent, owner: {:?}, operator: {:?}, approved: {} \n", 
+            owner.address(), 
+            operator, 
+            approved
+        );
+        println!("Waiting...");
+
+        self.confirm(&signed, pending_tx).await
+    }
+
+    /// Approve the given address to spend the given amount, with the hybrid `approve`.
+    /// Amounts up to the max token ID are treated as an NFT token ID by the contract, see `erc20_approve`.
+    /// ### Arguments
+    /// * `owner` - a `Wallet` reference, the owner of the tokens
+    /// * `spender` - an `Address` reference, the spender
+    /// * `value_or_id` - a `U256` reference, the amount (wei) or NFT token ID
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn approve(
+        &self,
+        owner: &Wallet,
+        spender: &Address,
+        value_or_id: &U256
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.approve(*spender, *value_or_id).tx;
+        let summary = format!("owner: {:?}, spender: {:?}, value or id: {}", owner.address(), spender, value_or_id);
+        self.send_and_confirm(owner, tx, summary).await
+    }
+
+    /// Approve the given address to spend the given amount (ERC20).
+    /// ### Arguments
+    /// * `owner` - a `Wallet` reference, the owner of the tokens
+    /// * `spender` - an `Address` reference, the spender
+    /// * `amount` - a `U256` reference, the amount (wei)
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn erc20_approve(
+        &self,
+        owner: &Wallet,
+        spender: &Address,
+        amount: &U256
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.erc_20_approve(*spender, *amount).tx;
+        let summary = format!("owner: {:?}, spender: {:?}, amount (wei): {}", owner.address(), spender, amount);
+        self.send_and_confirm(owner, tx, summary).await
+    }
+
+    /// Transfer the given amount (ERC20) from the owner to the given address, spending the allowance of the sender.
+    /// ### Arguments
+    /// * `spender` - a `Wallet` reference, the spender
+    /// * `from_address` - an `Address` reference, the owner of the tokens
+    /// * `to_address` - an `Address` reference, the recipient
+    /// * `amount` - a `U256` reference, the amount (wei)
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn erc20_transfer_from(
+        &self,
+        spender: &Wallet,
+        from_address: &Address,
+        to_address: &Address,
+        amount: &U256
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.erc_20_transfer_from(*from_address, *to_address, *amount).tx;
+        let summary = format!("from: {:?}, to: {:?}, amount (wei): {}", from_address, to_address, amount);
+        self.send_and_confirm(spender, tx, summary).await
+    }
+
+    /// Transfer from the owner to the given address with the hybrid `transferFrom`, spending the allowance of the sender.
+    /// Values up to the max token ID are treated as an NFT token ID by the contract, see `erc20_transfer_from`.
+    /// ### Arguments
+    /// * `spender` - a `Wallet` reference, the spender
+    /// * `from_address` - an `Address` reference, the owner of the tokens
+    /// * `to_address` - an `Address` reference, the recipient
+    /// * `value_or_id` - a `U256` reference, the amount (wei) or NFT token ID
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn transfer_from(
+        &self,
+        spender: &Wallet,
+        from_address: &Address,
+        to_address: &Address,
+        value_or_id: &U256
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.transfer_from(*from_address, *to_address, *value_or_id).tx;
+        let summary = format!("from: {:?}, to: {:?}, value or id: {}", from_address, to_address, value_or_id);
+        self.send_and_confirm(spender, tx, summary).await
+    }
+
+    /// Burn the given amount (ERC20) of the owner, spending the allowance of the sender.
+    /// ### Arguments
+    /// * `spender` - a `Wallet` reference, the spender
+    /// * `account` - an `Address` reference, the owner of the tokens
+    /// * `amount` - a `U256` reference, the amount (wei)
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn burn_from(
+        &self,
+        spender: &Wallet,
+        account: &Address,
+        amount: &U256
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.burn_from(*account, *amount).tx;
+        let summary = format!("account: {:?}, amount (wei): {}", account, amount);
+        self.send_and_confirm(spender, tx, summary).await
+    }
+
+    /// Grant the given role to the account, the sender must hold the admin role of the role.
+    /// ### Arguments
+    /// * `admin` - a `Wallet` reference, the sender of the transaction
+    /// * `role` - AccessControl role
+    /// * `account` - an `Address` reference, the account granted the role
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn grant_role(
+        &self,
+        admin: &Wallet,
+        role: Role,
+        account: &Address
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.grant_role(role.id(), *account).tx;
+        let summary = format!("role: {}, account: {:?}", role, account);
+        self.send_and_confirm(admin, tx, summary).await
+    }
+
+    /// Revoke the given role from the account, the sender must hold the admin role of the role.
+    /// ### Arguments
+    /// * `admin` - a `Wallet` reference, the sender of the transaction
+    /// * `role` - AccessControl role
+    /// * `account` - an `Address` reference, the account the role is revoked from
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn revoke_role(
+        &self,
+        admin: &Wallet,
+        role: Role,
+        account: &Address
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.revoke_role(role.id(), *account).tx;
+        let summary = format!("role: {}, account: {:?}", role, account);
+        self.send_and_confirm(admin, tx, summary).await
+    }
+
+    /// Renounce the given role, held by the sender.
+    /// ### Arguments
+    /// * `account` - a `Wallet` reference, the sender of the transaction giving up the role
+    /// * `role` - AccessControl role
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn renounce_role(
+        &self,
+        account: &Wallet,
+        role: Role
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.renounce_role(role.id(), account.address()).tx;
+        let summary = format!("role: {}", role);
+        self.send_and_confirm(account, tx, summary).await
+    }
+
+    /// Pause the contract, blocking transfers and mints until it is unpaused.
+    /// ### Arguments
+    /// * `pauser` - a `Wallet` reference, the sender of the transaction, holding `PAUSER_ROLE`
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn pause(&self, pauser: &Wallet) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.pause().tx;
+        self.send_and_confirm(pauser, tx, "function: pause".to_string()).await
+    }
+
+    /// Unpause the contract.
+    /// ### Arguments
+    /// * `pauser` - a `Wallet` reference, the sender of the transaction, holding `PAUSER_ROLE`
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn unpause(&self, pauser: &Wallet) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.unpause().tx;
+        self.send_and_confirm(pauser, tx, "function: unpause".to_string()).await
+    }
+
+    /// Set the msg value (wei) paid per minted NFT by `mintERC721`.
+    /// ### Arguments
+    /// * `admin` - a `Wallet` reference, the sender of the transaction
+    /// * `cost` - a `U256` reference, the minting cost (wei)
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn set_mint721_cost(
+        &self,
+        admin: &Wallet,
+        cost: &U256
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.set_mint_721_cost(*cost).tx;
+        let summary = format!("minting cost (wei): {}", cost);
+        self.send_and_confirm(admin, tx, summary).await
+    }
+
+    /// Set the max NFT token ID, capping the number of NFTs that can be minted.
+    /// ### Arguments
+    /// * `admin` - a `Wallet` reference, the sender of the transaction
+    /// * `cap` - a `U256` reference, the max token ID
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn set_max_token_id(
+        &self,
+        admin: &Wallet,
+        cap: &U256
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.set_max_token_id(*cap).tx;
+        let summary = format!("max token ID: {}", cap);
+        self.send_and_confirm(admin, tx, summary).await
+    }
+
+    /// Set the treasury address, receiving the minting costs.
+    /// ### Arguments
+    /// * `admin` - a `Wallet` reference, the sender of the transaction
+    /// * `treasury` - an `Address` reference, the new treasury
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn set_treasury_address(
+        &self,
+        admin: &Wallet,
+        treasury: &Address
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.set_treasury_address(*treasury).tx;
+        let summary = format!("treasury: {:?}", treasury);
+        self.send_and_confirm(admin, tx, summary).await
+    }
+
+    /// Set the base URI of the NFT metadata.
+    /// ### Arguments
+    /// * `admin` - a `Wallet` reference, the sender of the transaction
+    /// * `base_uri` - the new base URI
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn set_base_uri(
+        &self,
+        admin: &Wallet,
+        base_uri: &str
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.set_base_uri(base_uri.to_string()).tx;
+        let summary = format!("base URI: {}", base_uri);
+        self.send_and_confirm(admin, tx, summary).await
+    }
+
+    /// Send ETH held by the contract to the recipient.
+    /// ### Arguments
+    /// * `admin` - a `Wallet` reference, the sender of the transaction
+    /// * `amount` - a `U256` reference, the amount (wei)
+    /// * `recipient` - an `Address` reference, the recipient
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn recover_eth(
+        &self,
+        admin: &Wallet,
+        amount: &U256,
+        recipient: &Address
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.recover_eth(*amount, *recipient).tx;
+        let summary = format!("amount (wei): {}, recipient: {:?}", amount, recipient);
+        self.send_and_confirm(admin, tx, summary).await
+    }
+
+    /// Send ERC20 tokens held by the contract to the recipient.
+    /// ### Arguments
+    /// * `admin` - a `Wallet` reference, the sender of the transaction
+    /// * `token` - an `Address` reference, the ERC20 token contract
+    /// * `recipient` - an `Address` reference, the recipient
+    /// * `amount` - a `U256` reference, the amount (wei)
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn recover_token(
+        &self,
+        admin: &Wallet,
+        token: &Address,
+        recipient: &Address,
+        amount: &U256
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.recover_token(*token, *recipient, *amount).tx;
+        let summary = format!("token: {:?}, recipient: {:?}, amount (wei): {}", token, recipient, amount);
+        self.send_and_confirm(admin, tx, summary).await
+    }
+
+    /// Initialize the ERC404 state of a freshly deployed proxy, after `initialize`.
+    /// ### Arguments
+    /// * `admin` - a `Wallet` reference, the sender of the transaction
+    /// * `unit404_decimals` - a `U256` reference, the decimals of the ERC20 amount backing one NFT
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn init404(
+        &self,
+        admin: &Wallet,
+        unit404_decimals: &U256
+    ) -> Result<(String, String, String, String, String)> {
+        let tx = self.contract.init_404(*unit404_decimals).tx;
+        let summary = format!("unit404Decimals: {}", unit404_decimals);
+        self.send_and_confirm(admin, tx, summary).await
+    }
+
+    /// Submit a signed EIP-2612 permit, setting the allowance of the owner for the spender.
+    /// The permit is simulated first, so an expired or invalid signature fails before anything is sent.
+    /// ### Arguments
+    /// * `relayer` - a `Wallet` reference, the sender of the transaction (pays the gas)
+    /// * `permit` - a `SignedPermit` reference, see `sign_permit`
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn permit(
+        &self,
+        relayer: &Wallet,
+        permit: &SignedPermit
+    ) -> Result<(String, String, String, String, String)> {
+        let call = self.contract.permit(
+            permit.owner,
+            permit.spender,
+            permit.value,
+            permit.deadline,
+            permit.v,
+            permit.r.into(),
+            permit.s.into()
+        );
+        if let Err(e) = call.clone().from(relayer.address()).call().await {
+            return Err(eyre::eyre!("> Permit would revert: {}", Self::revert_reason(&e)))
+        }
+
+        let summary = format!(
+            "owner: {:?}, spender: {:?}, value (wei): {}, deadline: {}", 
+            permit.owner, 
+            permit.spender, 
+            permit.value, 
+            permit.deadline
+        );
+        self.send_and_confirm(relayer, call.tx, summary).await
+    }
+
+    /// Simulates the upgrade of the proxy to the new implementation, calling the initializer data if any.
+    /// ### Arguments
+    /// * `upgrader` - an `Address` reference, the sender of the upgrade, holding `UPGRADER_ROLE`
+    /// * `implementation` - an `Address` reference, the new implementation
+    /// * `data` - a `Bytes` reference, the initializer calldata, empty for none
+    /// 
+    /// ### Returns
+    /// * `Result<()>` - An error with the decoded revert reason if the upgrade would revert
+    pub async fn simulate_upgrade_to_and_call(
+        &self,
+        upgrader: &Address,
+        implementation: &Address,
+        data: &Bytes
+    ) -> Result<()> {
+        let call = self.contract.upgrade_to_and_call(*implementation, data.clone()).from(*upgrader);
+        match call.call().await {
+            Ok(()) => Ok(()),
+            Err(e) => Err(eyre::eyre!("> Upgrade would revert: {}", Self::revert_reason(&e)))
+        }
+    }
+
+    /// Upgrade the proxy to the new implementation, calling the initializer data if any.
+    /// The upgrade is simulated first, see `simulate_upgrade_to_and_call`.
+    /// ### Arguments
+    /// * `upgrader` - a `Wallet` reference, the sender of the transaction, holding `UPGRADER_ROLE`
+    /// * `implementation` - an `Address` reference, the new implementation
+    /// * `data` - a `Bytes` reference, the initializer calldata, empty for none
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn upgrade_to_and_call(
+        &self,
+        upgrader: &Wallet,
+        implementation: &Address,
+        data: &Bytes
+    ) -> Result<(String, String, String, String, String)> {
+        self.simulate_upgrade_to_and_call(&upgrader.address(), implementation, data).await?;
+        let tx = self.contract.upgrade_to_and_call(*implementation, data.clone()).tx;
+        let summary = format!("implementation: {:?}, data: {}", implementation, data);
+        self.send_and_confirm(upgrader, tx, summary).await
+    }
+
+    /// Describes why a simulated call reverted, decoding the known errors of the contract
+    fn revert_reason(e: &ContractError<M>) -> String {
+        match e.as_revert().and_then(|data| Purse404Errors::decode(data).ok()) {
+            Some(Purse404Errors::ERC2612ExpiredSignature(err)) => {
+                format!("ERC2612ExpiredSignature(), the permit expired at {}", err.deadline)
+            },
+            Some(Purse404Errors::ERC2612InvalidSigner(err)) => {
+                format!("ERC2612InvalidSigner(), recovered signer {:?} is not the owner {:?}", err.signer, err.owner)
+            },
+            Some(Purse404Errors::AccessControlUnauthorizedAccount(err)) => {
+                let role = match Role::from_id(&err.needed_role) {
+                    Some(role) => role.to_string(),
+                    None => format!("0x{}", ethers::utils::hex::encode(err.needed_role)),
+                };
+                format!("AccessControlUnauthorizedAccount(), {:?} does not hold {}", err.account, role)
+            },
+            Some(Purse404Errors::ERC1967InvalidImplementation(err)) => {
+                format!("ERC1967InvalidImplementation(), {:?} is not a valid implementation", err.implementation)
+            },
+            Some(Purse404Errors::UUPSUnsupportedProxiableUUID(err)) => {
+                format!(
+                    "UUPSUnsupportedProxiableUUID(), the implementation returned 0x{} as its proxiableUUID",
+                    ethers::utils::hex::encode(err.slot)
+                )
+            },
+            Some(err) => format!("{:?}", err),
+            None => e.to_string(),
+        }
+    }
+
+    /// Signs, broadcasts and confirms a transaction, printing the given summary once it is sent
+    async fn send_and_confirm(
+        &self,
+        sender: &Wallet,
+        tx: TypedTransaction,
+        summary: String
+    ) -> Result<(String, String, String, String, String)> {
+        let signed = self.sign_transaction(sender, tx).await?;
+        let pending_tx = self.broadcast(&signed).await?;
+        println!("Transaction sent, sender: {:?}, {} \n", sender.address(), summary);
+        println!("Waiting...");
+
+        self.confirm(&signed, pending_tx).await
+    }
+
+    /// Signs, broadcasts and confirms an ERC721 transfer transaction
+    async fn send_erc721_transfer(
+        &self,
+        sender: &Wallet,
+        from_address: &Address,
+        to_address: &Address,
+        id: &U256,
+        tx: TypedTransaction
+    ) -> Result<(String, String, String, String, String)> {
+        let signed = self.sign_transaction(sender, tx).await?;
+        let pending_tx = self.broadcast(&signed).await?;
+        println!(
+            "Transaction sent, from: {:?}, to: {:?}, id: {} \n", 
+            from_address, 
+            to_address, 
+            id
+        );
+        println!("Waiting...");
+
+        self.confirm(&signed, pending_tx).await
+    }
+
+    /// Broadcasts an already signed transaction and waits for its receipt.
+    /// ### Arguments
+    /// * `signed` - a `SignedTransaction` reference
+    /// 
+    /// ### Returns
+    /// * `Result<(String, String, String, String, String)>` - A tuple of transaction hash, 
+    /// gas price, gas used, transaction fees, and transaction receipt JSON
+    pub async fn send_signed_transaction(
+        &self,
+        signed: &SignedTransaction
+    ) -> Result<(String, String, String, String, String)> {
+        let pending_tx = self.broadcast(signed).await?;
+        println!(
+            "Transaction sent, from: {:?}, nonce: {}, hash: {:?} \n", 
+            signed.from, 
+            signed.nonce, 
+            signed.hash
+        );
+        println!("Waiting...");
+
+        self.confirm(signed, pending_tx).await
+    }
+
+    /// Fills in the nonce, gas and fee fields of the transaction, then signs it with the given wallet.
+    /// ### Arguments
+    /// * `from` - a `Wallet` reference, the signer of the transaction
+    /// * `tx` - the unsigned transaction
+    /// 
+    /// ### Returns
+    /// * `Result<SignedTransaction>` - The signed transaction
+    pub async fn sign_transaction(
+        &self,
+        from: &Wallet,
+        mut tx: TypedTransaction
+    ) -> Result<SignedTransaction> {
+        let signer_middleware = SignerMiddleware::new(
+            self.provider.clone(),
+            from.signer.clone()
+        );
+        if let Err(e) = signer_middleware.fill_transaction(&mut tx, None).await {
+            return Err(eyre::eyre!("Failed to send transaction: {}", e))
+        }
+        let signature = from.signer.sign_transaction(&tx).await?;
+
+        Ok(SignedTransaction {
+            from: from.address(),
+            derivation: from.derivation,
+            nonce: tx.nonce().copied().unwrap_or_default(),
+            chain_id: tx.chain_id().map(|id| id.as_u64()).unwrap_or_default(),
+            hash: tx.hash(&signature),
+            raw: tx.rlp_signed(&signature),
+        })
+    }
+
+    /// Journals the signed transaction (if a journal is set), then broadcasts it.
+    /// The journal entry is synced to disk before the transaction leaves this process.
+    async fn broadcast(
+        &self,
+        signed: &SignedTransaction
+    ) -> Result<PendingTransaction<'_, M::Provider>> {
+        if let Some(journal) = &self.journal {
+            journal.append(&JournalEntry {
+                tx_hash: signed.hash,
+                status: JournalStatus::Pending,
+                chain_id: signed.chain_id,
+                derivation: signed.derivation,
+                from: signed.from,
+                nonce: signed.nonce,
+                raw_tx: signed.raw.clone(),
+                block_number: None,
+                updated_at: unix_timestamp(),
+            })?;
+        }
+
+        match self.provider.send_raw_transaction(signed.raw.clone()).await {
+            Ok(pending_tx) => Ok(pending_tx),
+            Err(e) => Err(eyre::eyre!("Failed to send transaction: {}", e))
+        }
+    }
+
+    /// Waits for the receipt of a broadcast transaction, and marks it as mined in the journal.
+    /// A reverted transaction is marked as reverted in the journal, and returns an error.
+    async fn confirm(
+        &self,
+        signed: &SignedTransaction,
+        pending_tx: PendingTransaction<'_, M::Provider>
+    ) -> Result<(String, String, String, String, String)> {
+        let receipt = match pending_tx.await {
+            Ok(Some(receipt)) => receipt,
+            Ok(None) => {
+                return Err(eyre::eyre!("Transaction {:?} was dropped from the mempool", signed.hash))
+            },
+            Err(e) => {
+                return Err(eyre::eyre!("Unexpected error occurred: {}", e))
+            }
+        };
+        let reverted = receipt.status.map(|status| status.is_zero()).unwrap_or(false);
+        if let Some(journal) = &self.journal {
+            journal.update_status(
+                &signed.hash, 
+                if reverted { JournalStatus::Reverted } else { JournalStatus::Mined }, 
+                receipt.block_number.map(|n| n.as_u64())
+            )?;
+        }
+        if reverted {
+            return Err(eyre::eyre!(
+                "Transaction {:?} reverted in block {}",
+                signed.hash,
+                receipt.block_number.unwrap_or_default()
+            ))
+        }
 
-        let tx = contract_with_signer.mint(*to_address, *amount);
-        let pending_tx = match tx.send().await {
-            Ok(pending_tx) => {
-                println!(
-                    "Transaction sent, from: {}, to: {}, amount (wei): {} \n", 
-                    to_address, 
-                    self.address(), 
-                    amount
-                );
-                println!("Waiting...");
-                pending_tx
-            },
-            Err(e) => {
-                return Err(eyre::eyre!("Failed to send transaction: {}", e))
-            }
-        };
-        let receipt = match pending_tx.await {
-            Ok(receipt) => receipt,
-            Err(e) => {
-                return Err(eyre::eyre!("Unexpected error occurred: {}", e))
-            }
-        };
         let json_str = serde_json::to_string(&receipt)?;
         let tx_hash = get_tx_hash(&json_str);
         let gas_price = get_gas_price(&json_str);
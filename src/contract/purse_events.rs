@@ -0,0 +1,229 @@
+use std::fmt;
+use ethers::{
+    abi::RawLog,
+    contract::{EthEvent, EthLogDecode},
+    types::{Address, Log, TransactionReceipt, U256},
+};
+use crate::contract::purse_contract::{
+    ApprovalFilter,
+    Purse404Events,
+    TransferFilter,
+};
+
+/// ERC721 `Transfer` event of the Purse404 contract.
+/// Shares its signature with the ERC20 `Transfer` event, but the token ID is indexed,
+/// so it is told apart by its number of topics. Mints are transfers from, and burns are
+/// transfers to, the zero address.
+#[derive(Clone, Debug, Default, PartialEq, Eq, EthEvent)]
+#[ethevent(name = "Transfer", abi = "Transfer(address,address,uint256)")]
+pub struct Erc721TransferFilter {
+    #[ethevent(indexed)]
+    pub from: Address,
+    #[ethevent(indexed)]
+    pub to: Address,
+    #[ethevent(indexed)]
+    pub id: U256,
+}
+
+/// ERC721 `Approval` event of the Purse404 contract.
+/// Shares its signature with the ERC20 `Approval` event, but the token ID is indexed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, EthEvent)]
+#[ethevent(name = "Approval", abi = "Approval(address,address,uint256)")]
+pub struct Erc721ApprovalFilter {
+    #[ethevent(indexed)]
+    pub owner: Address,
+    #[ethevent(indexed)]
+    pub spender: Address,
+    #[ethevent(indexed)]
+    pub id: U256,
+}
+
+/// A decoded event of the Purse404 contract
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Purse404Event {
+    /// ERC20 `Transfer`, the amount is in the log data
+    Erc20Transfer(TransferFilter),
+    /// ERC20 `Approval`, the value is in the log data
+    Erc20Approval(ApprovalFilter),
+    /// ERC721 `Transfer`, the token ID is indexed
+    Erc721Transfer(Erc721TransferFilter),
+    /// ERC721 `Approval`, the token ID is indexed
+    Erc721Approval(Erc721ApprovalFilter),
+    /// Any other event of the contract ABI
+    Other(Purse404Events),
+}
+
+impl fmt::Display for Purse404Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Purse404Event::Erc20Transfer(e) => write!(
+                f, "ERC20 Transfer: from {:?} to {:?}, amount {}", e.from, e.to, e.amount
+            ),
+            Purse404Event::Erc20Approval(e) => write!(
+                f, "ERC20 Approval: owner {:?}, spender {:?}, value {}", e.owner, e.spender, e.value
+            ),
+            Purse404Event::Erc721Transfer(e) => write!(
+                f, "ERC721 Transfer: from {:?} to {:?}, id {}", e.from, e.to, e.id
+            ),
+            Purse404Event::Erc721Approval(e) => write!(
+                f, "ERC721 Approval: owner {:?}, spender {:?}, id {}", e.owner, e.spender, e.id
+            ),
+            Purse404Event::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Decode a log emitted by the Purse404 contract.
+/// ### Arguments
+/// * `log` - Log of a transaction receipt
+/// 
+/// ### Returns
+/// * `Option<Purse404Event>` - The decoded event, `None` if the log is not an event of the contract ABI
+pub fn decode_purse_log(log: &Log) -> Option<Purse404Event> {
+    let raw = RawLog { topics: log.topics.clone(), data: log.data.to_vec() };
+
+    // ERC721 events index all three params, the ERC20 events with the same signature only two
+    if log.topics.len() == 4 {
+        if let Ok(event) = <Erc721TransferFilter as EthEvent>::decode_log(&raw) {
+            return Some(Purse404Event::Erc721Transfer(event));
+        }
+        if let Ok(event) = <Erc721ApprovalFilter as EthEvent>::decode_log(&raw) {
+            return Some(Purse404Event::Erc721Approval(event));
+        }
+    }
+
+    match Purse404Events::decode_log(&raw) {
+        Ok(Purse404Events::TransferFilter(event)) => Some(Purse404Event::Erc20Transfer(event)),
+        Ok(Purse404Events::ApprovalFilter(event)) => Some(Purse404Event::Erc20Approval(event)),
+        Ok(event) => Some(Purse404Event::Other(event)),
+        Err(_) => None,
+    }
+}
+
+/// Decode the events emitted by the Purse404 contract in a transaction receipt.
+/// Logs of other contracts, and logs that do not match the contract ABI, are skipped.
+/// ### Arguments
+/// * `receipt` - Transaction receipt
+/// * `contract` - Address of the Purse404 contract
+/// 
+/// ### Returns
+/// * `Vec<Purse404Event>` - Decoded events, in log order
+pub fn decode_receipt_events(receipt: &TransactionReceipt, contract: Address) -> Vec<Purse404Event> {
+    receipt.logs
+        .iter()
+        .filter(|log| log.address == contract)
+        .filter_map(decode_purse_log)
+        .collect()
+}
+
+/// NFT IDs minted, burned or moved by a transaction, from its ERC721 `Transfer` events
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NftMovements {
+    /// IDs transferred from the zero address
+    pub minted: Vec<U256>,
+    /// IDs transferred to the zero address
+    pub burned: Vec<U256>,
+    /// IDs transferred between two accounts
+    pub moved: Vec<U256>,
+}
+
+impl NftMovements {
+    /// Collect the NFT movements from decoded events
+    /// ### Arguments
+    /// * `events` - Decoded events of a transaction
+    /// 
+    /// ### Returns
+    /// * `Self` - The NFT movements
+    pub fn from_events(events: &[Purse404Event]) -> Self {
+        let mut movements = Self::default();
+        for event in events {
+            if let Purse404Event::Erc721Transfer(transfer) = event {
+                if transfer.from.is_zero() {
+                    movements.minted.push(transfer.id);
+                } else if transfer.to.is_zero() {
+                    movements.burned.push(transfer.id);
+                } else {
+                    movements.moved.push(transfer.id);
+                }
+            }
+        }
+        movements
+    }
+
+    /// Returns `true` if no NFT was minted, burned or moved
+    pub fn is_empty(&self) -> bool {
+        self.minted.is_empty() && self.burned.is_empty() && self.moved.is_empty()
+    }
+}
+
+/// Formats as `minted:<ids>;burned:<ids>;moved:<ids>`, with comma separated IDs,
+/// leaving out empty groups, eg: `minted:1,2;moved:7`
+impl fmt::Display for NftMovements {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let groups: Vec<String> = [("minted", &self.minted), ("burned", &self.burned), ("moved", &self.moved)]
+            .iter()
+            .filter(|(_, ids)| !ids.is_empty())
+            .map(|(label, ids)| format!(
+                "{}:{}",
+                label,
+                ids.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(",")
+            ))
+            .collect();
+        write!(f, "{}", groups.join(";"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::H256;
+
+    fn address_topic(address: Address) -> H256 {
+        H256::from(address)
+    }
+
+    fn u256_topic(value: U256) -> H256 {
+        let mut bytes = [0u8; 32];
+        value.to_big_endian(&mut bytes);
+        H256::from(bytes)
+    }
+
+    #[test]
+    fn test_decode_transfer_logs() {
+        let from = Address::from_low_u64_be(1);
+        let to = Address::from_low_u64_be(2);
+        let signature = Erc721TransferFilter::signature();
+        assert_eq!(signature, TransferFilter::signature());
+
+        let erc20 = Log {
+            topics: vec![signature, address_topic(from), address_topic(to)],
+            data: ethers::abi::encode(&[ethers::abi::Token::Uint(U256::from(5))]).into(),
+            ..Default::default()
+        };
+        let erc721_mint = Log {
+            topics: vec![signature, address_topic(Address::zero()), address_topic(to), u256_topic(U256::from(7))],
+            ..Default::default()
+        };
+        let erc721_move = Log {
+            topics: vec![signature, address_topic(from), address_topic(to), u256_topic(U256::from(8))],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            decode_purse_log(&erc20),
+            Some(Purse404Event::Erc20Transfer(TransferFilter { from, to, amount: U256::from(5) }))
+        );
+        assert_eq!(
+            decode_purse_log(&erc721_mint),
+            Some(Purse404Event::Erc721Transfer(Erc721TransferFilter { from: Address::zero(), to, id: U256::from(7) }))
+        );
+
+        let events: Vec<Purse404Event> = [erc20, erc721_mint, erc721_move]
+            .iter()
+            .filter_map(decode_purse_log)
+            .collect();
+        let movements = NftMovements::from_events(&events);
+        assert_eq!(movements.to_string(), "minted:7;moved:8");
+        assert!(NftMovements::default().is_empty());
+    }
+}
@@ -1,11 +1,17 @@
 use eyre::Result;
 use std::str::FromStr;
 use ethers::{
-    types::{Address, U256},
+    abi::Token,
+    types::{Address, Bytes, U256},
     providers::Middleware,
 };
 use crate::{
-    contract::purse_contract::Purse404Contract,
+    contract::{
+        purse_calldata::decode_calldata,
+        purse_contract::{Purse404Contract, PURSE404_ABI},
+        purse_roles::Role,
+    },
+    utils::{str_wei_to_eth, token_to_string},
     wallet::Wallet,
 };
 
@@ -28,11 +34,29 @@ pub fn validate_purse_calldata(func: &str, calldata: &Option<Vec<String>>) -> ey
         // Single value calldata functions
         ("balanceOf", Some(data)) 
         | ("owned", Some(data))
-        | ("mintERC721", Some(data))  if data.len() == 1 => Ok(()),
+        | ("mintERC721", Some(data))
+        | ("ownerOf", Some(data))
+        | ("tokenURI", Some(data))
+        | ("getApproved", Some(data)) if data.len() == 1 => Ok(()),
 
         // Two value calldata functions
         ("transfer", Some(data)) 
-        | ("mint", Some(data)) if data.len() == 2 => Ok(()),
+        | ("mint", Some(data))
+        | ("erc721Approve", Some(data))
+        | ("setApprovalForAll", Some(data))
+        | ("isApprovedForAll", Some(data))
+        | ("allowance", Some(data))
+        | ("approve", Some(data))
+        | ("erc20Approve", Some(data))
+        | ("burnFrom", Some(data)) if data.len() == 2 => Ok(()),
+
+        // Three value calldata functions
+        ("erc721TransferFrom", Some(data))
+        | ("erc20TransferFrom", Some(data))
+        | ("transferFrom", Some(data)) if data.len() == 3 => Ok(()),
+
+        // `safeTransferFrom` with or without `data_`
+        ("safeTransferFrom", Some(data)) if data.len() == 3 || data.len() == 4 => Ok(()),
 
         // Unsupported functions
         (_, Some(_)) => Err(eyre::eyre!("Unsupported function: {}", func)),
@@ -40,6 +64,20 @@ pub fn validate_purse_calldata(func: &str, calldata: &Option<Vec<String>>) -> ey
     }
 }
 
+/// Decode the calldata of a transaction to the Purse smart contract.
+/// The function is found by its 4-byte selector, and the arguments are formatted
+/// the same way they are passed on the command line (see `validate_purse_calldata`).
+/// ### Arguments
+/// * `data` - Transaction input data
+/// 
+/// ### Returns
+/// * `Result<(String, Vec<String>)>` - The function name and its arguments
+pub fn decode_purse_calldata(data: &[u8]) -> eyre::Result<(String, Vec<String>)> {
+    let (function, tokens) = decode_calldata(&PURSE404_ABI, data)?;
+
+    Ok((function.name, tokens.iter().map(token_to_string).collect()))
+}
+
 /// Destruct the calldata based on the function of the Purse smart contract.
 /// This is only relevant to contract functions that require calldata.
 /// ### Arguments
@@ -53,23 +91,53 @@ pub fn destruct_purse_calldata(
     calldata: &Vec<String>
 ) -> (Option<String>, Option<String>, Option<String>, Option<String>, Option<String>, Option<String>, Option<String>) {
     match func {
-        "balanceOf" | "owned" | "mintERC721" => {
+        "balanceOf" | "owned" | "mintERC721" | "ownerOf" | "tokenURI" | "getApproved" => {
             let val = calldata[0].clone();
             (Some(val), None, None, None, None, None, None)
         },
-        "transfer" | "mint" => {
+        "transfer" | "mint" | "erc721Approve" | "setApprovalForAll" | "isApprovedForAll"
+        | "allowance" | "approve" | "erc20Approve" | "burnFrom" | "recoverEth" => {
             let val1 = calldata[0].clone();
             let val2 = calldata[1].clone();
             (Some(val1), Some(val2), None, None, None, None, None)
         },
+        "erc721TransferFrom" | "safeTransferFrom" | "erc20TransferFrom" | "transferFrom" | "recoverToken" => {
+            let val1 = calldata[0].clone();
+            let val2 = calldata[1].clone();
+            let val3 = calldata[2].clone();
+            let val4 = calldata.get(3).cloned();
+            (Some(val1), Some(val2), Some(val3), val4, None, None, None)
+        },
+        "permit" => {
+            let val = |i: usize| calldata.get(i).cloned();
+            (val(0), val(1), val(2), val(3), val(4), val(5), val(6))
+        },
         _ => (None, None, None, None, None, None, None)
     }
 }
 
+/// Returns the position of the ERC20 amount in the calldata of the function, if it takes one.
+/// For the hybrid `approve` and `transferFrom`, the value may also be an NFT token ID.
+/// ### Arguments
+/// * `func` - Function name
+/// 
+/// ### Returns
+/// * `Option<usize>` - Index of the amount in the calldata
+pub fn amount_calldata_index(func: &str) -> Option<usize> {
+    match func {
+        "transfer" | "mint" | "approve" | "erc20Approve" | "burnFrom" => Some(1),
+        "erc20TransferFrom" | "transferFrom" => Some(2),
+        _ => None,
+    }
+}
+
 /// Retrieves the recipient address and calldata value from the calldata for
-/// single transfer and mint related functions only.
+/// single transfer, mint and approval related functions only.
 /// #### Note
 /// For any ERC721 related mints, the recipient address returned by this function is `Address::zero()`.
+/// For ERC721 transfers the recipient is the `to_` argument, and for approvals the spender or operator,
+/// with a calldata value of zero, see `erc721_token_id` for the token ID.
+/// For `recoverEth` and `recoverToken`, the recipient and amount of the recovered ETH or tokens are returned.
 /// Does not work for batch mint/transfer functions where calldata is a vector of values.
 /// 
 /// ### Arguments
@@ -77,32 +145,102 @@ pub fn destruct_purse_calldata(
 /// * `calldata` - Calldata
 /// 
 /// ### Returns
-/// * `(Address, U256)` - A tuple containing the recipient address and calldata value for
-/// the single transfer or mint related function
+/// * `Result<(Address, U256)>` - A tuple containing the recipient address and calldata value for
+/// the single transfer or mint related function, errors if the calldata does not parse
 pub fn transfer_or_mint_recipient_n_calldata(
     func: &str,
     calldata: &Vec<String>
-) -> (Address, U256) {
+) -> Result<(Address, U256)> {
     let (a, 
         b, 
-        _c, 
+        c, 
         _d, 
         _e, 
         _f, 
         _g
     ) = destruct_purse_calldata(func, calldata);
+    let address = |val: Option<String>| -> Result<Address> {
+        let val = val.ok_or_else(|| eyre::eyre!("> {}: missing address in calldata", func))?;
+        Address::from_str(&val).map_err(|e| eyre::eyre!("> {}: invalid address {}: {}", func, val, e))
+    };
+    let value = |val: Option<String>| -> Result<U256> {
+        let val = val.ok_or_else(|| eyre::eyre!("> {}: missing value in calldata", func))?;
+        U256::from_dec_str(&val).map_err(|e| eyre::eyre!("> {}: invalid value {}: {}", func, val, e))
+    };
     match func {
-        "mintERC721" => {
-            let recipient = Address::zero();
-            let calldata_value = U256::from_dec_str(&a.unwrap()).unwrap();
-            (recipient, calldata_value)
-        },
-        "transfer" | "mint" => {
-            let recipient = Address::from_str(&a.unwrap()).unwrap();
-            let calldata_value = U256::from_dec_str(&b.unwrap()).unwrap();
-            (recipient, calldata_value)
-        },
-        _ => (Address::zero(), U256::from(0))
+        "mintERC721" => Ok((Address::zero(), value(a)?)),
+        "transfer" | "mint" | "approve" | "erc20Approve" => Ok((address(a)?, value(b)?)),
+        "erc20TransferFrom" | "transferFrom" | "permit" => Ok((address(b)?, value(c)?)),
+        "burnFrom" => Ok((Address::zero(), value(b)?)),
+        "recoverEth" => Ok((address(b)?, value(a)?)),
+        "recoverToken" => Ok((address(b)?, value(c)?)),
+        "erc721TransferFrom" | "safeTransferFrom" => Ok((address(b)?, U256::from(0))),
+        "erc721Approve" | "setApprovalForAll" => Ok((address(a)?, U256::from(0))),
+        _ => Ok((Address::zero(), U256::from(0)))
+    }
+}
+
+/// Retrieves the account whose tokens are spent by the message sender, from the calldata of
+/// allowance spending functions (`erc20TransferFrom`, `transferFrom` and `burnFrom`),
+/// or the owner of a relayed `permit`.
+/// ### Arguments
+/// * `func` - Function name
+/// * `calldata` - Calldata
+/// 
+/// ### Returns
+/// * `Option<Address>` - The owner of the spent tokens, `None` for other functions
+pub fn allowance_third_party(func: &str, calldata: &[String]) -> Option<Address> {
+    match func {
+        "erc20TransferFrom" | "transferFrom" | "burnFrom" | "permit" => calldata
+            .first()
+            .and_then(|owner| Address::from_str(owner).ok()),
+        _ => None
+    }
+}
+
+/// Retrieves the owner and spender of the allowance set or spent by a logged transaction.
+/// ### Arguments
+/// * `func` - Function name
+/// * `msg_sender` - Message sender
+/// * `msg_recipient` - Recipient, the spender of approvals
+/// * `third_party` - Owner of the tokens spent, see `allowance_third_party`
+/// 
+/// ### Returns
+/// * `Option<(Address, Address)>` - The owner and spender, `None` for functions not touching an allowance
+pub fn allowance_parties(
+    func: &str,
+    msg_sender: Address,
+    msg_recipient: Address,
+    third_party: Option<Address>
+) -> Option<(Address, Address)> {
+    match func {
+        "approve" | "erc20Approve" => Some((msg_sender, msg_recipient)),
+        "erc20TransferFrom" | "transferFrom" | "burnFrom" => third_party.map(|owner| (owner, msg_sender)),
+        "permit" => third_party.map(|owner| (owner, msg_recipient)),
+        _ => None
+    }
+}
+
+/// Retrieves the NFT token ID from the calldata of ERC721 transfer and approval functions.
+/// ### Arguments
+/// * `func` - Function name
+/// * `calldata` - Calldata
+/// 
+/// ### Returns
+/// * `Option<U256>` - The token ID, `None` for other functions
+pub fn erc721_token_id(func: &str, calldata: &Vec<String>) -> Option<U256> {
+    let (_a, 
+        b, 
+        c, 
+        _d, 
+        _e, 
+        _f, 
+        _g
+    ) = destruct_purse_calldata(func, calldata);
+    match func {
+        "erc721TransferFrom" | "safeTransferFrom" => c.and_then(|id| U256::from_dec_str(&id).ok()),
+        "erc721Approve" => b.and_then(|id| U256::from_dec_str(&id).ok()),
+        _ => None
     }
 }
 
@@ -114,8 +252,34 @@ pub enum Purse404FunctionCall {
     MintingCost,
     Owned(Address),
     Transfer(Wallet, Address, U256),
-    MintERC721(Wallet, U256, U256),
+    /// Minter, units to mint, and msg value, `None` to pay the minting cost (see `Purse404Executor::preflight`)
+    MintERC721(Wallet, U256, Option<U256>),
     Mint(Wallet, Address, U256),
+    OwnerOf(U256),
+    TokenURI(U256),
+    GetApproved(U256),
+    /// Owner and operator
+    IsApprovedForAll(Address, Address),
+    /// Sender, from, to and token ID
+    Erc721TransferFrom(Wallet, Address, Address, U256),
+    /// Sender, from, to, token ID and data, `None` for the overload without data
+    SafeTransferFrom(Wallet, Address, Address, U256, Option<Bytes>),
+    /// Owner, spender and token ID
+    Erc721Approve(Wallet, Address, U256),
+    /// Owner, operator and approved
+    SetApprovalForAll(Wallet, Address, bool),
+    /// Owner and spender
+    Allowance(Address, Address),
+    /// Owner, spender and amount or token ID
+    Approve(Wallet, Address, U256),
+    /// Owner, spender and amount
+    Erc20Approve(Wallet, Address, U256),
+    /// Spender, from, to and amount
+    Erc20TransferFrom(Wallet, Address, Address, U256),
+    /// Spender, from, to and amount or token ID
+    TransferFrom(Wallet, Address, Address, U256),
+    /// Spender, account and amount
+    BurnFrom(Wallet, Address, U256),
 }
 
 impl Purse404FunctionCall {
@@ -123,7 +287,7 @@ impl Purse404FunctionCall {
     /// calldata, and wallet.
     /// ### Arguments
     /// * `function` - Function name
-    /// * `message_value` - Message value
+    /// * `message_value` - Message value, `None` if not given
     /// * `calldata` - Calldata
     /// * `wallet` - Wallet
     /// 
@@ -131,7 +295,7 @@ impl Purse404FunctionCall {
     /// * `Result<Self>` - Result
     pub fn from_data(
         function: &str,
-        message_value: &U256, 
+        message_value: Option<U256>, 
         calldata: Vec<String>, 
         wallet: Wallet
     ) -> Result<Self, eyre::Report> {
@@ -154,16 +318,155 @@ impl Purse404FunctionCall {
             },
             "mintERC721" => {
                 let mint_unit = U256::from_dec_str(&calldata[0])?;
-                Ok(Purse404FunctionCall::MintERC721(wallet, mint_unit, *message_value))
+                Ok(Purse404FunctionCall::MintERC721(wallet, mint_unit, message_value))
             },
             "mint" => {
                 let to = Address::from_str(&calldata[0])?;
                 let amount = U256::from_dec_str(&calldata[1])?;
                 Ok(Purse404FunctionCall::Mint(wallet, to, amount))
             },
+            "ownerOf" => {
+                let id = U256::from_dec_str(&calldata[0])?;
+                Ok(Purse404FunctionCall::OwnerOf(id))
+            },
+            "tokenURI" => {
+                let id = U256::from_dec_str(&calldata[0])?;
+                Ok(Purse404FunctionCall::TokenURI(id))
+            },
+            "getApproved" => {
+                let id = U256::from_dec_str(&calldata[0])?;
+                Ok(Purse404FunctionCall::GetApproved(id))
+            },
+            "isApprovedForAll" => {
+                let owner = Address::from_str(&calldata[0])?;
+                let operator = Address::from_str(&calldata[1])?;
+                Ok(Purse404FunctionCall::IsApprovedForAll(owner, operator))
+            },
+            "erc721TransferFrom" => {
+                let from = Address::from_str(&calldata[0])?;
+                let to = Address::from_str(&calldata[1])?;
+                let id = U256::from_dec_str(&calldata[2])?;
+                Ok(Purse404FunctionCall::Erc721TransferFrom(wallet, from, to, id))
+            },
+            "safeTransferFrom" => {
+                let from = Address::from_str(&calldata[0])?;
+                let to = Address::from_str(&calldata[1])?;
+                let id = U256::from_dec_str(&calldata[2])?;
+                let data = match calldata.get(3) {
+                    Some(data) => Some(Bytes::from_str(data)?),
+                    None => None,
+                };
+                Ok(Purse404FunctionCall::SafeTransferFrom(wallet, from, to, id, data))
+            },
+            "erc721Approve" => {
+                let spender = Address::from_str(&calldata[0])?;
+                let id = U256::from_dec_str(&calldata[1])?;
+                Ok(Purse404FunctionCall::Erc721Approve(wallet, spender, id))
+            },
+            "setApprovalForAll" => {
+                let operator = Address::from_str(&calldata[0])?;
+                let approved = bool::from_str(&calldata[1])?;
+                Ok(Purse404FunctionCall::SetApprovalForAll(wallet, operator, approved))
+            },
+            "allowance" => {
+                let owner = Address::from_str(&calldata[0])?;
+                let spender = Address::from_str(&calldata[1])?;
+                Ok(Purse404FunctionCall::Allowance(owner, spender))
+            },
+            "approve" => {
+                let spender = Address::from_str(&calldata[0])?;
+                let value = U256::from_dec_str(&calldata[1])?;
+                Ok(Purse404FunctionCall::Approve(wallet, spender, value))
+            },
+            "erc20Approve" => {
+                let spender = Address::from_str(&calldata[0])?;
+                let amount = U256::from_dec_str(&calldata[1])?;
+                Ok(Purse404FunctionCall::Erc20Approve(wallet, spender, amount))
+            },
+            "erc20TransferFrom" => {
+                let from = Address::from_str(&calldata[0])?;
+                let to = Address::from_str(&calldata[1])?;
+                let amount = U256::from_dec_str(&calldata[2])?;
+                Ok(Purse404FunctionCall::Erc20TransferFrom(wallet, from, to, amount))
+            },
+            "transferFrom" => {
+                let from = Address::from_str(&calldata[0])?;
+                let to = Address::from_str(&calldata[1])?;
+                let value = U256::from_dec_str(&calldata[2])?;
+                Ok(Purse404FunctionCall::TransferFrom(wallet, from, to, value))
+            },
+            "burnFrom" => {
+                let account = Address::from_str(&calldata[0])?;
+                let amount = U256::from_dec_str(&calldata[1])?;
+                Ok(Purse404FunctionCall::BurnFrom(wallet, account, amount))
+            },
             _ => Err(eyre::eyre!("Unsupported function: {}", function)),
         }
     }
+
+    /// Returns the msg value sent with the call, zero if none is set
+    pub fn msg_value(&self) -> U256 {
+        match self {
+            Purse404FunctionCall::MintERC721(_, _, Some(value)) => *value,
+            _ => U256::zero(),
+        }
+    }
+
+    /// Encodes the calldata of a state changing call against the contract ABI, no provider is needed.
+    /// ### Returns
+    /// * `Result<(&Wallet, Bytes)>` - The wallet sending the call, and the calldata. An error for view functions
+    pub fn encode_calldata(&self) -> Result<(&Wallet, Bytes)> {
+        let (wallet, function, tokens) = match self {
+            Purse404FunctionCall::Transfer(wallet, to, amount) => {
+                (wallet, "transfer", vec![Token::Address(*to), Token::Uint(*amount)])
+            },
+            Purse404FunctionCall::MintERC721(wallet, mint_unit, _) => {
+                (wallet, "mintERC721", vec![Token::Uint(*mint_unit)])
+            },
+            Purse404FunctionCall::Mint(wallet, to, amount) => {
+                (wallet, "mint", vec![Token::Address(*to), Token::Uint(*amount)])
+            },
+            Purse404FunctionCall::Erc721TransferFrom(wallet, from, to, id) => {
+                (wallet, "erc721TransferFrom", vec![Token::Address(*from), Token::Address(*to), Token::Uint(*id)])
+            },
+            Purse404FunctionCall::SafeTransferFrom(wallet, from, to, id, data) => {
+                let mut tokens = vec![Token::Address(*from), Token::Address(*to), Token::Uint(*id)];
+                if let Some(data) = data {
+                    tokens.push(Token::Bytes(data.to_vec()));
+                }
+                (wallet, "safeTransferFrom", tokens)
+            },
+            Purse404FunctionCall::Erc721Approve(wallet, spender, id) => {
+                (wallet, "erc721Approve", vec![Token::Address(*spender), Token::Uint(*id)])
+            },
+            Purse404FunctionCall::SetApprovalForAll(wallet, operator, approved) => {
+                (wallet, "setApprovalForAll", vec![Token::Address(*operator), Token::Bool(*approved)])
+            },
+            Purse404FunctionCall::Approve(wallet, spender, value) => {
+                (wallet, "approve", vec![Token::Address(*spender), Token::Uint(*value)])
+            },
+            Purse404FunctionCall::Erc20Approve(wallet, spender, amount) => {
+                (wallet, "erc20Approve", vec![Token::Address(*spender), Token::Uint(*amount)])
+            },
+            Purse404FunctionCall::Erc20TransferFrom(wallet, from, to, amount) => {
+                (wallet, "erc20TransferFrom", vec![Token::Address(*from), Token::Address(*to), Token::Uint(*amount)])
+            },
+            Purse404FunctionCall::TransferFrom(wallet, from, to, value) => {
+                (wallet, "transferFrom", vec![Token::Address(*from), Token::Address(*to), Token::Uint(*value)])
+            },
+            Purse404FunctionCall::BurnFrom(wallet, account, amount) => {
+                (wallet, "burnFrom", vec![Token::Address(*account), Token::Uint(*amount)])
+            },
+            _ => return Err(eyre::eyre!("> View functions do not send a transaction, there is no calldata to encode")),
+        };
+        let abi_function = PURSE404_ABI
+            .functions_by_name(function)?
+            .iter()
+            .find(|f| f.inputs.len() == tokens.len())
+            .ok_or_else(|| eyre::eyre!("No {} function with {} arguments in the ABI", function, tokens.len()))?;
+
+        Ok((wallet, abi_function.encode_input(&tokens)?.into()))
+    }
 }
 
 /// Purse404 Contract Functions Results (See: `purse_contract.rs`)
@@ -173,12 +476,93 @@ pub enum Purse404Results {
     U256VecResult(Vec<U256>),
     StringResult(String),
     StringVecResult(Vec<String>),
+    BoolResult(bool),
     StateChangeResult((String, String, String, String, String)),
 }
 
 pub struct Purse404Executor;
 
 impl Purse404Executor {
+    /// Checks that the given function call would not revert for a known reason, before it is sent.
+    /// 
+    /// For `mintERC721`, the msg value is set to the minting cost of the minted units if it is not given,
+    /// and the call is refused if the contract is paused, the given msg value is not the minting cost
+    /// (`IncorrectEthValue()`), the minter's inactive balance cannot back the minted units
+    /// (`InsufficientInactiveBalance()`), or the units would mint past the max token ID (`MintLimitReached()`).
+    /// For `mint`, the call is refused if the sender does not hold `MINTER_ROLE`.
+    /// Other calls are returned as is.
+    /// ### Arguments
+    /// * `contract` - Purse404 contract
+    /// * `call` - Function call
+    /// 
+    /// ### Returns
+    /// * `Result<Purse404FunctionCall>` - The function call, with the msg value set
+    pub async fn preflight<M: Middleware + 'static>(
+        contract: &Purse404Contract<M>,
+        call: Purse404FunctionCall,
+    ) -> Result<Purse404FunctionCall> {
+        let (wallet, mint_units, msg_value) = match call {
+            Purse404FunctionCall::MintERC721(wallet, mint_units, msg_value) => (wallet, mint_units, msg_value),
+            Purse404FunctionCall::Mint(wallet, to, amount) => {
+                contract.require_role(Role::Minter, &wallet.address(), "mint").await?;
+                return Ok(Purse404FunctionCall::Mint(wallet, to, amount))
+            },
+            call => return Ok(call),
+        };
+        if mint_units.is_zero() {
+            return Err(eyre::eyre!("> mintERC721: the number of units to mint must be at least 1"))
+        }
+        if contract.paused().await? {
+            return Err(eyre::eyre!("> mintERC721: the contract is paused, the transaction would revert"))
+        }
+
+        let minting_cost = contract.minting_cost().await?;
+        let required_value = minting_cost.checked_mul(mint_units).ok_or_else(|| eyre::eyre!(
+            "> mintERC721: minting {} unit(s) at {} wei each overflows the msg value",
+            mint_units, minting_cost
+        ))?;
+        let msg_value = match msg_value {
+            Some(value) if value != required_value => return Err(eyre::eyre!(
+                "> mintERC721: msg value {} wei would revert with IncorrectEthValue(), minting {} unit(s) at {} wei each costs {} wei. \
+                Omit --msg-value to pay the minting cost",
+                value, mint_units, minting_cost, required_value
+            )),
+            Some(value) => value,
+            None => {
+                println!("> mintERC721: msg value set to the minting cost, {} wei", required_value);
+                required_value
+            }
+        };
+
+        let units = contract.units().await?;
+        let required_balance = units.checked_mul(mint_units).ok_or_else(|| eyre::eyre!(
+            "> mintERC721: minting {} unit(s) overflows the required inactive ERC20 balance",
+            mint_units
+        ))?;
+        let inactive = contract.inactive_balance(&wallet.address()).await?;
+        if inactive < required_balance {
+            return Err(eyre::eyre!(
+                "> mintERC721: would revert with InsufficientInactiveBalance(), minting {} unit(s) needs {} inactive ERC20 \
+                but {:?} only has {}, the rest of its balance backs the NFTs it owns",
+                mint_units,
+                str_wei_to_eth(&required_balance.to_string()),
+                wallet.address(),
+                str_wei_to_eth(&inactive.to_string())
+            ))
+        }
+
+        let minted = contract.minted().await?;
+        let max_token_id = contract.erc721_max_token_id().await?;
+        if minted.checked_add(mint_units).is_none_or(|total| total > max_token_id) {
+            return Err(eyre::eyre!(
+                "> mintERC721: would revert with MintLimitReached(), {} NFTs minted, minting {} more goes past the max token ID {}",
+                minted, mint_units, max_token_id
+            ))
+        }
+
+        Ok(Purse404FunctionCall::MintERC721(wallet, mint_units, Some(msg_value)))
+    }
+
     /// Execute the given function call on the Purse404 contract.
     /// ### Arguments
     /// * `contract` - Purse404 contract
@@ -220,6 +604,10 @@ impl Purse404Executor {
                 Ok(Purse404Results::StateChangeResult(res))
             },
             Purse404FunctionCall::MintERC721(wallet, mint_unit, msg_value) => {
+                let msg_value = match msg_value {
+                    Some(value) => value,
+                    None => return Err(eyre::eyre!("> mintERC721: no msg value set, see `Purse404Executor::preflight`")),
+                };
                 let res = contract.mint_erc721(
                     &wallet, 
                     &mint_unit, 
@@ -234,6 +622,81 @@ impl Purse404Executor {
                     &amount
                 ).await?;
                 Ok(Purse404Results::StateChangeResult(res))
+            },
+            Purse404FunctionCall::OwnerOf(id) => {
+                let res = contract.owner_of(&id).await?;
+                Ok(Purse404Results::Address(res))
+            },
+            Purse404FunctionCall::TokenURI(id) => {
+                let res = contract.token_uri(&id).await?;
+                Ok(Purse404Results::StringResult(res))
+            },
+            Purse404FunctionCall::GetApproved(id) => {
+                let res = contract.get_approved(&id).await?;
+                Ok(Purse404Results::Address(res))
+            },
+            Purse404FunctionCall::IsApprovedForAll(owner, operator) => {
+                let res = contract.is_approved_for_all(&owner, &operator).await?;
+                Ok(Purse404Results::BoolResult(res))
+            },
+            Purse404FunctionCall::Erc721TransferFrom(wallet, from, to, id) => {
+                let res = contract.erc721_transfer_from(
+                    &wallet, 
+                    &from, 
+                    &to, 
+                    &id
+                ).await?;
+                Ok(Purse404Results::StateChangeResult(res))
+            },
+            Purse404FunctionCall::SafeTransferFrom(wallet, from, to, id, data) => {
+                let res = contract.safe_transfer_from(
+                    &wallet, 
+                    &from, 
+                    &to, 
+                    &id,
+                    data.as_ref()
+                ).await?;
+                Ok(Purse404Results::StateChangeResult(res))
+            },
+            Purse404FunctionCall::Erc721Approve(wallet, spender, id) => {
+                let res = contract.erc721_approve(
+                    &wallet, 
+                    &spender, 
+                    &id
+                ).await?;
+                Ok(Purse404Results::StateChangeResult(res))
+            },
+            Purse404FunctionCall::SetApprovalForAll(wallet, operator, approved) => {
+                let res = contract.set_approval_for_all(
+                    &wallet, 
+                    &operator, 
+                    approved
+                ).await?;
+                Ok(Purse404Results::StateChangeResult(res))
+            },
+            Purse404FunctionCall::Allowance(owner, spender) => {
+                let res = contract.allowance(&owner, &spender, None).await?;
+                Ok(Purse404Results::U256Result(res))
+            },
+            Purse404FunctionCall::Approve(wallet, spender, value) => {
+                let res = contract.approve(&wallet, &spender, &value).await?;
+                Ok(Purse404Results::StateChangeResult(res))
+            },
+            Purse404FunctionCall::Erc20Approve(wallet, spender, amount) => {
+                let res = contract.erc20_approve(&wallet, &spender, &amount).await?;
+                Ok(Purse404Results::StateChangeResult(res))
+            },
+            Purse404FunctionCall::Erc20TransferFrom(wallet, from, to, amount) => {
+                let res = contract.erc20_transfer_from(&wallet, &from, &to, &amount).await?;
+                Ok(Purse404Results::StateChangeResult(res))
+            },
+            Purse404FunctionCall::TransferFrom(wallet, from, to, value) => {
+                let res = contract.transfer_from(&wallet, &from, &to, &value).await?;
+                Ok(Purse404Results::StateChangeResult(res))
+            },
+            Purse404FunctionCall::BurnFrom(wallet, account, amount) => {
+                let res = contract.burn_from(&wallet, &account, &amount).await?;
+                Ok(Purse404Results::StateChangeResult(res))
             }
         }
     }
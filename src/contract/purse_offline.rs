@@ -0,0 +1,233 @@
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use ethers::{
+    signers::Signer,
+    types::{
+        Address, Bytes, Eip1559TransactionRequest, NameOrAddress, TransactionRequest, H256, U256,
+        transaction::eip2718::TypedTransaction,
+    },
+    utils::{keccak256, rlp::Rlp},
+};
+use crate::{
+    contract::{
+        purse_contract::SignedTransaction,
+        purse_executor::{decode_purse_calldata, Purse404FunctionCall},
+    },
+    utils::unix_timestamp,
+};
+
+/// Fee fields of an offline signed transaction, given explicitly since no provider is queried
+/// With traits `Clone`, `Copy`, `Debug`, `PartialEq`, `Serialize` and `Deserialize`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TxFees {
+    /// EIP-1559 transaction, max fee and max priority fee per gas (wei)
+    Eip1559 { max_fee_per_gas: U256, max_priority_fee_per_gas: U256 },
+    /// Legacy transaction, gas price (wei)
+    Legacy { gas_price: U256 },
+}
+
+impl TxFees {
+    /// Returns the most the transaction can cost in fees (wei), at the given gas limit
+    /// ### Returns
+    /// * `Result<U256>` - Max fee, errors if it overflows
+    pub fn max_cost(&self, gas_limit: U256) -> Result<U256> {
+        let fee = match self {
+            TxFees::Eip1559 { max_fee_per_gas, .. } => *max_fee_per_gas,
+            TxFees::Legacy { gas_price } => *gas_price,
+        };
+        fee.checked_mul(gas_limit)
+            .ok_or_else(|| eyre::eyre!("> Fee per gas {} wei times gas limit {} overflows", fee, gas_limit))
+    }
+}
+
+/// A transaction signed without a provider, as written by `offline sign` and read by `offline broadcast`
+/// With traits `Clone`, `Debug`, `PartialEq`, `Serialize` and `Deserialize`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OfflineTransaction {
+    /// Chain Id the transaction was signed for
+    pub chain_id: u64,
+    /// Address of the token contract the transaction calls
+    pub contract: Address,
+    /// Address of the signing wallet
+    pub from: Address,
+    /// Derivation number of the signing wallet
+    pub derivation: u32,
+    /// Function called, decoded from the signed calldata
+    pub function: String,
+    /// Function arguments, decoded from the signed calldata (see `decode_purse_calldata`)
+    pub calldata: Vec<String>,
+    /// Msg value (wei)
+    pub value: U256,
+    pub nonce: U256,
+    pub gas_limit: U256,
+    pub fees: TxFees,
+    /// Transaction hash
+    pub hash: H256,
+    /// Raw RLP encoded signed transaction
+    pub raw: Bytes,
+    /// Unix timestamp (seconds) of the signing
+    pub signed_at: u64,
+}
+
+impl OfflineTransaction {
+    /// Decodes the raw transaction and checks it is the transaction described by the metadata:
+    /// the hash, signer, chain id, nonce, contract, msg value and calldata must all match,
+    /// so an edited file is refused before it is broadcast
+    /// ### Returns
+    /// * `Result<()>` - An error naming the first mismatch
+    pub fn verify(&self) -> Result<()> {
+        let (tx, signature) = TypedTransaction::decode_signed(&Rlp::new(&self.raw))
+            .map_err(|e| eyre::eyre!("> Raw transaction cannot be decoded: {}", e))?;
+
+        if H256::from(keccak256(&self.raw)) != self.hash {
+            return Err(eyre::eyre!("> Raw transaction does not hash to {:?}", self.hash))
+        }
+        let signer = signature.recover(tx.sighash())?;
+        if signer != self.from {
+            return Err(eyre::eyre!("> Raw transaction is signed by {:?}, not {:?}", signer, self.from))
+        }
+        if tx.chain_id().map(|id| id.as_u64()) != Some(self.chain_id) {
+            return Err(eyre::eyre!("> Raw transaction is for chain {:?}, not {}", tx.chain_id(), self.chain_id))
+        }
+        if tx.nonce() != Some(&self.nonce) {
+            return Err(eyre::eyre!("> Raw transaction has nonce {:?}, not {}", tx.nonce(), self.nonce))
+        }
+        if tx.to() != Some(&NameOrAddress::Address(self.contract)) {
+            return Err(eyre::eyre!("> Raw transaction is not sent to {:?}", self.contract))
+        }
+        if tx.value().copied().unwrap_or_default() != self.value {
+            return Err(eyre::eyre!("> Raw transaction has msg value {:?}, not {}", tx.value(), self.value))
+        }
+        let data = tx.data().cloned().unwrap_or_default();
+        if decode_purse_calldata(&data)? != (self.function.clone(), self.calldata.clone()) {
+            return Err(eyre::eyre!("> Raw transaction does not call {}({})", self.function, self.calldata.join(", ")))
+        }
+        Ok(())
+    }
+
+    /// Returns the transaction to broadcast, see `Purse404Contract::send_signed_transaction`
+    pub fn signed(&self) -> SignedTransaction {
+        SignedTransaction {
+            from: self.from,
+            derivation: self.derivation,
+            nonce: self.nonce,
+            chain_id: self.chain_id,
+            hash: self.hash,
+            raw: self.raw.clone(),
+        }
+    }
+}
+
+/// Builds and signs the transaction of a state changing function call, without a provider.
+/// The chain id is the one the sending wallet was created with, and the nonce, gas limit and fees are used as given.
+/// ### Arguments
+/// * `contract` - Address of the token contract
+/// * `call` - Function call, a msg value must be set for `mintERC721` since the minting cost cannot be read
+/// * `nonce` - Nonce of the sending wallet
+/// * `gas_limit` - Gas limit
+/// * `fees` - Fee fields
+/// 
+/// ### Returns
+/// * `Result<OfflineTransaction>` - The signed transaction with its metadata
+pub async fn sign_offline(
+    contract: Address,
+    call: &Purse404FunctionCall,
+    nonce: U256,
+    gas_limit: U256,
+    fees: TxFees,
+) -> Result<OfflineTransaction> {
+    if let Purse404FunctionCall::MintERC721(_, _, None) = call {
+        return Err(eyre::eyre!("> mintERC721: a msg value is required offline, the minting cost cannot be read"))
+    }
+    let (wallet, data) = call.encode_calldata()?;
+    let value = call.msg_value();
+    let chain_id = wallet.signer.chain_id();
+
+    let tx: TypedTransaction = match fees {
+        TxFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => Eip1559TransactionRequest::new()
+            .from(wallet.address())
+            .to(contract)
+            .data(data.clone())
+            .value(value)
+            .nonce(nonce)
+            .gas(gas_limit)
+            .max_fee_per_gas(max_fee_per_gas)
+            .max_priority_fee_per_gas(max_priority_fee_per_gas)
+            .chain_id(chain_id)
+            .into(),
+        TxFees::Legacy { gas_price } => TransactionRequest::new()
+            .from(wallet.address())
+            .to(contract)
+            .data(data.clone())
+            .value(value)
+            .nonce(nonce)
+            .gas(gas_limit)
+            .gas_price(gas_price)
+            .chain_id(chain_id)
+            .into(),
+    };
+    let signature = wallet.signer.sign_transaction(&tx).await?;
+    let (function, calldata) = decode_purse_calldata(&data)?;
+
+    Ok(OfflineTransaction {
+        chain_id,
+        contract,
+        from: wallet.address(),
+        derivation: wallet.derivation,
+        function,
+        calldata,
+        value,
+        nonce,
+        gas_limit,
+        fees,
+        hash: tx.hash(&signature),
+        raw: tx.rlp_signed(&signature),
+        signed_at: unix_timestamp(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::Wallet;
+
+    #[tokio::test]
+    async fn test_sign_offline() {
+        let wallet = Wallet::from_phrase(
+            "test test test test test test test test test test test junk",
+            1,
+            11155111
+        ).unwrap();
+        let contract = Address::repeat_byte(0x11);
+        let to = Address::repeat_byte(0x22);
+        let call = Purse404FunctionCall::Transfer(wallet.clone(), to, U256::exp10(18));
+        let fees = TxFees::Eip1559 {
+            max_fee_per_gas: U256::from(30_000_000_000u64),
+            max_priority_fee_per_gas: U256::from(1_000_000_000u64),
+        };
+
+        let offline = sign_offline(contract, &call, U256::from(7), U256::from(120_000), fees).await.unwrap();
+        assert_eq!(offline.chain_id, 11155111);
+        assert_eq!(offline.from, wallet.address());
+        assert_eq!(offline.derivation, 1);
+        assert_eq!(offline.function, "transfer");
+        assert_eq!(offline.calldata, vec![format!("{:?}", to), U256::exp10(18).to_string()]);
+        offline.verify().unwrap();
+
+        let legacy = sign_offline(contract, &call, U256::from(7), U256::from(120_000), TxFees::Legacy {
+            gas_price: U256::from(30_000_000_000u64),
+        }).await.unwrap();
+        legacy.verify().unwrap();
+
+        let mut edited = offline.clone();
+        edited.nonce = U256::from(8);
+        assert!(edited.verify().is_err());
+        let mut edited = offline.clone();
+        edited.calldata[1] = "1".to_string();
+        assert!(edited.verify().is_err());
+
+        let view = Purse404FunctionCall::BalanceOf(to);
+        assert!(sign_offline(contract, &view, U256::zero(), U256::from(120_000), fees).await.is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use ethers::{
+    providers::Middleware,
+    signers::Signer,
+    types::{
+        Address, BigEndianHash, H256, U256,
+        transaction::eip712::{EIP712Domain, Eip712, TypedData},
+    },
+};
+use crate::{
+    contract::purse_contract::Purse404Contract,
+    wallet::Wallet,
+};
+
+/// A signed EIP-2612 permit, as written by `permit sign` and read by `permit submit`
+/// With traits `Clone`, `Debug`, `PartialEq`, `Serialize` and `Deserialize`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedPermit {
+    /// Chain Id the permit was signed for
+    pub chain_id: u64,
+    /// Address of the token contract verifying the permit
+    pub contract: Address,
+    /// Owner of the tokens, the signer of the permit
+    pub owner: Address,
+    /// Spender the allowance is set for
+    pub spender: Address,
+    /// Allowance (wei)
+    pub value: U256,
+    /// Permit nonce of the owner at signing time
+    pub nonce: U256,
+    /// Unix timestamp (seconds) after which the permit is rejected
+    pub deadline: U256,
+    pub v: u8,
+    pub r: H256,
+    pub s: H256,
+}
+
+/// Builds the EIP-712 typed data of an EIP-2612 permit:
+/// `Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)`
+/// ### Arguments
+/// * `domain` - Signing domain of the token contract, see `Purse404Contract::eip712_domain`
+/// * `owner` - Owner of the tokens
+/// * `spender` - Spender
+/// * `value` - Allowance (wei)
+/// * `nonce` - Permit nonce of the owner
+/// * `deadline` - Unix timestamp (seconds) the permit is valid until
+/// 
+/// ### Returns
+/// * `Result<TypedData>` - The typed data to sign
+pub fn permit_typed_data(
+    domain: EIP712Domain,
+    owner: Address,
+    spender: Address,
+    value: U256,
+    nonce: U256,
+    deadline: U256,
+) -> Result<TypedData> {
+    let typed_data = serde_json::json!({
+        "domain": domain,
+        "types": {
+            "Permit": [
+                { "name": "owner", "type": "address" },
+                { "name": "spender", "type": "address" },
+                { "name": "value", "type": "uint256" },
+                { "name": "nonce", "type": "uint256" },
+                { "name": "deadline", "type": "uint256" },
+            ]
+        },
+        "primaryType": "Permit",
+        "message": {
+            "owner": owner,
+            "spender": spender,
+            "value": value.to_string(),
+            "nonce": nonce.to_string(),
+            "deadline": deadline.to_string(),
+        }
+    });
+
+    Ok(serde_json::from_value(typed_data)?)
+}
+
+/// Signs an EIP-2612 permit with the given wallet, using the domain and the owner nonce read on-chain.
+/// The deadline is not checked, so an expired permit can be signed on purpose.
+/// ### Arguments
+/// * `purse_token` - Purse404 contract
+/// * `owner` - a `Wallet` reference, the owner of the tokens
+/// * `spender` - Spender
+/// * `value` - Allowance (wei)
+/// * `deadline` - Unix timestamp (seconds) the permit is valid until
+/// 
+/// ### Returns
+/// * `Result<SignedPermit>` - The signed permit
+pub async fn sign_permit<M: Middleware + 'static>(
+    purse_token: &Purse404Contract<M>,
+    owner: &Wallet,
+    spender: Address,
+    value: U256,
+    deadline: U256,
+) -> Result<SignedPermit> {
+    let domain = purse_token.eip712_domain().await?;
+    let separator = purse_token.domain_separator().await?;
+    if domain.separator() != separator {
+        return Err(eyre::eyre!(
+            "> Domain separator of {:?} does not match its eip712Domain, refusing to sign",
+            purse_token.address()
+        ))
+    }
+    let chain_id = match domain.chain_id {
+        Some(chain_id) => chain_id.as_u64(),
+        None => owner.signer.chain_id(),
+    };
+    let nonce = purse_token.nonces(&owner.address()).await?;
+
+    let typed_data = permit_typed_data(domain, owner.address(), spender, value, nonce, deadline)?;
+    let signature = owner.signer.sign_typed_data(&typed_data).await?;
+    // The contract recovers the signer from the same digest
+    let digest = typed_data.encode_eip712()?;
+    signature.verify(digest, owner.address())?;
+
+    Ok(SignedPermit {
+        chain_id,
+        contract: purse_token.address(),
+        owner: owner.address(),
+        spender,
+        value,
+        nonce,
+        deadline,
+        v: signature.v as u8,
+        r: H256::from_uint(&signature.r),
+        s: H256::from_uint(&signature.s),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        abi::{encode, Token},
+        utils::keccak256,
+    };
+
+    #[tokio::test]
+    async fn test_permit_typed_data() {
+        let domain = EIP712Domain {
+            name: Some("Purse".to_string()),
+            version: Some("1".to_string()),
+            chain_id: Some(U256::from(11155111)),
+            verifying_contract: Some(Address::repeat_byte(0x11)),
+            salt: None,
+        };
+        let owner = Address::repeat_byte(0x22);
+        let spender = Address::repeat_byte(0x33);
+        let (value, nonce, deadline) = (U256::exp10(18), U256::from(2), U256::from(1_700_000_000u64));
+        let typed_data = permit_typed_data(domain.clone(), owner, spender, value, nonce, deadline).unwrap();
+
+        let struct_hash = keccak256(encode(&[
+            Token::FixedBytes(keccak256("Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)").to_vec()),
+            Token::Address(owner),
+            Token::Address(spender),
+            Token::Uint(value),
+            Token::Uint(nonce),
+            Token::Uint(deadline),
+        ]));
+        let digest = keccak256([&[0x19, 0x01], &domain.separator()[..], &struct_hash[..]].concat());
+        assert_eq!(typed_data.encode_eip712().unwrap(), digest);
+
+        let wallet = Wallet::from_phrase(
+            "test test test test test test test test test test test junk",
+            0,
+            11155111
+        ).unwrap();
+        let signature = wallet.signer.sign_typed_data(&typed_data).await.unwrap();
+        assert_eq!(signature.recover(digest).unwrap(), wallet.address());
+    }
+}
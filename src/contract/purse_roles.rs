@@ -0,0 +1,91 @@
+use clap::ValueEnum;
+use ethers::utils::keccak256;
+
+/// AccessControl roles of the Purse404 contract
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Role {
+    /// `DEFAULT_ADMIN_ROLE`, admin of every other role
+    DefaultAdmin,
+    /// `MINTER_ROLE`, may call `mint`
+    Minter,
+    /// `PAUSER_ROLE`, may call `pause` and `unpause`
+    Pauser,
+    /// `UPGRADER_ROLE`, may call `upgradeToAndCall`
+    Upgrader,
+}
+
+impl Role {
+    /// All roles, in the order they are listed
+    pub const ALL: [Role; 4] = [Role::DefaultAdmin, Role::Minter, Role::Pauser, Role::Upgrader];
+
+    /// Returns the name of the role constant in the contract, eg: "MINTER_ROLE"
+    pub fn name(&self) -> &'static str {
+        match self {
+            Role::DefaultAdmin => "DEFAULT_ADMIN_ROLE",
+            Role::Minter => "MINTER_ROLE",
+            Role::Pauser => "PAUSER_ROLE",
+            Role::Upgrader => "UPGRADER_ROLE",
+        }
+    }
+
+    /// Returns the `bytes32` role identifier.
+    /// `DEFAULT_ADMIN_ROLE` is zero, the other roles are the keccak256 hash of their name.
+    pub fn id(&self) -> [u8; 32] {
+        match self {
+            Role::DefaultAdmin => [0u8; 32],
+            role => keccak256(role.name()),
+        }
+    }
+
+    /// Returns the role a function of the contract is gated by
+    /// ### Arguments
+    /// * `func` - Function name, eg: "mint"
+    /// 
+    /// ### Returns
+    /// * `Option<Role>` - The role, `None` for functions anyone may call
+    pub fn gating(func: &str) -> Option<Role> {
+        match func {
+            "mint" => Some(Role::Minter),
+            "pause" | "unpause" => Some(Role::Pauser),
+            "upgradeToAndCall" => Some(Role::Upgrader),
+            "setMint721Cost" | "setBaseURI" | "setMaxTokenId" | "setTreasuryAddress"
+            | "recoverEth" | "recoverToken" => Some(Role::DefaultAdmin),
+            _ => None
+        }
+    }
+
+    /// Finds the role of the given `bytes32` role identifier
+    /// ### Arguments
+    /// * `id` - Role identifier
+    /// 
+    /// ### Returns
+    /// * `Option<Role>` - The role, `None` for an unknown identifier
+    pub fn from_id(id: &[u8; 32]) -> Option<Role> {
+        Role::ALL.into_iter().find(|role| role.id() == *id)
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_ids() {
+        assert_eq!(Role::DefaultAdmin.id(), [0u8; 32]);
+        assert_eq!(
+            ethers::utils::hex::encode(Role::Minter.id()),
+            "9f2df0fed2c77648de5860a4cc508cd0818c85b8b8a1ab4ceeef8d981c8956a6"
+        );
+        for role in Role::ALL {
+            assert_eq!(Role::from_id(&role.id()), Some(role));
+        }
+        assert_eq!(Role::gating("mint"), Some(Role::Minter));
+        assert_eq!(Role::gating("transfer"), None);
+    }
+}
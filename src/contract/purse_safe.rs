@@ -0,0 +1,408 @@
+use eyre::Result;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use ethers::{
+    abi::{encode, AbiEncode, Token},
+    contract::abigen,
+    providers::Middleware,
+    types::{Address, Bytes, Signature, H256, U256},
+    utils::keccak256,
+};
+use crate::wallet::Wallet;
+
+abigen!(
+    GnosisSafe,
+    r#"[
+        function nonce() external view returns (uint256)
+        function getThreshold() external view returns (uint256)
+        function getOwners() external view returns (address[])
+        function VERSION() external view returns (string)
+        function getTransactionHash(address to, uint256 value, bytes data, uint8 operation, uint256 safeTxGas, uint256 baseGas, uint256 gasPrice, address gasToken, address refundReceiver, uint256 _nonce) external view returns (bytes32)
+    ]"#,
+);
+
+abigen!(
+    MultiSendCallOnly,
+    r#"[function multiSend(bytes transactions) external payable]"#,
+);
+
+/// Safe operation of a plain call
+pub const SAFE_OPERATION_CALL: u8 = 0;
+
+/// Safe operation of a delegate call, used to batch calls through MultiSend
+pub const SAFE_OPERATION_DELEGATE_CALL: u8 = 1;
+
+/// Owners, threshold, nonce and version of a Safe, read with `read_safe_state`
+/// With traits `Clone`, `Debug` and `PartialEq`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SafeState {
+    pub nonce: U256,
+    pub threshold: u64,
+    pub owners: Vec<Address>,
+    pub version: String,
+}
+
+/// A call made by a Safe transaction
+/// With traits `Clone`, `Debug` and `PartialEq`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SafeCall {
+    /// Called contract
+    pub to: Address,
+    /// Msg value (wei), sent from the Safe balance
+    pub value: U256,
+    /// Calldata
+    pub data: Bytes,
+    /// Function call with its arguments, for review, eg: "pause()"
+    pub description: String,
+}
+
+/// An owner signature of a Safe transaction
+/// With traits `Clone`, `Debug`, `PartialEq`, `Serialize` and `Deserialize`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SafeSignature {
+    pub owner: Address,
+    /// 65 byte ECDSA signature of the Safe transaction hash, `r || s || v`
+    pub signature: Bytes,
+}
+
+/// A Safe transaction proposal, as written by `safe propose` and signed by owners with `safe sign`.
+/// The fields of the Safe transaction use the Safe naming and decimal strings.
+/// With traits `Clone`, `Debug`, `PartialEq`, `Serialize` and `Deserialize`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeTransaction {
+    /// Address of the Safe
+    pub safe: Address,
+    pub chain_id: u64,
+    /// Safe contract version, the signing domain depends on it
+    pub safe_version: String,
+    pub to: Address,
+    #[serde(with = "decimal")]
+    pub value: U256,
+    pub data: Bytes,
+    /// 0 for a call, 1 for a delegate call
+    pub operation: u8,
+    #[serde(with = "decimal")]
+    pub safe_tx_gas: U256,
+    #[serde(with = "decimal")]
+    pub base_gas: U256,
+    #[serde(with = "decimal")]
+    pub gas_price: U256,
+    pub gas_token: Address,
+    pub refund_receiver: Address,
+    #[serde(with = "decimal")]
+    pub nonce: U256,
+    /// EIP-712 hash the owners sign
+    pub safe_tx_hash: H256,
+    /// Calls made by the transaction, for review
+    pub calls: Vec<String>,
+    /// Owners of the Safe at proposal time
+    pub owners: Vec<Address>,
+    /// Number of owner signatures required at proposal time
+    pub threshold: u64,
+    /// Owner signatures, sorted by owner as the Safe requires
+    pub signatures: Vec<SafeSignature>,
+}
+
+impl SafeTransaction {
+    /// Create a Safe transaction making the given calls, at the current nonce of the Safe.
+    /// A single call is made directly, several are batched as a delegate call to MultiSend.
+    /// No refund is paid, so the gas fields are zero.
+    /// ### Arguments
+    /// * `safe` - Address of the Safe
+    /// * `chain_id` - Chain Id
+    /// * `state` - State of the Safe, see `read_safe_state`
+    /// * `calls` - Calls, at least one
+    /// * `multisend` - Address of the MultiSendCallOnly contract, used for batches only
+    /// 
+    /// ### Returns
+    /// * `Result<Self>` - The Safe transaction, without signatures
+    pub fn new(safe: Address, chain_id: u64, state: &SafeState, calls: &[SafeCall], multisend: Address) -> Result<Self> {
+        let (to, value, data, operation) = match calls {
+            [] => return Err(eyre::eyre!("> A Safe transaction needs at least one call")),
+            [call] => (call.to, call.value, call.data.clone(), SAFE_OPERATION_CALL),
+            calls => (multisend, U256::zero(), encode_multisend(calls), SAFE_OPERATION_DELEGATE_CALL),
+        };
+        let mut tx = Self {
+            safe,
+            chain_id,
+            safe_version: state.version.clone(),
+            to,
+            value,
+            data,
+            operation,
+            safe_tx_gas: U256::zero(),
+            base_gas: U256::zero(),
+            gas_price: U256::zero(),
+            gas_token: Address::zero(),
+            refund_receiver: Address::zero(),
+            nonce: state.nonce,
+            safe_tx_hash: H256::zero(),
+            calls: calls.iter().map(|call| call.description.clone()).collect(),
+            owners: state.owners.clone(),
+            threshold: state.threshold,
+            signatures: Vec::new(),
+        };
+        tx.safe_tx_hash = tx.hash();
+        Ok(tx)
+    }
+
+    /// Computes the EIP-712 hash of the Safe transaction.
+    /// Safes before 1.3.0 sign without the chain id in their domain.
+    /// ### Returns
+    /// * `H256` - The Safe transaction hash
+    pub fn hash(&self) -> H256 {
+        let legacy_domain = ["1.0.", "1.1.", "1.2."].iter().any(|v| self.safe_version.starts_with(v));
+        let domain_separator = match legacy_domain {
+            true => keccak256(encode(&[
+                Token::FixedBytes(keccak256("EIP712Domain(address verifyingContract)").to_vec()),
+                Token::Address(self.safe),
+            ])),
+            false => keccak256(encode(&[
+                Token::FixedBytes(keccak256("EIP712Domain(uint256 chainId,address verifyingContract)").to_vec()),
+                Token::Uint(U256::from(self.chain_id)),
+                Token::Address(self.safe),
+            ])),
+        };
+        let struct_hash = keccak256(encode(&[
+            Token::FixedBytes(keccak256(
+                "SafeTx(address to,uint256 value,bytes data,uint8 operation,uint256 safeTxGas,uint256 baseGas,\
+                uint256 gasPrice,address gasToken,address refundReceiver,uint256 nonce)"
+            ).to_vec()),
+            Token::Address(self.to),
+            Token::Uint(self.value),
+            Token::FixedBytes(keccak256(&self.data).to_vec()),
+            Token::Uint(U256::from(self.operation)),
+            Token::Uint(self.safe_tx_gas),
+            Token::Uint(self.base_gas),
+            Token::Uint(self.gas_price),
+            Token::Address(self.gas_token),
+            Token::Address(self.refund_receiver),
+            Token::Uint(self.nonce),
+        ]));
+        keccak256([&[0x19, 0x01], &domain_separator[..], &struct_hash[..]].concat()).into()
+    }
+
+    /// Checks the recorded hash is the hash of the transaction fields, and that every signature
+    /// recovers to its owner, so an edited file is refused
+    /// ### Returns
+    /// * `Result<()>` - An error naming the first mismatch
+    pub fn verify(&self) -> Result<()> {
+        if self.hash() != self.safe_tx_hash {
+            return Err(eyre::eyre!("> Safe transaction fields do not hash to {:?}", self.safe_tx_hash))
+        }
+        for signature in &self.signatures {
+            let signer = Signature::try_from(signature.signature.as_ref())?.recover(self.safe_tx_hash)?;
+            if signer != signature.owner {
+                return Err(eyre::eyre!("> Signature of {:?} recovers to {:?}", signature.owner, signer))
+            }
+        }
+        Ok(())
+    }
+
+    /// Signs the Safe transaction hash with the given owner, replacing an earlier signature of the owner.
+    /// ### Arguments
+    /// * `owner` - Wallet of a Safe owner
+    /// 
+    /// ### Returns
+    /// * `Result<()>` - Result
+    pub fn sign(&mut self, owner: &Wallet) -> Result<()> {
+        self.verify()?;
+        if !self.owners.contains(&owner.address()) {
+            return Err(eyre::eyre!("> {:?} is not an owner of the Safe {:?}", owner.address(), self.safe))
+        }
+        let signature = owner.signer.sign_hash(self.safe_tx_hash)?;
+        self.signatures.retain(|s| s.owner != owner.address());
+        self.signatures.push(SafeSignature {
+            owner: owner.address(),
+            signature: signature.to_vec().into(),
+        });
+        self.signatures.sort_by_key(|s| s.owner);
+        Ok(())
+    }
+
+    /// Returns the signatures concatenated in owner order, the `signatures` argument of `execTransaction`
+    pub fn packed_signatures(&self) -> Bytes {
+        self.signatures
+            .iter()
+            .flat_map(|s| s.signature.to_vec())
+            .collect::<Vec<u8>>()
+            .into()
+    }
+}
+
+/// Encodes the calldata of a MultiSend batch, each call packed as
+/// `operation (uint8), to (address), value (uint256), data length (uint256), data`
+/// ### Arguments
+/// * `calls` - Calls, made in order
+/// 
+/// ### Returns
+/// * `Bytes` - The `multiSend(bytes)` calldata
+pub fn encode_multisend(calls: &[SafeCall]) -> Bytes {
+    let mut transactions = Vec::new();
+    for call in calls {
+        let mut word = [0u8; 32];
+        transactions.push(SAFE_OPERATION_CALL);
+        transactions.extend_from_slice(call.to.as_bytes());
+        call.value.to_big_endian(&mut word);
+        transactions.extend_from_slice(&word);
+        U256::from(call.data.len()).to_big_endian(&mut word);
+        transactions.extend_from_slice(&word);
+        transactions.extend_from_slice(&call.data);
+    }
+    MultiSendCall { transactions: transactions.into() }.encode().into()
+}
+
+/// Reads the nonce, threshold, owners and version of the Safe at the given address
+/// ### Arguments
+/// * `provider` - Network Provider
+/// * `safe` - Address of the Safe
+/// 
+/// ### Returns
+/// * `Result<SafeState>` - The state of the Safe
+pub async fn read_safe_state<M: Middleware + 'static>(provider: &Arc<M>, safe: Address) -> Result<SafeState> {
+    let contract = GnosisSafe::new(safe, Arc::clone(provider));
+    let not_a_safe = |e: ethers::contract::ContractError<M>| eyre::eyre!("> {:?} is not a Safe: {}", safe, e);
+    Ok(SafeState {
+        nonce: contract.nonce().call().await.map_err(not_a_safe)?,
+        threshold: contract.get_threshold().call().await.map_err(not_a_safe)?.as_u64(),
+        owners: contract.get_owners().call().await.map_err(not_a_safe)?,
+        version: contract.version().call().await.map_err(not_a_safe)?,
+    })
+}
+
+/// Reads the hash of the Safe transaction computed by the Safe itself
+/// ### Arguments
+/// * `provider` - Network Provider
+/// * `tx` - Safe transaction
+/// 
+/// ### Returns
+/// * `Result<H256>` - The Safe transaction hash
+pub async fn read_safe_tx_hash<M: Middleware + 'static>(provider: &Arc<M>, tx: &SafeTransaction) -> Result<H256> {
+    let contract = GnosisSafe::new(tx.safe, Arc::clone(provider));
+    let res = contract
+        .get_transaction_hash(
+            tx.to,
+            tx.value,
+            tx.data.clone(),
+            tx.operation,
+            tx.safe_tx_gas,
+            tx.base_gas,
+            tx.gas_price,
+            tx.gas_token,
+            tx.refund_receiver,
+            tx.nonce,
+        )
+        .call()
+        .await;
+    match res {
+        Ok(hash) => Ok(hash.into()),
+        Err(e) => Err(eyre::Report::from(e)),
+    }
+}
+
+/// Serializes `U256` values as decimal strings, the format Safe uses
+mod decimal {
+    use ethers::types::U256;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        U256::from_dec_str(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::transaction::eip712::{Eip712, TypedData};
+
+    #[test]
+    fn test_safe_transaction() {
+        let owners: Vec<Wallet> = (0..2)
+            .map(|d| Wallet::from_phrase(
+                "test test test test test test test test test test test junk",
+                d,
+                11155111
+            ).unwrap())
+            .collect();
+        let state = SafeState {
+            nonce: U256::from(4),
+            threshold: 2,
+            owners: owners.iter().map(|o| o.address()).collect(),
+            version: "1.3.0".to_string(),
+        };
+        let safe = Address::repeat_byte(0x11);
+        let pause = SafeCall {
+            to: Address::repeat_byte(0x22),
+            value: U256::zero(),
+            data: Bytes::from(vec![0x84, 0x56, 0xcb, 0x59]),
+            description: "pause()".to_string(),
+        };
+        let mut tx = SafeTransaction::new(safe, 11155111, &state, &[pause.clone()], Address::repeat_byte(0x33)).unwrap();
+        assert_eq!(tx.operation, SAFE_OPERATION_CALL);
+
+        // The hash is the EIP-712 hash of the SafeTx typed data
+        let typed_data: TypedData = serde_json::from_value(serde_json::json!({
+            "types": {
+                "EIP712Domain": [
+                    {"name": "chainId", "type": "uint256"},
+                    {"name": "verifyingContract", "type": "address"}
+                ],
+                "SafeTx": [
+                    {"name": "to", "type": "address"},
+                    {"name": "value", "type": "uint256"},
+                    {"name": "data", "type": "bytes"},
+                    {"name": "operation", "type": "uint8"},
+                    {"name": "safeTxGas", "type": "uint256"},
+                    {"name": "baseGas", "type": "uint256"},
+                    {"name": "gasPrice", "type": "uint256"},
+                    {"name": "gasToken", "type": "address"},
+                    {"name": "refundReceiver", "type": "address"},
+                    {"name": "nonce", "type": "uint256"}
+                ]
+            },
+            "primaryType": "SafeTx",
+            "domain": {"chainId": 11155111, "verifyingContract": format!("{:?}", safe)},
+            "message": {
+                "to": format!("{:?}", pause.to),
+                "value": "0",
+                "data": "0x8456cb59",
+                "operation": 0,
+                "safeTxGas": "0",
+                "baseGas": "0",
+                "gasPrice": "0",
+                "gasToken": format!("{:?}", Address::zero()),
+                "refundReceiver": format!("{:?}", Address::zero()),
+                "nonce": "4"
+            }
+        })).unwrap();
+        assert_eq!(tx.safe_tx_hash, H256::from(typed_data.encode_eip712().unwrap()));
+
+        tx.sign(&owners[1]).unwrap();
+        tx.sign(&owners[0]).unwrap();
+        tx.sign(&owners[1]).unwrap();
+        assert_eq!(tx.signatures.len(), 2);
+        assert!(tx.signatures[0].owner < tx.signatures[1].owner);
+        assert_eq!(tx.packed_signatures().len(), 2 * 65);
+        tx.verify().unwrap();
+
+        let json = serde_json::to_string(&tx).unwrap();
+        assert!(json.contains("\"nonce\":\"4\""));
+        assert_eq!(serde_json::from_str::<SafeTransaction>(&json).unwrap(), tx);
+
+        let mut edited = tx.clone();
+        edited.nonce = U256::from(5);
+        assert!(edited.verify().is_err());
+
+        // Batches are a delegate call to MultiSend: 1 + 20 + 32 + 32 + 4 bytes per call, padded
+        let batch = SafeTransaction::new(safe, 11155111, &state, &[pause.clone(), pause], Address::repeat_byte(0x33)).unwrap();
+        assert_eq!(batch.operation, SAFE_OPERATION_DELEGATE_CALL);
+        assert_eq!(batch.to, Address::repeat_byte(0x33));
+        assert_eq!(batch.data.len(), 4 + 32 + 32 + 2 * 89 + 14);
+    }
+}
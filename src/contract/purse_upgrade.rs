@@ -0,0 +1,87 @@
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+};
+use ethers::{
+    abi::AbiParser,
+    types::{Address, Bytes, H256},
+};
+use crate::contract::purse_calldata::encode_calldata;
+
+/// ERC1967 storage slot of the implementation address, `keccak256("eip1967.proxy.implementation") - 1`.
+/// A UUPS implementation returns it from `proxiableUUID`.
+pub const ERC1967_IMPLEMENTATION_SLOT: &str = "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+
+/// A proxy upgrade, appended as a JSON line to the upgrade record by the `upgrade` command
+/// With traits `Clone`, `Debug`, `Serialize` and `Deserialize`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpgradeRecord {
+    /// Unix timestamp (seconds) of the upgrade
+    pub timestamp: u64,
+    /// Chain Id of the upgraded proxy
+    pub chain_id: u64,
+    /// Address of the proxy
+    pub proxy: Address,
+    /// Implementation before the upgrade
+    pub previous_implementation: Address,
+    /// Implementation after the upgrade
+    pub new_implementation: Address,
+    /// Calldata of the initializer called with the upgrade, empty if none
+    pub init_data: Bytes,
+    /// Transaction hash of the upgrade
+    pub tx_hash: H256,
+    /// Block number the upgrade was included in
+    pub block_number: Option<u64>,
+}
+
+/// Appends the upgrade to the record at the given file path, one JSON object per line
+/// ### Arguments
+/// * `file_path` - File path of the upgrade record
+/// * `record` - Upgrade
+/// 
+/// ### Returns
+/// * `Result<()>` - Result
+pub fn append_upgrade_record(file_path: &str, record: &UpgradeRecord) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file_path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Encodes the calldata of an initializer called with the upgrade
+/// ### Arguments
+/// * `signature` - Function signature, eg: "initializeV2(uint256,address)"
+/// * `args` - Arguments, in the order of the signature
+/// 
+/// ### Returns
+/// * `Result<Bytes>` - The calldata
+pub fn encode_initializer(signature: &str, args: &[String]) -> Result<Bytes> {
+    let function = AbiParser::default()
+        .parse_function(signature.trim_start_matches("function "))
+        .map_err(|e| eyre::eyre!("Invalid initializer signature {}: {}", signature, e))?;
+    encode_calldata(&function, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_initializer() {
+        let data = encode_initializer("initializeV2(uint256,address)", &[
+            "5".to_string(),
+            "0x50F49019DE9d7d33EBb7809803A25db4d8fC2ba0".to_string(),
+        ]).unwrap();
+        assert_eq!(&data[..4], &ethers::utils::id("initializeV2(uint256,address)")[..]);
+        assert_eq!(data.len(), 4 + 2 * 32);
+        assert_eq!(data[4 + 31], 5);
+
+        assert!(encode_initializer("initializeV2(uint256)", &[]).is_err());
+        assert!(encode_initializer("initializeV2(uint256)", &["x".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+};
+use ethers::types::{Address, Bytes, H256};
+
+/// Default file path of the deployment registry, see `registry_path`
+pub const DEFAULT_REGISTRY_PATH: &str = "deployments.json";
+
+/// A Purse404 deployment, recorded by the `deploy` command
+/// With traits `Clone`, `Debug`, `PartialEq`, `Serialize` and `Deserialize`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deployment {
+    /// Address of the ERC1967 proxy, the address the token is used at
+    pub proxy: Address,
+    /// Address of the implementation behind the proxy
+    pub implementation: Address,
+    /// Address of the deploying wallet
+    pub deployer: Address,
+    /// Block number the proxy was deployed in
+    pub block_number: Option<u64>,
+    /// Unix timestamp (seconds) of the deployment
+    pub deployed_at: u64,
+    /// Transaction hash of the implementation deployment
+    pub implementation_tx: H256,
+    /// Transaction hash of the proxy deployment, which also calls `initialize`
+    pub proxy_tx: H256,
+    /// Transaction hash of the `init404` call
+    pub init404_tx: Option<H256>,
+}
+
+/// Deployments by chain id, stored as JSON. A chain only keeps its latest deployment.
+/// With traits `Clone`, `Debug`, `Default`, `Serialize` and `Deserialize`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeploymentRegistry {
+    pub deployments: BTreeMap<u64, Deployment>,
+}
+
+impl DeploymentRegistry {
+    /// Load the registry at the given file path, empty if the file does not exist
+    /// ### Arguments
+    /// * `file_path` - File path of the registry
+    /// 
+    /// ### Returns
+    /// * `Result<Self>` - The registry
+    pub fn load(file_path: &str) -> Result<Self> {
+        if !Path::new(file_path).exists() {
+            return Ok(Self::default())
+        }
+        let content = fs::read_to_string(file_path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| eyre::eyre!("Invalid deployment registry {}: {}", file_path, e))
+    }
+
+    /// Write the registry to the given file path, through a temporary file so it is never left half written
+    /// ### Arguments
+    /// * `file_path` - File path of the registry
+    /// 
+    /// ### Returns
+    /// * `Result<()>` - Result
+    pub fn save(&self, file_path: &str) -> Result<()> {
+        let tmp_path = format!("{}.tmp", file_path);
+        fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp_path, file_path)?;
+        Ok(())
+    }
+
+    /// Returns the deployment on the given chain, if any
+    pub fn get(&self, chain_id: u64) -> Option<&Deployment> {
+        self.deployments.get(&chain_id)
+    }
+
+    /// Record the deployment on the given chain, replacing the previous one
+    pub fn insert(&mut self, chain_id: u64, deployment: Deployment) {
+        self.deployments.insert(chain_id, deployment);
+    }
+}
+
+/// Get the file path of the deployment registry.
+/// Read from `DEPLOYMENTS_PATH` in the environment, and defaults to `deployments.json`.
+/// ### Returns
+/// * `String` - The file path
+pub fn registry_path() -> String {
+    std::env::var("DEPLOYMENTS_PATH").unwrap_or_else(|_| DEFAULT_REGISTRY_PATH.to_string())
+}
+
+/// Sets the given variable in a dotenv file, replacing its line if it is already set,
+/// and creating the file if it does not exist
+/// ### Arguments
+/// * `file_path` - File path of the dotenv file, eg: ".env"
+/// * `key` - Variable name
+/// * `value` - Variable value
+/// 
+/// ### Returns
+/// * `Result<()>` - Result
+pub fn upsert_env_var(file_path: &str, key: &str, value: &str) -> Result<()> {
+    let content = match Path::new(file_path).exists() {
+        true => fs::read_to_string(file_path)?,
+        false => String::new(),
+    };
+    let line = format!("{}={}", key, value);
+    let mut replaced = false;
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|l| match l.split_once('=') {
+            Some((k, _)) if k.trim() == key => {
+                replaced = true;
+                line.clone()
+            },
+            _ => l.to_string(),
+        })
+        .collect();
+    if !replaced {
+        lines.push(line);
+    }
+    fs::write(file_path, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+/// Reads the creation bytecode from a compiled contract artifact: a Foundry artifact (`bytecode.object`),
+/// a Hardhat artifact (`bytecode`), or a file holding only the hex encoded bytecode
+/// ### Arguments
+/// * `file_path` - File path of the artifact
+/// 
+/// ### Returns
+/// * `Result<Bytes>` - The creation bytecode
+pub fn read_artifact_bytecode(file_path: &str) -> Result<Bytes> {
+    let content = fs::read_to_string(file_path)?;
+    let hex_str = match serde_json::from_str::<serde_json::Value>(&content) {
+        Ok(artifact) => match &artifact["bytecode"] {
+            serde_json::Value::String(bytecode) => bytecode.clone(),
+            serde_json::Value::Object(bytecode) => match bytecode.get("object") {
+                Some(serde_json::Value::String(object)) => object.clone(),
+                _ => return Err(eyre::eyre!("Artifact {} has no bytecode.object", file_path)),
+            },
+            _ => return Err(eyre::eyre!("Artifact {} has no bytecode", file_path)),
+        },
+        Err(_) => content.trim().to_string(),
+    };
+    if hex_str.contains("__") {
+        return Err(eyre::eyre!("Bytecode in {} has unlinked libraries", file_path))
+    }
+    let bytecode: Bytes = hex_str
+        .parse()
+        .map_err(|e| eyre::eyre!("Invalid bytecode in {}: {}", file_path, e))?;
+    if bytecode.is_empty() {
+        return Err(eyre::eyre!("Bytecode in {} is empty, is the contract abstract?", file_path))
+    }
+    Ok(bytecode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upsert_env_var() {
+        let path = std::env::temp_dir().join(format!("contract_interfacer_{}.env", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, "MNEMONIC=test\nLOCAL_PURSE_ADDRESS=0x01\n").unwrap();
+
+        upsert_env_var(path, "LOCAL_PURSE_ADDRESS", "0x02").unwrap();
+        upsert_env_var(path, "LOCAL_RPC", "http://127.0.0.1:8545").unwrap();
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            "MNEMONIC=test\nLOCAL_PURSE_ADDRESS=0x02\nLOCAL_RPC=http://127.0.0.1:8545\n"
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_read_artifact_bytecode() {
+        let path = std::env::temp_dir().join(format!("contract_interfacer_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        fs::write(path, r#"{"abi": [], "bytecode": {"object": "0x6080", "linkReferences": {}}}"#).unwrap();
+        assert_eq!(read_artifact_bytecode(path).unwrap(), Bytes::from(vec![0x60, 0x80]));
+        fs::write(path, r#"{"abi": [], "bytecode": "0x6080"}"#).unwrap();
+        assert_eq!(read_artifact_bytecode(path).unwrap(), Bytes::from(vec![0x60, 0x80]));
+        fs::write(path, "0x6080\n").unwrap();
+        assert_eq!(read_artifact_bytecode(path).unwrap(), Bytes::from(vec![0x60, 0x80]));
+        fs::write(path, r#"{"abi": [], "bytecode": "0x"}"#).unwrap();
+        assert!(read_artifact_bytecode(path).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
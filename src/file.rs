@@ -1,15 +1,29 @@
-use crate::utils::str_wei_to_eth;
-use core::panic;
-use csv::{ WriterBuilder, ReaderBuilder };
-use std::{ fs::{File, OpenOptions}, path::Path };
+use crate::{
+    contract::purse_events::NftMovements,
+    utils::str_wei_to_eth,
+};
+use csv::{ WriterBuilder, ReaderBuilder, StringRecord };
+use fs2::FileExt;
+use std::{ 
+    fs::{self, File, OpenOptions}, 
+    io::{Seek, SeekFrom, Write}, 
+    path::Path,
+};
 use eyre::Result;
 use serde::{Deserialize, Serialize};
 use ethers::types::{Address, U256};
+use chrono::{DateTime, SecondsFormat};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Record {
     #[serde(rename = "Transaction Hash")]
     pub transaction_hash: String,
+    #[serde(rename = "Timestamp (UTC)", default)]
+    pub timestamp: String,
+    #[serde(rename = "Balances Block Before", default)]
+    pub balances_block_before: Option<u64>,
+    #[serde(rename = "Balances Block After", default)]
+    pub balances_block_after: Option<u64>,
     #[serde(rename = "Derivation")]
     pub derivation: u32,
     #[serde(rename = "Sender")]
@@ -38,8 +52,22 @@ pub struct Record {
     pub msg_value: f64,
     #[serde(rename = "Calldata Value (Raw)")]
     pub calldata_value: f64,
+    #[serde(rename = "Token ID", default)]
+    pub token_id: String,
+    #[serde(rename = "Third Party", default)]
+    pub third_party: String,
+    #[serde(rename = "Third Party Balance Before (ERC20)", default)]
+    pub third_party_balance_before_erc20: Option<f64>,
+    #[serde(rename = "Third Party Balance After (ERC20)", default)]
+    pub third_party_balance_after_erc20: Option<f64>,
+    #[serde(rename = "Allowance Before (Raw)", default)]
+    pub allowance_before: Option<f64>,
+    #[serde(rename = "Allowance After (Raw)", default)]
+    pub allowance_after: Option<f64>,
     #[serde(rename = "Msg.sender Owned Token IDs")]
     pub msg_sender_owned_token_ids: String,
+    #[serde(rename = "NFT Movements", default)]
+    pub nft_movements: String,
     #[serde(rename = "Tx Fee")]
     pub tx_fee: f64,
     #[serde(rename = "Gas Price")]
@@ -50,6 +78,371 @@ pub struct Record {
     pub receipt_json: String,
 }
 
+impl Record {
+    /// Parses the comma separated `Msg.sender Owned Token IDs` column
+    /// ### Returns
+    /// * `Vec<&str>` - Token IDs in decimal
+    pub fn owned_token_ids(&self) -> Vec<&str> {
+        self.msg_sender_owned_token_ids
+            .split(',')
+            .map(|id| id.trim())
+            .filter(|id| !id.is_empty())
+            .collect()
+    }
+}
+
+/// A transaction to be logged to the CSV file, see `write_to_csv`.
+/// Balances that are not set are logged as zero.
+#[derive(Debug, Clone, Default)]
+pub struct LogEntry {
+    /// Transaction hash
+    pub tx_hash: String,
+    /// Unix timestamp (seconds) of the block the transaction was included in
+    pub block_timestamp: Option<u64>,
+    /// Block the "before" balances were read at
+    pub balances_block_bef: Option<u64>,
+    /// Block the "after" balances and owned token IDs were read at
+    pub balances_block_aft: Option<u64>,
+    /// Gas price in gwei
+    pub gas_price: String,
+    /// Gas used in decimal
+    pub gas_used: String,
+    /// Transaction fee in ETH
+    pub tx_fee: String,
+    /// Transaction receipt JSON
+    pub receipt_json: String,
+    /// Contract function called
+    pub call_function: String,
+    /// Derivation number of the address
+    pub derivation_number: u32,
+    /// Message sender
+    pub msg_sender: Address,
+    /// Sender balance, before (native)
+    pub sender_eth_balance_bef: Option<U256>,
+    /// Sender balance, after (native)
+    pub sender_eth_balance_aft: Option<U256>,
+    /// Sender balance, before (ERC20)
+    pub sender_erc20_balance_bef: Option<U256>,
+    /// Sender balance, after (ERC20)
+    pub sender_erc20_balance_aft: Option<U256>,
+    /// Message recipient
+    pub msg_recipient: Address,
+    /// Recipient balance, before (native)
+    pub recipient_eth_balance_bef: Option<U256>,
+    /// Recipient balance, after (native)
+    pub recipient_eth_balance_aft: Option<U256>,
+    /// Recipient balance, before (ERC20)
+    pub recipient_erc20_balance_bef: Option<U256>,
+    /// Recipient balance, after (ERC20)
+    pub recipient_erc20_balance_aft: Option<U256>,
+    /// Message value
+    pub msg_value: Option<U256>,
+    /// Calldata value
+    pub calldata_value: Option<U256>,
+    /// NFT token ID of ERC721 transfers and approvals
+    pub token_id: Option<U256>,
+    /// Account whose tokens are spent by the message sender, eg: the `from_` of `erc20TransferFrom`
+    pub third_party: Option<Address>,
+    /// Third party balance, before (ERC20)
+    pub third_party_erc20_balance_bef: Option<U256>,
+    /// Third party balance, after (ERC20)
+    pub third_party_erc20_balance_aft: Option<U256>,
+    /// Allowance set or spent by the transaction, before
+    pub allowance_bef: Option<U256>,
+    /// Allowance set or spent by the transaction, after
+    pub allowance_aft: Option<U256>,
+    /// Msg.sender Owned token IDs
+    pub msg_sender_owned_token_ids: Option<Vec<U256>>,
+    /// NFT IDs minted, burned or moved by the transaction
+    pub nft_movements: Option<NftMovements>,
+}
+
+impl LogEntry {
+    /// Formats the entry into the values of every column of the current schema version
+    /// ### Returns
+    /// * `Vec<(&'static str, String)>` - Pairs of column header and value
+    fn to_row(&self) -> Vec<(&'static str, String)> {
+        let default_u256 = U256::from(0);
+        let eth = |value: Option<U256>| str_wei_to_eth(&value.unwrap_or(default_u256).to_string());
+
+        let timestamp = self.block_timestamp
+            .and_then(|ts| DateTime::from_timestamp(ts as i64, 0))
+            .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Secs, true))
+            .unwrap_or_default();
+
+        let msg_sender_owned_token_ids = self.msg_sender_owned_token_ids
+            .as_ref()
+            .map(|vec| vec.iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(","))
+            .unwrap_or_default();
+
+        vec![
+            ("Transaction Hash", self.tx_hash.clone()),
+            ("Timestamp (UTC)", timestamp),
+            ("Balances Block Before", self.balances_block_bef.map(|n| n.to_string()).unwrap_or_default()),
+            ("Balances Block After", self.balances_block_aft.map(|n| n.to_string()).unwrap_or_default()),
+            ("Derivation", self.derivation_number.to_string()),
+            ("Sender", format!("{:?}", self.msg_sender)),
+            ("Sender Balance Before (ETH)", eth(self.sender_eth_balance_bef)),
+            ("Sender Balance After (ETH)", eth(self.sender_eth_balance_aft)),
+            ("Sender Balance Before (ERC20)", eth(self.sender_erc20_balance_bef)),
+            ("Sender Balance After (ERC20)", eth(self.sender_erc20_balance_aft)),
+            ("Recipient", format!("{:?}", self.msg_recipient)),
+            ("Recipient Balance Before (ETH)", eth(self.recipient_eth_balance_bef)),
+            ("Recipient Balance After (ETH)", eth(self.recipient_eth_balance_aft)),
+            ("Recipient Balance Before (ERC20)", eth(self.recipient_erc20_balance_bef)),
+            ("Recipient Balance After (ERC20)", eth(self.recipient_erc20_balance_aft)),
+            ("Function", self.call_function.clone()),
+            ("Msg Value (Raw)", self.msg_value.unwrap_or(default_u256).to_string()),
+            ("Calldata Value (Raw)", self.calldata_value.unwrap_or(default_u256).to_string()),
+            ("Token ID", self.token_id.map(|id| id.to_string()).unwrap_or_default()),
+            ("Third Party", self.third_party.map(|addr| format!("{:?}", addr)).unwrap_or_default()),
+            ("Third Party Balance Before (ERC20)", self.third_party_erc20_balance_bef.map(|v| eth(Some(v))).unwrap_or_default()),
+            ("Third Party Balance After (ERC20)", self.third_party_erc20_balance_aft.map(|v| eth(Some(v))).unwrap_or_default()),
+            ("Allowance Before (Raw)", self.allowance_bef.map(|v| v.to_string()).unwrap_or_default()),
+            ("Allowance After (Raw)", self.allowance_aft.map(|v| v.to_string()).unwrap_or_default()),
+            ("Msg.sender Owned Token IDs", msg_sender_owned_token_ids),
+            ("NFT Movements", self.nft_movements.as_ref().map(|m| m.to_string()).unwrap_or_default()),
+            ("Tx Fee", self.tx_fee.clone()),
+            ("Gas Price", self.gas_price.clone()),
+            ("Gas Used", self.gas_used.clone()),
+            ("Receipt JSON", self.receipt_json.clone()),
+        ]
+    }
+}
+
+/// Layout versions of the CSV transaction log.
+/// A new version may only add columns, so that a file written with an older
+/// layout can always be migrated into the current one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SchemaVersion {
+    V1,
+    /// Adds `Timestamp (UTC)`
+    V2,
+    /// Adds `Balances Block Before` and `Balances Block After`
+    V3,
+    /// Adds `NFT Movements`
+    V4,
+    /// Adds `Token ID`
+    V5,
+    /// Adds the `Third Party` and `Allowance` columns
+    V6,
+}
+
+impl SchemaVersion {
+    /// The layout written by the current version of the tool
+    pub const CURRENT: SchemaVersion = SchemaVersion::V6;
+
+    /// All known layouts, oldest first
+    pub const ALL: [SchemaVersion; 6] = [
+        SchemaVersion::V1,
+        SchemaVersion::V2,
+        SchemaVersion::V3,
+        SchemaVersion::V4,
+        SchemaVersion::V5,
+        SchemaVersion::V6,
+    ];
+
+    /// Returns the ordered column headers of this layout
+    /// ### Returns
+    /// * `Vec<&'static str>` - Column headers
+    pub fn headers(&self) -> Vec<&'static str> {
+        LOG_COLUMNS
+            .iter()
+            .filter(|column| column.since <= *self)
+            .map(|column| column.name)
+            .collect()
+    }
+
+    /// Detects the layout version from the headers of a log file
+    /// ### Arguments
+    /// * `headers` - Headers read from the file
+    /// 
+    /// ### Returns
+    /// * `Result<SchemaVersion>` - The matching version, or an error for unknown headers
+    pub fn detect(headers: &StringRecord) -> Result<SchemaVersion> {
+        SchemaVersion::ALL
+            .iter()
+            .find(|version| {
+                let expected = version.headers();
+                headers.len() == expected.len() 
+                && headers.iter().zip(expected.iter()).all(|(a, b)| a == *b)
+            })
+            .copied()
+            .ok_or_else(|| eyre::eyre!("Headers do not match any known log schema version"))
+    }
+}
+
+impl std::fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaVersion::V1 => write!(f, "v1"),
+            SchemaVersion::V2 => write!(f, "v2"),
+            SchemaVersion::V3 => write!(f, "v3"),
+            SchemaVersion::V4 => write!(f, "v4"),
+            SchemaVersion::V5 => write!(f, "v5"),
+            SchemaVersion::V6 => write!(f, "v6"),
+        }
+    }
+}
+
+/// A column of the CSV transaction log
+struct LogColumn {
+    /// Header of the column
+    name: &'static str,
+    /// First layout version containing the column
+    since: SchemaVersion,
+    /// Value written for the column when migrating rows from an older layout
+    default: &'static str,
+}
+
+/// All columns of the CSV transaction log, in file order
+const LOG_COLUMNS: &[LogColumn] = &[
+    LogColumn { name: "Transaction Hash", since: SchemaVersion::V1, default: "" },
+    LogColumn { name: "Timestamp (UTC)", since: SchemaVersion::V2, default: "" },
+    LogColumn { name: "Balances Block Before", since: SchemaVersion::V3, default: "" },
+    LogColumn { name: "Balances Block After", since: SchemaVersion::V3, default: "" },
+    LogColumn { name: "Derivation", since: SchemaVersion::V1, default: "" },
+    LogColumn { name: "Sender", since: SchemaVersion::V1, default: "" },
+    LogColumn { name: "Sender Balance Before (ETH)", since: SchemaVersion::V1, default: "0" },
+    LogColumn { name: "Sender Balance After (ETH)", since: SchemaVersion::V1, default: "0" },
+    LogColumn { name: "Sender Balance Before (ERC20)", since: SchemaVersion::V1, default: "0" },
+    LogColumn { name: "Sender Balance After (ERC20)", since: SchemaVersion::V1, default: "0" },
+    LogColumn { name: "Recipient", since: SchemaVersion::V1, default: "" },
+    LogColumn { name: "Recipient Balance Before (ETH)", since: SchemaVersion::V1, default: "0" },
+    LogColumn { name: "Recipient Balance After (ETH)", since: SchemaVersion::V1, default: "0" },
+    LogColumn { name: "Recipient Balance Before (ERC20)", since: SchemaVersion::V1, default: "0" },
+    LogColumn { name: "Recipient Balance After (ERC20)", since: SchemaVersion::V1, default: "0" },
+    LogColumn { name: "Function", since: SchemaVersion::V1, default: "" },
+    LogColumn { name: "Msg Value (Raw)", since: SchemaVersion::V1, default: "0" },
+    LogColumn { name: "Calldata Value (Raw)", since: SchemaVersion::V1, default: "0" },
+    LogColumn { name: "Token ID", since: SchemaVersion::V5, default: "" },
+    LogColumn { name: "Third Party", since: SchemaVersion::V6, default: "" },
+    LogColumn { name: "Third Party Balance Before (ERC20)", since: SchemaVersion::V6, default: "" },
+    LogColumn { name: "Third Party Balance After (ERC20)", since: SchemaVersion::V6, default: "" },
+    LogColumn { name: "Allowance Before (Raw)", since: SchemaVersion::V6, default: "" },
+    LogColumn { name: "Allowance After (Raw)", since: SchemaVersion::V6, default: "" },
+    LogColumn { name: "Msg.sender Owned Token IDs", since: SchemaVersion::V1, default: "" },
+    LogColumn { name: "NFT Movements", since: SchemaVersion::V4, default: "" },
+    LogColumn { name: "Tx Fee", since: SchemaVersion::V1, default: "0" },
+    LogColumn { name: "Gas Price", since: SchemaVersion::V1, default: "0" },
+    LogColumn { name: "Gas Used", since: SchemaVersion::V1, default: "0" },
+    LogColumn { name: "Receipt JSON", since: SchemaVersion::V1, default: "" },
+];
+
+/// Reads the schema version of an existing log file.
+/// ### Arguments
+/// * `file_path` - File path
+/// 
+/// ### Returns
+/// * `Result<Option<SchemaVersion>>` - `None` if the file does not exist
+pub fn read_schema_version(file_path: &str) -> Result<Option<SchemaVersion>> {
+    let path = Path::new(file_path);
+    let file_exists = match path.try_exists() {
+        Ok(exists) => exists,
+        Err(_) => return Err(eyre::eyre!("File existence cannot be confirmed, check dir permissions"))
+    };
+    if !file_exists {
+        return Ok(None);
+    }
+
+    let file = File::open(path)?;
+    file.lock_shared()?;
+    let mut reader = ReaderBuilder::new()
+        .has_headers(true)
+        .from_reader(&file);
+    let version = SchemaVersion::detect(reader.headers()?)?;
+    file.unlock()?;
+
+    Ok(Some(version))
+}
+
+/// Rewrites a log file into the current schema version.
+/// Columns missing from the older layout are filled with their default values.
+/// If no output path is given, the file is rewritten in place.
+/// 
+/// The file is held under an exclusive lock for the whole migration, so concurrent
+/// writers wait for it to complete and then append in the new layout.
+/// ### Arguments
+/// * `file_path` - File path of the log to migrate
+/// * `output_path` - File path to write the migrated log to (optional)
+/// 
+/// ### Returns
+/// * `Result<(SchemaVersion, usize)>` - The original schema version and the number of migrated rows
+pub fn migrate_csv(file_path: &str, output_path: Option<&str>) -> Result<(SchemaVersion, usize)> {
+    let file = match OpenOptions::new().read(true).write(true).open(file_path) {
+        Ok(f) => f,
+        Err(_) => return Err(eyre::eyre!("Cannot open file, not found."))
+    };
+    file.lock_exclusive()?;
+
+    let mut reader = ReaderBuilder::new()
+        .has_headers(true)
+        .from_reader(&file);
+    let headers_read = reader.headers()?.clone();
+    let from_version = SchemaVersion::detect(&headers_read)?;
+
+    // Position of each current column in the old layout, if present
+    let positions: Vec<Option<usize>> = SchemaVersion::CURRENT
+        .headers()
+        .iter()
+        .map(|header| headers_read.iter().position(|h| h == *header))
+        .collect();
+    let defaults: Vec<&str> = LOG_COLUMNS
+        .iter()
+        .filter(|column| column.since <= SchemaVersion::CURRENT)
+        .map(|column| column.default)
+        .collect();
+
+    let mut writer = WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(Vec::new());
+    writer.write_record(SchemaVersion::CURRENT.headers())?;
+
+    let mut rows = 0;
+    for res in reader.records() {
+        let row = res?;
+        let migrated: Vec<&str> = positions
+            .iter()
+            .zip(defaults.iter())
+            .map(|(pos, default)| match pos {
+                Some(i) => row.get(*i).unwrap_or(default),
+                None => default,
+            })
+            .collect();
+        writer.write_record(&migrated)?;
+        rows += 1;
+    }
+    let migrated_bytes = writer
+        .into_inner()
+        .map_err(|e| eyre::eyre!("Failed to buffer migrated log: {}", e))?;
+
+    match output_path {
+        Some(output) => {
+            fs::write(output, &migrated_bytes)?;
+        },
+        None => {
+            // Keep a complete copy of the migrated log until the rewrite is synced,
+            // so a crash half way through never loses the log
+            let backup_path = format!("{}.migrating", file_path);
+            fs::write(&backup_path, &migrated_bytes)?;
+
+            // Rewrite the same file rather than renaming over it, so writers waiting
+            // on the lock append to the migrated file
+            file.set_len(0)?;
+            (&file).seek(SeekFrom::Start(0))?;
+            (&file).write_all(&migrated_bytes)?;
+            file.sync_all()?;
+            fs::remove_file(&backup_path)?;
+        }
+    }
+    file.unlock()?;
+
+    Ok((from_version, rows))
+}
+
 /// Reads the data from a CSV file into a vector of `Record` structs.
 /// ### Arguments
 /// * `file_path` - File path
@@ -61,173 +454,235 @@ pub fn read_from_csv(file_path: &str) -> Result<Vec<Record>> {
         Ok(f) => f,
         Err(_) => return Err(eyre::eyre!("Cannot open file, not found."))
     };
+    // A shared lock waits for any in-progress append or migration to complete
+    file.lock_shared()?;
     let mut reader = ReaderBuilder::new()
         .has_headers(true)
-        .from_reader(file);
+        .from_reader(&file);
+    SchemaVersion::detect(reader.headers()?)?;
 
     let mut records = Vec::new();
     for res in reader.deserialize() {
         let record: Record = res.expect("Failed to deserialize record");
         records.push(record);
     }
+    file.unlock()?;
 
     Ok(records)
 }
 
 /// Logs the transaction information to a CSV file.
 /// The CSV file is created if it does not exist.
-/// The order of the columns is as follows: Transaction Hash, Derivation, Sender, Sender Balance Before (ETH),
-/// Sender Balance After (ETH), Sender Balance Before (ERC20), Sender Balance After (ERC20), Recipient, 
-/// Recipient Balance Before (ETH), Recipient Balance After (ETH), Recipient Balance Before (ERC20),
-/// Recipient Balance After (ERC20), Function, Msg Value (Raw), Calldata Value (Raw), Msg.sender Owned Token IDs,
-/// Tx Fee, Gas Price, Gas Used, Receipt JSON.
 /// 
-/// Additionally, if the file already exists, but the headers do not match the expected headers,
-/// either in length, or content order, the program will panic.
+/// The file is held under an exclusive advisory lock while its headers are checked and the
+/// row is appended, and each row is written in a single write followed by an `fsync`.
+/// This allows several processes to safely append to the same file.
+/// The columns and their order are those of `SchemaVersion::CURRENT.headers()`, see `LOG_COLUMNS`.
+/// 
+/// If the file already exists, its headers must match a known schema version (see `SchemaVersion`).
+/// Appending to a file written with an older schema version returns an error, unless
+/// `allow_older_schema` is set, in which case only the columns of the older layout are written.
+/// Files can be brought up to date with `migrate_csv`.
 /// 
 /// #### Note
 /// The Msg value and Calldata value are recorded as is, without any conversions. 
 /// 
 /// ### Arguments
 /// * `file_path` - File path
-/// * `tx_hash` - Transaction hash
-/// * `gas_price` - Gas price in gwei
-/// * `gas_used` - Gas used in decimal
-/// * `tx_fee` - Transaction fee in ETH
-/// * `receipt_json_str` - Transaction receipt JSON
-/// * `call_function` - Contract function called
-/// * `derivation_number` - Derivation number of the address
-/// * `msg_sender` - Message sender
-/// * `sender_eth_balance_bef` - Sender balance, before (native)
-/// * `sender_eth_balance_aft` - Sender balance, after (native)
-/// * `sender_erc20_balance_bef` - Sender balance, before (ERC20)
-/// * `sender_erc20_balance_aft` - Sender balance, after (ERC20)
-/// * `msg_recipient` - Message recipient
-/// * `recipient_eth_balance_bef` - Recipient balance, before (native)
-/// * `recipient_eth_balance_aft` - Recipient balance, after (native)
-/// * `recipient_erc20_balance_bef` - Recipient balance, before (ERC20)
-/// * `recipient_erc20_balance_aft` - Recipient balance, after (ERC20)
-/// * `msg_value` - Message value (optional)
-/// * `calldata_value` - Calldata value (optional)
-/// * `msg_sender_owned_token_ids` - Msg.sender Owned token IDs (optional)
+/// * `entry` - The transaction to log
+/// * `allow_older_schema` - Allow appending to a file written with an older schema version
 /// 
 /// ### Returns
-/// * `Result<(), Box<dyn std::error::Error>>` - Result
+/// * `Result<()>` - Result
 pub fn write_to_csv(
     file_path: &str,
-    tx_hash: &str,
-    gas_price: &str,
-    gas_used: &str,
-    tx_fee: &str,
-    receipt_json_str: &str,
-    call_function: &str,
-    derivation_number: u32,
-    msg_sender: Address,
-    sender_eth_balance_bef: Option<U256>,
-    sender_eth_balance_aft: Option<U256>,
-    sender_erc20_balance_bef: Option<U256>,
-    sender_erc20_balance_aft: Option<U256>,
-    msg_recipient: Address,
-    recipient_eth_balance_bef: Option<U256>,
-    recipient_eth_balance_aft: Option<U256>,
-    recipient_erc20_balance_bef: Option<U256>,
-    recipient_erc20_balance_aft: Option<U256>,
-    msg_value: Option<U256>,
-    calldata_value: Option<U256>,
-    msg_sender_owned_token_ids: Option<Vec<U256>>,
+    entry: &LogEntry,
+    allow_older_schema: bool,
 ) -> Result<()> {
-    let path = Path::new(file_path);
-    let file_exists = match path.try_exists() {
-        Ok(exists) => exists,
-        Err(_) => return Err(eyre::eyre!("File existence cannot be confirmed, check dir permissions"))
+    // Opened in append mode, so that every write lands at the end of the file
+    let file = match OpenOptions::new().read(true).append(true).create(true).open(file_path) {
+        Ok(f) => f,
+        Err(e) => return Err(eyre::eyre!("Failed to create/open file: {}", e))
     };
-    let headers = [
-        "Transaction Hash", "Derivation", 
-        "Sender", 
-        "Sender Balance Before (ETH)", "Sender Balance After (ETH)", 
-        "Sender Balance Before (ERC20)", "Sender Balance After (ERC20)", 
-        "Recipient", 
-        "Recipient Balance Before (ETH)", "Recipient Balance After (ETH)",
-        "Recipient Balance Before (ERC20)", "Recipient Balance After (ERC20)",
-        "Function", "Msg Value (Raw)", "Calldata Value (Raw)", "Msg.sender Owned Token IDs", 
-        "Tx Fee", "Gas Price", "Gas Used", "Receipt JSON"
-    ];
 
-    if file_exists {
+    // The lock is held from the header check until the row is synced, so that concurrent
+    // writers can neither both write the headers nor interleave their rows.
+    // It is also released if the file is dropped on an early return.
+    file.lock_exclusive()?;
+
+    let file_exists = file.metadata()?.len() > 0;
+    let existing_version = if file_exists {
         let mut reader = ReaderBuilder::new()
             .has_headers(true)
-            .from_path(path)
-            .expect("Failed to read file");
-
-        let headers_read = reader.headers().expect("Failed to read headers");
-        if (headers_read.len() != headers.len()) 
-        || (!headers_read.iter().zip(headers.iter()).all(|(a, b)| a == *b)) {
-            panic!("Headers length or content order mismatch");
-        }
-    }
-
-    let file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .append(file_exists)
-        .open(path)
-        .expect("Failed to create/open file");
+            .from_reader(&file);
+        Some(SchemaVersion::detect(reader.headers()?)?)
+    } else {
+        None
+    };
+    let file_version = match existing_version {
+        Some(version) if version == SchemaVersion::CURRENT => version,
+        Some(version) if version < SchemaVersion::CURRENT && allow_older_schema => {
+            println!(
+                "> File: {} uses log schema {}, writing only its columns (current: {})",
+                file_path, version, SchemaVersion::CURRENT
+            );
+            version
+        },
+        Some(version) => return Err(eyre::eyre!(
+            "File: {} uses log schema {} but the current schema is {}. \
+            Run `log migrate --file-path {}`, or allow appending to the older schema.",
+            file_path, version, SchemaVersion::CURRENT, file_path
+        )),
+        None => SchemaVersion::CURRENT,
+    };
 
+    // The row (and headers, for a new file) is buffered, then written in one go
     let mut writer = WriterBuilder::new()
-        .has_headers(!file_exists)
-        .from_writer(file);
+        .has_headers(false)
+        .from_writer(Vec::new());
 
     if !file_exists {
-        writer.write_record(&headers).expect("Failed to write headers");
+        writer.write_record(file_version.headers())?;
     }
 
-    let default_u256 = U256::from(0);
-
-    let sender_eth_balance_bef = sender_eth_balance_bef.unwrap_or_else(|| default_u256);
-    let sender_eth_balance_aft = sender_eth_balance_aft.unwrap_or_else(|| default_u256);
-    let sender_erc20_balance_bef = sender_erc20_balance_bef.unwrap_or_else(|| default_u256);
-    let sender_erc20_balance_aft = sender_erc20_balance_aft.unwrap_or_else(|| default_u256);
-
-    let recipient_eth_balance_bef = recipient_eth_balance_bef.unwrap_or_else(|| default_u256);
-    let recipient_eth_balance_aft = recipient_eth_balance_aft.unwrap_or_else(|| default_u256);
-    let recipient_erc20_balance_bef = recipient_erc20_balance_bef.unwrap_or_else(|| default_u256);
-    let recipient_erc20_balance_aft = recipient_erc20_balance_aft.unwrap_or_else(|| default_u256);
-
-    let msg_value = msg_value.unwrap_or_else(|| default_u256);
-    let calldata_value = calldata_value.unwrap_or_else(|| default_u256);
-
-    let msg_sender_owned_token_ids = msg_sender_owned_token_ids
-        .map(|vec| vec.into_iter()
-        .map(|id| id.to_string())
-        .collect::<Vec<String>>()
-        .join(","))
-        .unwrap_or_default();
-
-    writer.write_record(&[
-        tx_hash,
-        derivation_number.to_string().as_str(),
-        msg_sender.to_string().as_str(),
-        str_wei_to_eth(&sender_eth_balance_bef.to_string()).as_str(),
-        str_wei_to_eth(&sender_eth_balance_aft.to_string()).as_str(),
-        str_wei_to_eth(&sender_erc20_balance_bef.to_string()).as_str(),
-        str_wei_to_eth(&sender_erc20_balance_aft.to_string()).as_str(),
-        msg_recipient.to_string().as_str(),
-        str_wei_to_eth(&recipient_eth_balance_bef.to_string()).as_str(),
-        str_wei_to_eth(&recipient_eth_balance_aft.to_string()).as_str(),
-        str_wei_to_eth(&recipient_erc20_balance_bef.to_string()).as_str(),
-        str_wei_to_eth(&recipient_erc20_balance_aft.to_string()).as_str(),
-        call_function,
-        msg_value.to_string().as_str(),
-        calldata_value.to_string().as_str(),
-        msg_sender_owned_token_ids.as_str(),
-        tx_fee,
-        gas_price,
-        gas_used,
-        receipt_json_str
-    ]).expect("Failed to write record");
-
-    writer.flush().expect("Failed to flush writer");
-    println!("Transaction hash: {}, from address: {:?}, added to file: {}", tx_hash, msg_sender, file_path);
+    let row = entry.to_row();
+
+    // Only the columns of the file's layout are written, in the file's order
+    let record: Vec<&str> = file_version
+        .headers()
+        .iter()
+        .map(|header| {
+            row.iter()
+                .find(|(name, _)| name == header)
+                .map(|(_, value)| value.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+    writer.write_record(&record)?;
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| eyre::eyre!("Failed to buffer record: {}", e))?;
+    (&file).write_all(&bytes)?;
+    file.sync_all()?;
+    file.unlock()?;
+    println!("Transaction hash: {}, from address: {:?}, added to file: {}", entry.tx_hash, entry.msg_sender, file_path);
 
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_schema_version() {
+        let headers = StringRecord::from(SchemaVersion::V1.headers());
+        assert_eq!(SchemaVersion::detect(&headers).unwrap(), SchemaVersion::V1);
+        let headers = StringRecord::from(SchemaVersion::V2.headers());
+        assert_eq!(SchemaVersion::detect(&headers).unwrap(), SchemaVersion::V2);
+        let headers = StringRecord::from(SchemaVersion::V3.headers());
+        assert_eq!(SchemaVersion::detect(&headers).unwrap(), SchemaVersion::V3);
+        let headers = StringRecord::from(SchemaVersion::V4.headers());
+        assert_eq!(SchemaVersion::detect(&headers).unwrap(), SchemaVersion::V4);
+        let headers = StringRecord::from(SchemaVersion::V5.headers());
+        assert_eq!(SchemaVersion::detect(&headers).unwrap(), SchemaVersion::V5);
+        let headers = StringRecord::from(SchemaVersion::V6.headers());
+        assert_eq!(SchemaVersion::detect(&headers).unwrap(), SchemaVersion::V6);
+
+        let mut reordered = SchemaVersion::V1.headers();
+        reordered.swap(0, 1);
+        assert!(SchemaVersion::detect(&StringRecord::from(reordered)).is_err());
+
+        let truncated = SchemaVersion::V1.headers()[..5].to_vec();
+        assert!(SchemaVersion::detect(&StringRecord::from(truncated)).is_err());
+    }
+
+    #[test]
+    fn test_migrate_csv() {
+        let dir = std::env::temp_dir();
+        let file_path = dir.join("contract_interfacer_migrate_test.csv");
+        let output_path = dir.join("contract_interfacer_migrate_test_out.csv");
+        let file_path = file_path.to_str().unwrap();
+        let output_path = output_path.to_str().unwrap();
+
+        fs::copy("test.csv", file_path).unwrap();
+        let rows_before = read_from_csv(file_path).unwrap().len();
+
+        let (from_version, rows) = migrate_csv(file_path, Some(output_path)).unwrap();
+        assert_eq!(from_version, SchemaVersion::V1);
+        assert_eq!(rows, rows_before);
+        assert_eq!(read_schema_version(output_path).unwrap(), Some(SchemaVersion::CURRENT));
+        let migrated = read_from_csv(output_path).unwrap();
+        assert_eq!(migrated.len(), rows_before);
+        assert!(migrated.iter().all(|record| record.timestamp.is_empty()));
+        assert!(migrated.iter().all(|record| record.balances_block_after.is_none()));
+
+        fs::remove_file(file_path).unwrap();
+        fs::remove_file(output_path).unwrap();
+    }
+
+    #[test]
+    fn test_append_to_older_schema() {
+        let file_path = std::env::temp_dir().join("contract_interfacer_older_schema_test.csv");
+        let file_path = file_path.to_str().unwrap();
+        fs::copy("test.csv", file_path).unwrap();
+        let rows_before = read_from_csv(file_path).unwrap().len();
+
+        let entry = LogEntry {
+            tx_hash: format!("0x{:064x}", 1),
+            block_timestamp: Some(1_700_000_000),
+            gas_price: "1.5".to_string(),
+            gas_used: "21000".to_string(),
+            tx_fee: "0.0000315".to_string(),
+            receipt_json: "{}".to_string(),
+            call_function: "transfer".to_string(),
+            ..Default::default()
+        };
+        assert!(write_to_csv(file_path, &entry, false).is_err());
+        write_to_csv(file_path, &entry, true).unwrap();
+
+        assert_eq!(read_schema_version(file_path).unwrap(), Some(SchemaVersion::V1));
+        assert_eq!(read_from_csv(file_path).unwrap().len(), rows_before + 1);
+
+        fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_appends() {
+        let file_path = std::env::temp_dir().join("contract_interfacer_concurrent_test.csv");
+        let file_path = file_path.to_str().unwrap().to_string();
+        let _ = fs::remove_file(&file_path);
+
+        let handles: Vec<_> = (0..8u32)
+            .map(|i| {
+                let file_path = file_path.clone();
+                std::thread::spawn(move || {
+                    let entry = LogEntry {
+                        tx_hash: format!("0x{:064x}", i),
+                        gas_price: "1.5".to_string(),
+                        gas_used: "21000".to_string(),
+                        tx_fee: "0.0000315".to_string(),
+                        receipt_json: "{}".to_string(),
+                        call_function: "transfer".to_string(),
+                        derivation_number: i,
+                        ..Default::default()
+                    };
+                    write_to_csv(&file_path, &entry, false).unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut derivations: Vec<u32> = read_from_csv(&file_path)
+            .unwrap()
+            .iter()
+            .map(|record| record.derivation)
+            .collect();
+        derivations.sort();
+        assert_eq!(derivations, (0..8).collect::<Vec<u32>>());
+
+        fs::remove_file(&file_path).unwrap();
+    }
+}
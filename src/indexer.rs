@@ -0,0 +1,443 @@
+use eyre::Result;
+use std::collections::{BTreeMap, HashMap};
+use rusqlite::{params, Connection, OptionalExtension};
+use ethers::{
+    contract::EthEvent,
+    providers::Middleware,
+    types::{Address, Filter, Log, U256},
+};
+use crate::contract::{
+    purse_contract::{ApprovalFilter, TransferFilter},
+    purse_events::{decode_purse_log, Purse404Event},
+};
+
+/// Kind of an indexed event, as stored in the `kind` column
+pub const ERC20_TRANSFER: &str = "erc20_transfer";
+pub const ERC20_APPROVAL: &str = "erc20_approval";
+pub const ERC721_TRANSFER: &str = "erc721_transfer";
+pub const ERC721_APPROVAL: &str = "erc721_approval";
+
+/// A Transfer or Approval event stored in the index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedEvent {
+    pub block_number: u64,
+    pub log_index: u64,
+    pub tx_hash: String,
+    /// One of `erc20_transfer`, `erc20_approval`, `erc721_transfer`, `erc721_approval`
+    pub kind: String,
+    /// Sender of a transfer, owner of an approval
+    pub from: Address,
+    /// Recipient of a transfer, spender of an approval
+    pub to: Address,
+    /// Amount (ERC20) or token ID (ERC721)
+    pub value: U256,
+}
+
+impl IndexedEvent {
+    /// Convert a log of the Purse404 contract into an indexed event
+    /// ### Arguments
+    /// * `log` - Log returned by `eth_getLogs`
+    /// 
+    /// ### Returns
+    /// * `Option<IndexedEvent>` - `None` if the log is not a mined Transfer or Approval event
+    pub fn from_log(log: &Log) -> Option<Self> {
+        if log.removed == Some(true) {
+            return None;
+        }
+        let (kind, from, to, value) = match decode_purse_log(log)? {
+            Purse404Event::Erc20Transfer(e) => (ERC20_TRANSFER, e.from, e.to, e.amount),
+            Purse404Event::Erc20Approval(e) => (ERC20_APPROVAL, e.owner, e.spender, e.value),
+            Purse404Event::Erc721Transfer(e) => (ERC721_TRANSFER, e.from, e.to, e.id),
+            Purse404Event::Erc721Approval(e) => (ERC721_APPROVAL, e.owner, e.spender, e.id),
+            Purse404Event::Other(_) => return None,
+        };
+        Some(Self {
+            block_number: log.block_number?.as_u64(),
+            log_index: log.log_index?.as_u64(),
+            tx_hash: format!("{:?}", log.transaction_hash?),
+            kind: kind.to_string(),
+            from,
+            to,
+            value,
+        })
+    }
+}
+
+/// Holdings of an account at a given block, rebuilt from the indexed transfers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Holder {
+    pub address: Address,
+    /// ERC20 balance
+    pub balance: U256,
+    /// ERC721 token IDs owned
+    pub token_ids: Vec<U256>,
+}
+
+/// Local SQLite index of the Transfer and Approval events of the Purse404 contract.
+/// 
+/// Events are keyed by block number and log index, so re-indexing a block range is idempotent.
+/// The index is bound to the chain id and contract address it was created for, and records
+/// the last indexed block for incremental catch-up.
+pub struct EventIndex {
+    conn: Connection,
+}
+
+impl EventIndex {
+    /// Open (or create) the index database
+    /// ### Arguments
+    /// * `path` - File path of the SQLite database
+    /// * `chain_id` - Chain Id of the indexed contract
+    /// * `contract` - Address of the indexed contract
+    /// 
+    /// ### Returns
+    /// * `Result<Self>` - The index, or an error if the database belongs to another chain or contract
+    pub fn open(path: &str, chain_id: u32, contract: Address) -> Result<Self> {
+        Self::from_connection(Connection::open(path)?, chain_id, contract)
+    }
+
+    fn from_connection(conn: Connection, chain_id: u32, contract: Address) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS events (
+                block_number INTEGER NOT NULL,
+                log_index INTEGER NOT NULL,
+                tx_hash TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                from_address TEXT NOT NULL,
+                to_address TEXT NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (block_number, log_index)
+            );
+            CREATE INDEX IF NOT EXISTS events_to_address ON events (kind, to_address);
+            CREATE TABLE IF NOT EXISTS meta (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );"
+        )?;
+        let index = Self { conn };
+
+        for (key, value) in [("chain_id", chain_id.to_string()), ("contract", format!("{:?}", contract))] {
+            match index.meta(key)? {
+                Some(stored) if stored != value => return Err(eyre::eyre!(
+                    "Index was created for {} {}, not {}", key, stored, value
+                )),
+                Some(_) => {},
+                None => index.set_meta(key, &value)?,
+            }
+        }
+
+        Ok(index)
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.conn
+            .query_row("SELECT value FROM meta WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()?)
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, ?2)
+            ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+            params![key, value]
+        )?;
+        Ok(())
+    }
+
+    /// Returns the last block the index is complete up to, `None` if nothing was indexed yet
+    pub fn last_indexed_block(&self) -> Result<Option<u64>> {
+        self.meta("last_indexed_block")?
+            .map(|block| block.parse::<u64>().map_err(eyre::Report::from))
+            .transpose()
+    }
+
+    /// Checks that indexing from the given block leaves no gap after the blocks already indexed
+    /// ### Arguments
+    /// * `from_block` - First block to index
+    pub fn check_sync_start(&self, from_block: u64) -> Result<()> {
+        match self.last_indexed_block()? {
+            Some(last) if from_block > last + 1 => Err(eyre::eyre!(
+                "Index is complete up to block {}, syncing from block {} would leave a gap, start at or before block {}",
+                last,
+                from_block,
+                last + 1
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Stores the events of a block range, and marks the range as indexed, in one transaction.
+    /// Re-indexing an older range does not move the last indexed block backwards.
+    /// ### Arguments
+    /// * `events` - Events in the block range
+    /// * `to_block` - Last block of the range
+    /// 
+    /// ### Returns
+    /// * `Result<usize>` - Number of events that were not already indexed
+    pub fn insert_events(&mut self, events: &[IndexedEvent], to_block: u64) -> Result<usize> {
+        let last_block = self.last_indexed_block()?.map_or(to_block, |last| last.max(to_block));
+        let tx = self.conn.transaction()?;
+        let mut inserted = 0;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO events
+                (block_number, log_index, tx_hash, kind, from_address, to_address, value)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"
+            )?;
+            for event in events {
+                inserted += stmt.execute(params![
+                    event.block_number,
+                    event.log_index,
+                    event.tx_hash,
+                    event.kind,
+                    format!("{:?}", event.from),
+                    format!("{:?}", event.to),
+                    event.value.to_string(),
+                ])?;
+            }
+        }
+        tx.execute(
+            "INSERT INTO meta (key, value) VALUES ('last_indexed_block', ?1)
+            ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+            params![last_block.to_string()]
+        )?;
+        tx.commit()?;
+        Ok(inserted)
+    }
+
+    /// Reads the indexed events of the given kind, in chain order
+    /// ### Arguments
+    /// * `kind` - Event kind
+    /// * `up_to_block` - Only include events up to and including this block (optional)
+    /// 
+    /// ### Returns
+    /// * `Result<Vec<IndexedEvent>>` - Indexed events
+    pub fn events(&self, kind: &str, up_to_block: Option<u64>) -> Result<Vec<IndexedEvent>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT block_number, log_index, tx_hash, kind, from_address, to_address, value
+            FROM events WHERE kind = ?1 AND block_number <= ?2
+            ORDER BY block_number, log_index"
+        )?;
+        let rows = stmt.query_map(params![kind, up_to_block.unwrap_or(i64::MAX as u64)], |row| {
+            Ok((
+                row.get::<_, u64>(0)?,
+                row.get::<_, u64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+            ))
+        })?;
+
+        let mut events = Vec::new();
+        for row in rows {
+            let (block_number, log_index, tx_hash, kind, from, to, value) = row?;
+            events.push(IndexedEvent {
+                block_number,
+                log_index,
+                tx_hash,
+                kind,
+                from: from.parse()?,
+                to: to.parse()?,
+                value: U256::from_dec_str(&value)?,
+            });
+        }
+        Ok(events)
+    }
+
+    /// Returns every NFT ID the given address ever received
+    /// ### Arguments
+    /// * `address` - Holder address
+    /// 
+    /// ### Returns
+    /// * `Result<Vec<U256>>` - Token IDs, sorted ascending
+    pub fn nft_ids_ever_held(&self, address: &Address) -> Result<Vec<U256>> {
+        let mut ids: Vec<U256> = self
+            .events(ERC721_TRANSFER, None)?
+            .into_iter()
+            .filter(|event| event.to == *address)
+            .map(|event| event.value)
+            .collect();
+        ids.sort();
+        ids.dedup();
+        Ok(ids)
+    }
+
+    /// Rebuilds the holder list at the given block from the indexed transfers.
+    /// 
+    /// #### Note
+    /// Balances are only complete if the index starts at the deployment block of the contract.
+    /// ### Arguments
+    /// * `block` - Block number
+    /// 
+    /// ### Returns
+    /// * `Result<Vec<Holder>>` - Accounts with a non-zero ERC20 balance or owned NFTs, sorted by address
+    pub fn holders_at(&self, block: u64) -> Result<Vec<Holder>> {
+        let mut balances: HashMap<Address, U256> = HashMap::new();
+        for transfer in self.events(ERC20_TRANSFER, Some(block))? {
+            if !transfer.from.is_zero() {
+                let balance = balances.entry(transfer.from).or_default();
+                *balance = balance.saturating_sub(transfer.value);
+            }
+            if !transfer.to.is_zero() {
+                let balance = balances.entry(transfer.to).or_default();
+                *balance = balance.saturating_add(transfer.value);
+            }
+        }
+
+        let mut owners: HashMap<U256, Address> = HashMap::new();
+        for transfer in self.events(ERC721_TRANSFER, Some(block))? {
+            owners.insert(transfer.value, transfer.to);
+        }
+
+        let mut holders: BTreeMap<Address, Holder> = BTreeMap::new();
+        for (address, balance) in balances.into_iter().filter(|(_, balance)| !balance.is_zero()) {
+            holders.entry(address).or_insert_with(|| Holder { address, ..Default::default() }).balance = balance;
+        }
+        for (id, owner) in owners.into_iter().filter(|(_, owner)| !owner.is_zero()) {
+            holders
+                .entry(owner)
+                .or_insert_with(|| Holder { address: owner, ..Default::default() })
+                .token_ids
+                .push(id);
+        }
+
+        Ok(holders
+            .into_values()
+            .map(|mut holder| {
+                holder.token_ids.sort();
+                holder
+            })
+            .collect())
+    }
+}
+
+/// Returns the latest block that has at least the given number of confirmations.
+/// Blocks past it may still be reorged, so they are not indexed, see `sync_index`.
+/// ### Arguments
+/// * `prov` - Provider
+/// * `confirmations` - Number of blocks behind the latest block
+/// 
+/// ### Returns
+/// * `Result<u64>` - The latest confirmed block number
+pub async fn confirmed_block<M: Middleware>(prov: &M, confirmations: u64) -> Result<u64> {
+    let latest = prov
+        .get_block_number()
+        .await
+        .map_err(|e| eyre::eyre!("Failed to get block number: {}", e))?
+        .as_u64();
+    Ok(latest.saturating_sub(confirmations))
+}
+
+/// Pages `eth_getLogs` over the block range and stores the Transfer and Approval events
+/// of the contract in the index. Each page is committed with its last block, so an
+/// interrupted sync resumes from the last completed page. The range may not start
+/// after the block following the last indexed block, see `EventIndex::check_sync_start`.
+/// 
+/// #### Note
+/// Indexed blocks are never revisited, so the range should end at a confirmed block (see `confirmed_block`),
+/// or events of reorged blocks stay in the index.
+/// ### Arguments
+/// * `prov` - Provider
+/// * `index` - Event index
+/// * `contract` - Address of the Purse404 contract
+/// * `from_block` - First block of the range
+/// * `to_block` - Last block of the range
+/// * `page_size` - Number of blocks per `eth_getLogs` request
+/// 
+/// ### Returns
+/// * `Result<usize>` - Number of events added to the index
+pub async fn sync_index<M: Middleware>(
+    prov: &M,
+    index: &mut EventIndex,
+    contract: Address,
+    from_block: u64,
+    to_block: u64,
+    page_size: u64,
+) -> Result<usize> {
+    index.check_sync_start(from_block)?;
+    let page_size = page_size.max(1);
+    let mut added = 0;
+    let mut start = from_block;
+
+    while start <= to_block {
+        let end = to_block.min(start.saturating_add(page_size - 1));
+        let filter = Filter::new()
+            .address(contract)
+            .topic0(vec![TransferFilter::signature(), ApprovalFilter::signature()])
+            .from_block(start)
+            .to_block(end);
+        let logs = match prov.get_logs(&filter).await {
+            Ok(logs) => logs,
+            Err(e) => return Err(eyre::eyre!("Failed to get logs for blocks {} to {}: {}", start, end, e))
+        };
+        let events: Vec<IndexedEvent> = logs.iter().filter_map(IndexedEvent::from_log).collect();
+        let inserted = index.insert_events(&events, end)?;
+        println!("> Indexed blocks {} to {}: {} new events", start, end, inserted);
+
+        added += inserted;
+        start = end + 1;
+    }
+
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(block_number: u64, kind: &str, from: u64, to: u64, value: u64) -> IndexedEvent {
+        IndexedEvent {
+            block_number,
+            log_index: 0,
+            tx_hash: format!("0x{:064x}", block_number),
+            kind: kind.to_string(),
+            from: Address::from_low_u64_be(from),
+            to: Address::from_low_u64_be(to),
+            value: U256::from(value),
+        }
+    }
+
+    #[test]
+    fn test_event_index() {
+        let contract = Address::from_low_u64_be(404);
+        let mut index = EventIndex::from_connection(Connection::open_in_memory().unwrap(), 1, contract).unwrap();
+        assert_eq!(index.last_indexed_block().unwrap(), None);
+
+        let events = vec![
+            transfer(10, ERC20_TRANSFER, 0, 1, 100),
+            transfer(11, ERC721_TRANSFER, 0, 1, 7),
+            transfer(12, ERC20_TRANSFER, 1, 2, 40),
+            transfer(13, ERC721_TRANSFER, 1, 2, 7),
+        ];
+        assert_eq!(index.insert_events(&events, 20).unwrap(), 4);
+        // Re-indexing the same range does not duplicate events
+        assert_eq!(index.insert_events(&events, 20).unwrap(), 0);
+        assert_eq!(index.last_indexed_block().unwrap(), Some(20));
+
+        assert_eq!(index.nft_ids_ever_held(&Address::from_low_u64_be(1)).unwrap(), vec![U256::from(7)]);
+
+        let holders = index.holders_at(11).unwrap();
+        assert_eq!(holders.len(), 1);
+        assert_eq!(holders[0].balance, U256::from(100));
+        assert_eq!(holders[0].token_ids, vec![U256::from(7)]);
+
+        let holders = index.holders_at(20).unwrap();
+        assert_eq!(holders.len(), 2);
+        assert_eq!(holders[0].balance, U256::from(60));
+        assert!(holders[0].token_ids.is_empty());
+        assert_eq!(holders[1].token_ids, vec![U256::from(7)]);
+
+        // Re-indexing an older range does not move the marker backwards
+        index.insert_events(&events[..2], 11).unwrap();
+        assert_eq!(index.last_indexed_block().unwrap(), Some(20));
+
+        // Syncing may start anywhere up to the block after the marker, but not beyond it
+        assert!(index.check_sync_start(5).is_ok());
+        assert!(index.check_sync_start(21).is_ok());
+        assert!(index.check_sync_start(22).is_err());
+
+        let conn = index.conn;
+        assert!(EventIndex::from_connection(conn, 11155111, contract).is_err());
+    }
+}
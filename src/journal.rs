@@ -0,0 +1,287 @@
+use eyre::Result;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+use ethers::types::{Address, Bytes, H256, U256};
+use crate::utils::unix_timestamp;
+
+/// Status of a journaled transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalStatus {
+    /// Signed and about to be broadcast, the transaction may or may not have reached the network
+    Pending,
+    /// Included in a block, but not yet recorded in the csv log
+    Mined,
+    /// Recorded in the csv log
+    Logged,
+    /// Never included, its nonce was used by another transaction
+    Dropped,
+    /// Included in a block, but reverted
+    Reverted,
+}
+
+impl std::fmt::Display for JournalStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JournalStatus::Pending => write!(f, "pending"),
+            JournalStatus::Mined => write!(f, "mined"),
+            JournalStatus::Logged => write!(f, "logged"),
+            JournalStatus::Dropped => write!(f, "dropped"),
+            JournalStatus::Reverted => write!(f, "reverted"),
+        }
+    }
+}
+
+/// An entry of the transaction journal
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct JournalEntry {
+    /// Transaction hash
+    pub tx_hash: H256,
+    /// Status of the transaction
+    pub status: JournalStatus,
+    /// Chain Id the transaction was signed for
+    pub chain_id: u64,
+    /// Derivation number of the signing wallet
+    pub derivation: u32,
+    /// Address of the signing wallet
+    pub from: Address,
+    /// Nonce of the transaction
+    pub nonce: U256,
+    /// Raw RLP encoded signed transaction
+    pub raw_tx: Bytes,
+    /// Block number the transaction was included in, once mined
+    pub block_number: Option<u64>,
+    /// Unix timestamp (seconds) of the status update
+    pub updated_at: u64,
+}
+
+impl JournalEntry {
+    /// Returns a copy of the entry with the given status (and block number, if known)
+    /// ### Arguments
+    /// * `status` - New status
+    /// * `block_number` - Block number the transaction was included in (optional)
+    /// 
+    /// ### Returns
+    /// * `Self` - The updated `JournalEntry`
+    pub fn with_status(&self, status: JournalStatus, block_number: Option<u64>) -> Self {
+        Self {
+            status,
+            block_number: block_number.or(self.block_number),
+            updated_at: unix_timestamp(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Append-only write-ahead journal of signed transactions.
+/// 
+/// An entry is persisted before a transaction is broadcast, and every status change is appended
+/// as a new line (JSON per line), so the journal survives crashes at any point of the execution.
+/// The latest line of a transaction hash is its current state.
+#[derive(Clone, Debug)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    /// Create a new `Journal` instance
+    /// ### Arguments
+    /// * `path` - File path of the journal
+    /// 
+    /// ### Returns
+    /// * `Self` - A new `Journal` instance
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self { path: path.as_ref().to_path_buf() }
+    }
+
+    /// Create the journal that belongs to the given csv log, stored alongside it
+    /// ### Arguments
+    /// * `file_path` - File path of the csv log
+    /// 
+    /// ### Returns
+    /// * `Self` - A new `Journal` instance
+    pub fn for_log(file_path: &str) -> Self {
+        Self::new(format!("{}.journal", file_path))
+    }
+
+    /// Returns the file path of the journal
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends the entry to the journal, and syncs it to disk before returning
+    /// ### Arguments
+    /// * `entry` - Journal entry
+    /// 
+    /// ### Returns
+    /// * `Result<()>` - Result
+    pub fn append(&self, entry: &JournalEntry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&self.path)?;
+        file.lock_exclusive()?;
+
+        // Terminate a line left truncated by a crash, so it does not swallow this entry
+        let len = file.metadata()?.len();
+        if len > 0 {
+            let mut last = [0u8; 1];
+            (&file).seek(SeekFrom::Start(len - 1))?;
+            (&file).read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                line.insert(0, b'\n');
+            }
+        }
+        (&file).write_all(&line)?;
+        file.sync_all()?;
+        file.unlock()?;
+
+        Ok(())
+    }
+
+    /// Appends a status update for the given transaction hash
+    /// ### Arguments
+    /// * `tx_hash` - Transaction hash
+    /// * `status` - New status
+    /// * `block_number` - Block number the transaction was included in (optional)
+    /// 
+    /// ### Returns
+    /// * `Result<()>` - Result, an error if the transaction is not in the journal
+    pub fn update_status(
+        &self,
+        tx_hash: &H256,
+        status: JournalStatus,
+        block_number: Option<u64>
+    ) -> Result<()> {
+        let entry = self
+            .entries()?
+            .into_iter()
+            .find(|entry| entry.tx_hash == *tx_hash)
+            .ok_or_else(|| eyre::eyre!("Transaction {:?} not found in journal", tx_hash))?;
+        self.append(&entry.with_status(status, block_number))
+    }
+
+    /// Reads the current state of every journaled transaction, in the order they were first journaled
+    /// 
+    /// ### Returns
+    /// * `Result<Vec<JournalEntry>>` - Journal entries, empty if the journal does not exist
+    pub fn entries(&self) -> Result<Vec<JournalEntry>> {
+        let file = match File::open(&self.path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(eyre::eyre!("Failed to open journal: {}", e))
+        };
+        file.lock_shared()?;
+
+        let mut entries: Vec<JournalEntry> = Vec::new();
+        for line in BufReader::new(&file).lines() {
+            let line = line?;
+            // A crash during an append can leave a truncated last line, which is skipped
+            let entry: JournalEntry = match serde_json::from_str(&line) {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            match entries.iter_mut().find(|e| e.tx_hash == entry.tx_hash) {
+                Some(existing) => *existing = entry,
+                None => entries.push(entry),
+            }
+        }
+        file.unlock()?;
+
+        Ok(entries)
+    }
+
+    /// Reads the journaled transactions that are not yet logged, dropped or reverted
+    /// 
+    /// ### Returns
+    /// * `Result<Vec<JournalEntry>>` - Unsettled journal entries
+    pub fn unsettled(&self) -> Result<Vec<JournalEntry>> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|entry| matches!(entry.status, JournalStatus::Pending | JournalStatus::Mined))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn entry(n: u64, status: JournalStatus) -> JournalEntry {
+        JournalEntry {
+            tx_hash: H256::from_low_u64_be(n),
+            status,
+            chain_id: 11155111,
+            derivation: 0,
+            from: Address::from_low_u64_be(1),
+            nonce: U256::from(n),
+            raw_tx: Bytes::from(vec![0x02, n as u8]),
+            block_number: None,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_journal() {
+        let file_path = std::env::temp_dir().join("contract_interfacer_journal_test.journal");
+        let _ = fs::remove_file(&file_path);
+        let journal = Journal::new(&file_path);
+        assert!(journal.entries().unwrap().is_empty());
+
+        journal.append(&entry(1, JournalStatus::Pending)).unwrap();
+        journal.append(&entry(2, JournalStatus::Pending)).unwrap();
+        journal.append(&entry(3, JournalStatus::Pending)).unwrap();
+
+        // Status updates replace the state of an existing hash, a missing hash is an error
+        journal.update_status(&H256::from_low_u64_be(1), JournalStatus::Mined, Some(100)).unwrap();
+        journal.update_status(&H256::from_low_u64_be(2), JournalStatus::Logged, Some(101)).unwrap();
+        assert!(journal.update_status(&H256::from_low_u64_be(4), JournalStatus::Mined, None).is_err());
+
+        let entries = journal.entries().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].status, JournalStatus::Mined);
+        assert_eq!(entries[0].block_number, Some(100));
+        assert_eq!(entries[1].status, JournalStatus::Logged);
+        assert_eq!(entries[2].status, JournalStatus::Pending);
+
+        // Only pending and mined transactions are unsettled
+        let unsettled: Vec<H256> = journal.unsettled().unwrap().iter().map(|e| e.tx_hash).collect();
+        assert_eq!(unsettled, vec![H256::from_low_u64_be(1), H256::from_low_u64_be(3)]);
+
+        fs::remove_file(&file_path).unwrap();
+    }
+
+    #[test]
+    fn test_journal_truncated_line() {
+        let file_path = std::env::temp_dir().join("contract_interfacer_journal_truncated_test.journal");
+        let _ = fs::remove_file(&file_path);
+        let journal = Journal::new(&file_path);
+
+        // A crash during an append leaves a truncated last line, and other lines may be malformed
+        journal.append(&entry(1, JournalStatus::Pending)).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&file_path).unwrap();
+        file.write_all(b"not json\n").unwrap();
+        let truncated = serde_json::to_string(&entry(2, JournalStatus::Pending)).unwrap();
+        file.write_all(&truncated.as_bytes()[..truncated.len() / 2]).unwrap();
+        drop(file);
+
+        // The truncated line is terminated, so the appended entry stays on its own line
+        journal.append(&entry(3, JournalStatus::Pending)).unwrap();
+        let entries = journal.entries().unwrap();
+        let hashes: Vec<H256> = entries.iter().map(|e| e.tx_hash).collect();
+        assert_eq!(hashes, vec![H256::from_low_u64_be(1), H256::from_low_u64_be(3)]);
+
+        fs::remove_file(&file_path).unwrap();
+    }
+}
@@ -2,12 +2,24 @@ mod wallet;
 mod contract;
 mod constants;
 mod utils;
+mod transport;
 mod file;
+mod journal;
+mod deployments;
+mod indexer;
+mod report;
+mod verify;
 pub mod cli;
 
 pub use constants::*;
 pub use wallet::Wallet;
 pub use contract::*;
 pub use utils::*;
+pub use transport::*;
 pub use file::*;
+pub use journal::*;
+pub use deployments::*;
+pub use indexer::*;
+pub use report::*;
+pub use verify::*;
 pub use cli::*;
\ No newline at end of file
@@ -0,0 +1,310 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use chrono::{DateTime, NaiveDate};
+use ethers::types::Address;
+use serde::Serialize;
+use crate::file::Record;
+
+/// Filters applied to the records of the csv log when building a report
+#[derive(Debug, Clone, Default)]
+pub struct ReportFilter {
+    /// Only include transactions sent from these derivation numbers
+    pub derivations: Option<Vec<u32>>,
+    /// Only include calls to these functions
+    pub functions: Option<Vec<String>>,
+    /// Only include transactions on or after this date (UTC)
+    pub from_date: Option<NaiveDate>,
+    /// Only include transactions on or before this date (UTC)
+    pub to_date: Option<NaiveDate>,
+}
+
+impl ReportFilter {
+    /// Checks whether the record passes the filter.
+    /// Records without a timestamp (logged with schema v1) never pass a date filter.
+    /// ### Arguments
+    /// * `record` - a `Record` reference
+    /// 
+    /// ### Returns
+    /// * `bool` - `true` if the record should be included
+    pub fn matches(&self, record: &Record) -> bool {
+        if let Some(derivations) = &self.derivations {
+            if !derivations.contains(&record.derivation) {
+                return false;
+            }
+        }
+        if let Some(functions) = &self.functions {
+            if !functions.contains(&record.function) {
+                return false;
+            }
+        }
+        if self.from_date.is_some() || self.to_date.is_some() {
+            let date = match DateTime::parse_from_rfc3339(&record.timestamp) {
+                Ok(dt) => dt.date_naive(),
+                Err(_) => return false,
+            };
+            if self.from_date.is_some_and(|from| date < from)
+            || self.to_date.is_some_and(|to| date > to) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Summary of the calls to one contract function
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FunctionSummary {
+    pub function: String,
+    pub transactions: usize,
+    pub total_tx_fee_eth: f64,
+    pub avg_gas_used: f64,
+    pub avg_gas_price_gwei: f64,
+    pub nfts_gained: usize,
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+}
+
+/// Summary of the transactions sent by one wallet
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SenderSummary {
+    pub derivation: u32,
+    pub sender: String,
+    pub transactions: usize,
+    pub total_tx_fee_eth: f64,
+    pub net_eth_delta: f64,
+    pub net_erc20_delta: f64,
+    pub nfts_gained: usize,
+    pub nfts_owned: usize,
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+}
+
+/// Aggregated report over the csv log
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LogReport {
+    pub transactions: usize,
+    pub total_tx_fee_eth: f64,
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+    pub functions: Vec<FunctionSummary>,
+    pub senders: Vec<SenderSummary>,
+}
+
+/// Widens the time range to include the given timestamp (RFC 3339, UTC), ignoring empty timestamps
+fn extend_range(first: &mut Option<String>, last: &mut Option<String>, timestamp: &str) {
+    if timestamp.is_empty() {
+        return;
+    }
+    if first.as_deref().is_none_or(|f| timestamp < f) {
+        *first = Some(timestamp.to_string());
+    }
+    if last.as_deref().is_none_or(|l| timestamp > l) {
+        *last = Some(timestamp.to_string());
+    }
+}
+
+/// Key of the sender of a record, with its derivation number.
+/// Older logs hold abbreviated addresses (e.g. `0xf775…8d82`) and newer logs full hex addresses.
+/// A full address is its own key. An abbreviated address takes the full address logged for the same
+/// derivation if it abbreviates to it, and otherwise stays abbreviated, so it is told apart by the derivation.
+/// ### Arguments
+/// * `record` - a `Record` reference
+/// * `full_addresses` - Full addresses logged per derivation number
+fn sender_key(record: &Record, full_addresses: &HashMap<u32, HashSet<Address>>) -> (u32, String) {
+    let address = record.sender.parse::<Address>().ok().or_else(|| {
+        full_addresses
+            .get(&record.derivation)?
+            .iter()
+            .find(|address| address.to_string() == record.sender)
+            .copied()
+    });
+    match address {
+        Some(address) => (record.derivation, format!("{:?}", address)),
+        None => (record.derivation, record.sender.clone()),
+    }
+}
+
+/// Builds the report over the records of the csv log.
+/// 
+/// #### Note
+/// NFTs gained by a transaction are the token IDs owned by the sender after the transaction that
+/// were not owned after the sender's previous logged transaction. The previous transaction is
+/// looked up over all records, so filtering does not affect the count. For the first logged
+/// transaction of a sender, every owned token ID counts as gained.
+/// 
+/// Senders are grouped by full address, see `sender_key`, and abbreviated addresses are reported
+/// as their full address once it is logged for the same derivation.
+/// ### Arguments
+/// * `records` - Records of the csv log, in file order
+/// * `filter` - Filters to apply
+/// 
+/// ### Returns
+/// * `LogReport` - The report
+pub fn build_report(records: &[Record], filter: &ReportFilter) -> LogReport {
+    let mut report = LogReport::default();
+    let mut functions: BTreeMap<String, (FunctionSummary, u64, f64)> = BTreeMap::new();
+    let mut senders: BTreeMap<(u32, String), SenderSummary> = BTreeMap::new();
+    let mut previous_owned: HashMap<(u32, String), HashSet<&str>> = HashMap::new();
+    let mut full_addresses: HashMap<u32, HashSet<Address>> = HashMap::new();
+    for record in records {
+        if let Ok(address) = record.sender.parse::<Address>() {
+            full_addresses.entry(record.derivation).or_default().insert(address);
+        }
+    }
+
+    for record in records {
+        let key = sender_key(record, &full_addresses);
+        let owned: HashSet<&str> = record.owned_token_ids().into_iter().collect();
+        let nfts_gained = match previous_owned.get(&key) {
+            Some(previous) => owned.difference(previous).count(),
+            None => owned.len(),
+        };
+        let nfts_owned = owned.len();
+        previous_owned.insert(key.clone(), owned);
+
+        if !filter.matches(record) {
+            continue;
+        }
+
+        report.transactions += 1;
+        report.total_tx_fee_eth += record.tx_fee;
+        extend_range(&mut report.first_timestamp, &mut report.last_timestamp, &record.timestamp);
+
+        let (function, gas_used_sum, gas_price_sum) = functions
+            .entry(record.function.clone())
+            .or_insert_with(|| (
+                FunctionSummary { function: record.function.clone(), ..Default::default() },
+                0,
+                0.0
+            ));
+        function.transactions += 1;
+        function.total_tx_fee_eth += record.tx_fee;
+        function.nfts_gained += nfts_gained;
+        *gas_used_sum += record.gas_used;
+        *gas_price_sum += record.gas_price;
+        extend_range(&mut function.first_timestamp, &mut function.last_timestamp, &record.timestamp);
+
+        let sender = senders
+            .entry(key.clone())
+            .or_insert_with(|| SenderSummary {
+                derivation: record.derivation,
+                sender: key.1,
+                ..Default::default()
+            });
+        sender.transactions += 1;
+        sender.total_tx_fee_eth += record.tx_fee;
+        sender.net_eth_delta += record.sender_balance_after_eth - record.sender_balance_before_eth;
+        sender.net_erc20_delta += record.sender_balance_after_erc20 - record.sender_balance_before_erc20;
+        sender.nfts_gained += nfts_gained;
+        sender.nfts_owned = nfts_owned;
+        extend_range(&mut sender.first_timestamp, &mut sender.last_timestamp, &record.timestamp);
+    }
+
+    report.functions = functions
+        .into_values()
+        .map(|(mut function, gas_used_sum, gas_price_sum)| {
+            function.avg_gas_used = gas_used_sum as f64 / function.transactions as f64;
+            function.avg_gas_price_gwei = gas_price_sum / function.transactions as f64;
+            function
+        })
+        .collect();
+    report.senders = senders.into_values().collect();
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::U256;
+    use crate::file::{read_from_csv, write_to_csv, LogEntry};
+
+    #[test]
+    fn test_build_report() {
+        let records = read_from_csv("test.csv").unwrap();
+
+        let report = build_report(&records, &ReportFilter::default());
+        assert_eq!(report.transactions, records.len());
+        assert_eq!(
+            report.functions.iter().map(|f| f.transactions).sum::<usize>(),
+            records.len()
+        );
+        assert_eq!(
+            report.senders.iter().map(|s| s.transactions).sum::<usize>(),
+            records.len()
+        );
+
+        let filter = ReportFilter {
+            functions: Some(vec!["mintERC721".to_string()]),
+            ..Default::default()
+        };
+        let report = build_report(&records, &filter);
+        assert_eq!(report.functions.len(), 1);
+        assert_eq!(report.functions[0].function, "mintERC721");
+        assert_eq!(report.functions[0].nfts_gained, 1);
+
+        // Records logged with schema v1 have no timestamp, so never pass a date filter
+        let filter = ReportFilter {
+            from_date: NaiveDate::from_ymd_opt(2024, 1, 1),
+            ..Default::default()
+        };
+        assert_eq!(build_report(&records, &filter).transactions, 0);
+    }
+
+    #[test]
+    fn test_build_report_mixed_address_formats() {
+        let file_path = std::env::temp_dir().join("contract_interfacer_report_mixed_test.csv");
+        let file_path = file_path.to_str().unwrap();
+        std::fs::copy("test.csv", file_path).unwrap();
+        let records = read_from_csv(file_path).unwrap();
+        let report_before = build_report(&records, &ReportFilter::default());
+
+        // Same wallet as the abbreviated `0xf775…8d82`, logged as a full hex address
+        let last = records.iter().rev().find(|r| r.sender == "0xf775…8d82").unwrap();
+        let owned_token_ids = last.owned_token_ids()
+            .iter()
+            .map(|id| U256::from_dec_str(id).unwrap())
+            .collect();
+        let entry = LogEntry {
+            tx_hash: format!("0x{:064x}", 1),
+            derivation_number: last.derivation,
+            msg_sender: "0xf775000000000000000000000000000000008d82".parse().unwrap(),
+            msg_sender_owned_token_ids: Some(owned_token_ids),
+            gas_price: "1.5".to_string(),
+            gas_used: "21000".to_string(),
+            tx_fee: "0.0000315".to_string(),
+            receipt_json: "{}".to_string(),
+            call_function: "transfer".to_string(),
+            ..Default::default()
+        };
+        write_to_csv(file_path, &entry, true).unwrap();
+        // Another wallet with the same abbreviation, from another derivation
+        let other = LogEntry {
+            tx_hash: format!("0x{:064x}", 2),
+            derivation_number: 99,
+            msg_sender: "0xf775000000000000000000000000000000018d82".parse().unwrap(),
+            ..entry.clone()
+        };
+        write_to_csv(file_path, &other, true).unwrap();
+
+        let records = read_from_csv(file_path).unwrap();
+        let report = build_report(&records, &ReportFilter::default());
+        assert_eq!(report.senders.len(), report_before.senders.len() + 1);
+
+        let sender_before = report_before.senders.iter().find(|s| s.sender == "0xf775…8d82").unwrap();
+        let sender = report.senders
+            .iter()
+            .find(|s| s.sender == "0xf775000000000000000000000000000000008d82")
+            .unwrap();
+        assert_eq!(sender.transactions, sender_before.transactions + 1);
+        assert_eq!(sender.nfts_gained, sender_before.nfts_gained);
+
+        let other_sender = report.senders
+            .iter()
+            .find(|s| s.sender == "0xf775000000000000000000000000000000018d82")
+            .unwrap();
+        assert_eq!(other_sender.derivation, 99);
+        assert_eq!(other_sender.transactions, 1);
+
+        std::fs::remove_file(file_path).unwrap();
+    }
+}
@@ -0,0 +1,413 @@
+use std::{fmt, str::FromStr, sync::Arc, time::Duration};
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use ethers::{
+    providers::{
+        Http,
+        HttpClientError,
+        HttpRateLimitRetryPolicy,
+        Ipc,
+        IpcError,
+        JsonRpcClient,
+        JsonRpcError,
+        ProviderError,
+        PubsubClient,
+        RetryPolicy,
+        RpcError,
+        Ws,
+        WsClientError,
+    },
+    types::{H256, U256},
+    utils::{hex, keccak256},
+};
+
+/// JSON-RPC transport picked from the endpoint, so that one `Provider<RpcTransport>` type
+/// can talk to a node over HTTP, WebSocket or IPC.
+/// Subscriptions are only supported over WebSocket and IPC.
+#[derive(Clone, Debug)]
+pub enum RpcTransport {
+    Http(Http),
+    Ws(Ws),
+    Ipc(Ipc),
+}
+
+impl RpcTransport {
+    /// Connect to the given endpoint.
+    /// `http://` and `https://` URLs are used over HTTP, `ws://` and `wss://` URLs over WebSocket,
+    /// and any other endpoint is used as the path of an IPC socket.
+    /// ### Arguments
+    /// * `endpoint` - RPC URL or IPC socket path
+    /// 
+    /// ### Returns
+    /// * `Result<RpcTransport>` - The connected transport
+    pub async fn connect(endpoint: &str) -> eyre::Result<Self> {
+        if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+            Ok(Self::Http(Http::from_str(endpoint)?))
+        } else if endpoint.starts_with("ws://") || endpoint.starts_with("wss://") {
+            Ok(Self::Ws(Ws::connect(endpoint).await?))
+        } else {
+            Ok(Self::Ipc(Ipc::connect(endpoint).await?))
+        }
+    }
+
+    /// Returns `true` if the transport supports subscriptions
+    pub fn supports_pubsub(&self) -> bool {
+        !matches!(self, Self::Http(_))
+    }
+}
+
+/// Error of a `RpcTransport` or `ResilientTransport`
+#[derive(Debug)]
+pub enum RpcTransportError {
+    Http(HttpClientError),
+    Ws(WsClientError),
+    Ipc(IpcError),
+    /// Subscriptions were requested over HTTP
+    PubsubUnsupported,
+    /// The request params could not be serialized
+    Serde(serde_json::Error),
+    /// No response within the request timeout
+    Timeout { method: String, timeout: Duration },
+    /// Every endpoint failed, after retries
+    Exhausted { method: String, endpoints: usize, retries: u32, last: Box<RpcTransportError> },
+}
+
+impl fmt::Display for RpcTransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcTransportError::Http(e) => write!(f, "{}", e),
+            RpcTransportError::Ws(e) => write!(f, "{}", e),
+            RpcTransportError::Ipc(e) => write!(f, "{}", e),
+            RpcTransportError::PubsubUnsupported => write!(f, "Subscriptions are not supported over HTTP"),
+            RpcTransportError::Serde(e) => write!(f, "Failed to serialize request params: {}", e),
+            RpcTransportError::Timeout { method, timeout } => write!(
+                f, "{} timed out after {}s", method, timeout.as_secs_f64()
+            ),
+            RpcTransportError::Exhausted { method, endpoints, retries, last } => write!(
+                f, "{} failed on all {} RPC endpoint(s), after up to {} retries each. Last error: {}",
+                method, endpoints, retries, last
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RpcTransportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RpcTransportError::Http(e) => Some(e),
+            RpcTransportError::Ws(e) => Some(e),
+            RpcTransportError::Ipc(e) => Some(e),
+            RpcTransportError::Serde(e) => Some(e),
+            RpcTransportError::Exhausted { last, .. } => Some(last.as_ref()),
+            RpcTransportError::PubsubUnsupported | RpcTransportError::Timeout { .. } => None,
+        }
+    }
+}
+
+impl RpcError for RpcTransportError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            RpcTransportError::Http(e) => e.as_error_response(),
+            RpcTransportError::Ws(e) => e.as_error_response(),
+            RpcTransportError::Ipc(e) => e.as_error_response(),
+            RpcTransportError::Exhausted { last, .. } => last.as_error_response(),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            RpcTransportError::Http(e) => e.as_serde_error(),
+            RpcTransportError::Ws(e) => e.as_serde_error(),
+            RpcTransportError::Ipc(e) => e.as_serde_error(),
+            RpcTransportError::Serde(e) => Some(e),
+            RpcTransportError::Exhausted { last, .. } => last.as_serde_error(),
+            _ => None,
+        }
+    }
+}
+
+impl From<RpcTransportError> for ProviderError {
+    fn from(e: RpcTransportError) -> Self {
+        match e {
+            RpcTransportError::Http(e) => e.into(),
+            RpcTransportError::Ws(e) => e.into(),
+            RpcTransportError::Ipc(e) => e.into(),
+            RpcTransportError::PubsubUnsupported => ProviderError::CustomError(e.to_string()),
+            // Kept as a client error, so that revert data in the last error can still be decoded
+            e => ProviderError::JsonRpcClientError(Box::new(e)),
+        }
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for RpcTransport {
+    type Error = RpcTransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            RpcTransport::Http(client) => client.request(method, params).await.map_err(RpcTransportError::Http),
+            RpcTransport::Ws(client) => client.request(method, params).await.map_err(RpcTransportError::Ws),
+            RpcTransport::Ipc(client) => client.request(method, params).await.map_err(RpcTransportError::Ipc),
+        }
+    }
+}
+
+impl PubsubClient for RpcTransport {
+    type NotificationStream = <Ws as PubsubClient>::NotificationStream;
+
+    fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, Self::Error> {
+        match self {
+            RpcTransport::Http(_) => Err(RpcTransportError::PubsubUnsupported),
+            RpcTransport::Ws(client) => client.subscribe(id).map_err(RpcTransportError::Ws),
+            RpcTransport::Ipc(client) => client.subscribe(id).map_err(RpcTransportError::Ipc),
+        }
+    }
+
+    fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), Self::Error> {
+        match self {
+            RpcTransport::Http(_) => Err(RpcTransportError::PubsubUnsupported),
+            RpcTransport::Ws(client) => client.unsubscribe(id).map_err(RpcTransportError::Ws),
+            RpcTransport::Ipc(client) => client.unsubscribe(id).map_err(RpcTransportError::Ipc),
+        }
+    }
+}
+
+/// Retry settings of a `ResilientTransport`
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryConfig {
+    /// Retries per endpoint, after the first attempt
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled on each retry
+    pub initial_backoff: Duration,
+    /// Timeout of each attempt
+    pub timeout: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(500),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Read the retry settings from the environment, falling back to the defaults:
+    /// `RPC_MAX_RETRIES`, `RPC_INITIAL_BACKOFF_MS` and `RPC_TIMEOUT_SECS`
+    /// ### Returns
+    /// * `Result<RetryConfig>` - The retry settings, an error if a variable is set but invalid
+    pub fn from_env() -> eyre::Result<Self> {
+        fn var<T: FromStr>(name: &str) -> eyre::Result<Option<T>> {
+            match std::env::var(name) {
+                Ok(value) => value
+                    .trim()
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| eyre::eyre!("Invalid {}: {}", name, value)),
+                Err(_) => Ok(None),
+            }
+        }
+
+        let default = Self::default();
+        Ok(Self {
+            max_retries: var("RPC_MAX_RETRIES")?.unwrap_or(default.max_retries),
+            initial_backoff: var("RPC_INITIAL_BACKOFF_MS")?.map(Duration::from_millis).unwrap_or(default.initial_backoff),
+            timeout: var("RPC_TIMEOUT_SECS")?.map(Duration::from_secs).unwrap_or(default.timeout),
+        })
+    }
+}
+
+/// Retry policy of a `ResilientTransport`.
+/// Rate limits (HTTP 429 and their JSON-RPC error codes) and timeouts are retried,
+/// following `HttpRateLimitRetryPolicy` for HTTP endpoints.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimitRetryPolicy;
+
+impl RetryPolicy<RpcTransportError> for RateLimitRetryPolicy {
+    fn should_retry(&self, error: &RpcTransportError) -> bool {
+        match error {
+            RpcTransportError::Http(e) => HttpRateLimitRetryPolicy.should_retry(e),
+            RpcTransportError::Timeout { .. } => true,
+            e => match e.as_error_response() {
+                Some(response) => HttpRateLimitRetryPolicy.should_retry(&HttpClientError::JsonRpcError(response.clone())),
+                None => false,
+            },
+        }
+    }
+
+    fn backoff_hint(&self, error: &RpcTransportError) -> Option<Duration> {
+        match error {
+            RpcTransportError::Http(e) => HttpRateLimitRetryPolicy.backoff_hint(e),
+            _ => None,
+        }
+    }
+}
+
+/// An endpoint of a `ResilientTransport`
+#[derive(Clone, Debug)]
+struct RpcEndpoint {
+    /// Position in the configured list, from 1, printed instead of the URL which may hold an API key
+    number: usize,
+    transport: RpcTransport,
+}
+
+/// JSON-RPC transport over an ordered list of endpoints.
+/// Each request is given a timeout, and retried with exponential backoff while the retry policy
+/// allows it, eg: on rate limits. When the retries of an endpoint are exhausted, or it fails otherwise,
+/// the request falls over to the next endpoint.
+/// 
+/// Error responses of the node that are not rate limits, eg: reverts, are returned as is,
+/// as another endpoint would answer the same.
+#[derive(Clone, Debug)]
+pub struct ResilientTransport {
+    endpoints: Vec<RpcEndpoint>,
+    config: RetryConfig,
+    policy: Arc<dyn RetryPolicy<RpcTransportError>>,
+}
+
+impl ResilientTransport {
+    /// Connect to the given endpoints, in order of preference.
+    /// Endpoints that cannot be connected to are skipped.
+    /// ### Arguments
+    /// * `endpoints` - RPC URLs or IPC socket paths, see `RpcTransport::connect`
+    /// * `config` - Retry settings
+    /// 
+    /// ### Returns
+    /// * `Result<ResilientTransport>` - The transport, an error if no endpoint could be connected to
+    pub async fn connect(endpoints: &[String], config: RetryConfig) -> eyre::Result<Self> {
+        let mut connected = Vec::new();
+        for (i, endpoint) in endpoints.iter().enumerate() {
+            match RpcTransport::connect(endpoint).await {
+                Ok(transport) => connected.push(RpcEndpoint { number: i + 1, transport }),
+                Err(e) => eprintln!("> Skipping RPC endpoint #{}: {}", i + 1, e),
+            }
+        }
+        if connected.is_empty() {
+            return Err(eyre::eyre!("None of the {} RPC endpoint(s) could be connected to", endpoints.len()))
+        }
+        Ok(Self { endpoints: connected, config, policy: Arc::new(RateLimitRetryPolicy) })
+    }
+
+    /// Replace the retry policy
+    pub fn with_policy(mut self, policy: impl RetryPolicy<RpcTransportError> + 'static) -> Self {
+        self.policy = Arc::new(policy);
+        self
+    }
+
+    /// Send a request to one endpoint, with retries
+    async fn request_endpoint<R: DeserializeOwned + Send>(
+        &self,
+        endpoint: &RpcEndpoint,
+        method: &str,
+        params: &Value,
+    ) -> Result<R, RpcTransportError> {
+        let mut backoff = self.config.initial_backoff;
+        let mut retries = 0;
+        loop {
+            let error = match tokio::time::timeout(self.config.timeout, endpoint.transport.request(method, params)).await {
+                Ok(Ok(response)) => return Ok(response),
+                Ok(Err(e)) => e,
+                Err(_) => RpcTransportError::Timeout { method: method.to_string(), timeout: self.config.timeout },
+            };
+            if retries >= self.config.max_retries || !self.policy.should_retry(&error) {
+                return Err(error)
+            }
+            let wait = self.policy.backoff_hint(&error).unwrap_or(backoff);
+            eprintln!(
+                "> RPC endpoint #{}: {} failed ({}), retrying in {}ms ({}/{})",
+                endpoint.number, method, error, wait.as_millis(), retries + 1, self.config.max_retries
+            );
+            tokio::time::sleep(wait).await;
+            backoff = backoff.saturating_mul(2);
+            retries += 1;
+        }
+    }
+}
+
+/// Checks whether the error of `eth_sendRawTransaction` means the node already has the transaction,
+/// eg: when a retry follows a send that timed out but went through
+fn is_already_known(error: &RpcTransportError) -> bool {
+    match error.as_error_response() {
+        Some(response) => {
+            let message = response.message.to_lowercase();
+            message.contains("already known") || message.contains("known transaction") || message.contains("already imported")
+        },
+        None => false,
+    }
+}
+
+/// Hash of the raw transaction in the params of `eth_sendRawTransaction`
+fn raw_transaction_hash(params: &Value) -> Option<H256> {
+    let raw = params.get(0)?.as_str()?;
+    let bytes = hex::decode(raw.trim_start_matches("0x")).ok()?;
+    Some(H256::from(keccak256(bytes)))
+}
+
+#[async_trait]
+impl JsonRpcClient for ResilientTransport {
+    type Error = RpcTransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        // Serialized once, to be sent to every endpoint
+        let params = serde_json::to_value(params).map_err(RpcTransportError::Serde)?;
+
+        let mut last_error = None;
+        for (i, endpoint) in self.endpoints.iter().enumerate() {
+            let error = match self.request_endpoint(endpoint, method, &params).await {
+                Ok(response) => return Ok(response),
+                Err(e) => e,
+            };
+            if method == "eth_sendRawTransaction" && is_already_known(&error) {
+                if let Some(hash) = raw_transaction_hash(&params) {
+                    return serde_json::from_value(serde_json::json!(hash)).map_err(RpcTransportError::Serde)
+                }
+            }
+            if error.as_error_response().is_some() && !self.policy.should_retry(&error) {
+                return Err(error)
+            }
+            if i + 1 < self.endpoints.len() {
+                eprintln!("> RPC endpoint #{}: {} failed ({}), trying the next endpoint", endpoint.number, method, error);
+            }
+            last_error = Some(error);
+        }
+
+        Err(RpcTransportError::Exhausted {
+            method: method.to_string(),
+            endpoints: self.endpoints.len(),
+            retries: self.config.max_retries,
+            last: Box::new(last_error.expect("a transport has at least one endpoint")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_retry_policy() {
+        let policy = RateLimitRetryPolicy;
+        let rate_limited = JsonRpcError { code: 429, message: "Too many requests".to_string(), data: None };
+        let reverted = JsonRpcError { code: 3, message: "execution reverted".to_string(), data: None };
+
+        assert!(policy.should_retry(&RpcTransportError::Http(HttpClientError::JsonRpcError(rate_limited.clone()))));
+        assert!(policy.should_retry(&RpcTransportError::Ws(WsClientError::JsonRpcError(rate_limited))));
+        assert!(policy.should_retry(&RpcTransportError::Timeout { method: "eth_call".to_string(), timeout: Duration::from_secs(1) }));
+        assert!(!policy.should_retry(&RpcTransportError::Http(HttpClientError::JsonRpcError(reverted))));
+
+        let raw = serde_json::json!(["0x0102"]);
+        assert_eq!(raw_transaction_hash(&raw), Some(H256::from(keccak256([1u8, 2u8]))));
+    }
+}
@@ -1,28 +1,168 @@
 use core::panic;
+use std::io::Write;
 use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 use eyre::Result;
 use serde_json::Value;
 use ethers::{
-    providers::{Http, Provider},
-    types::{Address, U256},
-    middleware::Middleware
+    abi::Token,
+    contract::MULTICALL_ADDRESS,
+    providers::Provider,
+    types::{Address, BlockId, U256},
+    middleware::Middleware,
+    utils::hex,
+};
+use crate::{
+    constants::{MULTISEND_CALL_ONLY_ADDRESS, PURSE_ETH_ADDRESS},
+    deployments::{registry_path, DeploymentRegistry},
+    transport::{ResilientTransport, RetryConfig, RpcTransport},
 };
 use bigdecimal::{BigDecimal, FromPrimitive};
+use chrono::NaiveDate;
 
-/// Create an instance of a provider
+/// Create an instance of a provider.
+/// The transport is picked from the RPC URL: `http(s)://` for HTTP, `ws(s)://` for WebSocket,
+/// and any other value is used as the path of an IPC socket, see `RpcTransport::connect`.
 /// ### Arguments
-/// * `rpc_url` - RPC URL
+/// * `rpc_url` - RPC URL or IPC socket path
 /// 
 /// ### Returns
-/// * `Provider<Http>` - A new instance of `Provider<Http>`
-pub async fn get_provider(rpc_url: &str) -> eyre::Result<Provider<Http>> {
-    let provider = Provider::<Http>::try_from(rpc_url);
-    match provider {
-        Ok(prov) => Ok(prov),
+/// * `Provider<RpcTransport>` - A new instance of `Provider<RpcTransport>`
+pub async fn get_provider(rpc_url: &str) -> eyre::Result<Provider<RpcTransport>> {
+    let transport = RpcTransport::connect(rpc_url).await;
+    match transport {
+        Ok(transport) => Ok(Provider::new(transport)),
         Err(e) => Err(eyre::eyre!("Failed to get provider: {}", e))
     }
 }
 
+/// Prefix of the environment variables of the given chain id: `MAINNET` for mainnet, `SEPOLIA` for sepolia,
+/// and `LOCAL` for local devnets (31337 for anvil and hardhat, 1337 for geth and ganache)
+fn chain_env_prefix(chain_id: u32) -> eyre::Result<&'static str> {
+    match chain_id {
+        1 => Ok("MAINNET"),
+        11155111 => Ok("SEPOLIA"),
+        31337 | 1337 => Ok("LOCAL"),
+        _ => Err(eyre::eyre!("Unsupported chain id: {}. Halting...", chain_id))
+    }
+}
+
+/// Create an instance of a provider for the given chain id, with retries, request timeouts and fallback endpoints.
+/// The RPC URL (or IPC socket path) is read from the environment: `MAINNET_RPC` for mainnet, `SEPOLIA_RPC` for sepolia,
+/// `LOCAL_RPC` for local devnets.
+/// Fallback endpoints, tried in order when it fails, are read from `MAINNET_RPC_FALLBACKS`/`SEPOLIA_RPC_FALLBACKS`/`LOCAL_RPC_FALLBACKS`
+/// as a comma separated list, and the retry settings from `RPC_MAX_RETRIES`, `RPC_INITIAL_BACKOFF_MS` and `RPC_TIMEOUT_SECS`.
+/// ### Arguments
+/// * `chain_id` - Chain ID
+/// 
+/// ### Returns
+/// * `Provider<ResilientTransport>` - A new instance of `Provider<ResilientTransport>`
+pub async fn get_chain_provider(chain_id: u32) -> eyre::Result<Provider<ResilientTransport>> {
+    let prefix = chain_env_prefix(chain_id)?;
+    let rpc_var = format!("{}_RPC", prefix);
+    let mut endpoints = match std::env::var(&rpc_var) {
+        Ok(rpc_url) => vec![rpc_url],
+        Err(_) => return Err(eyre::eyre!("{} is not set. Halting...", rpc_var))
+    };
+    if let Ok(fallbacks) = std::env::var(format!("{}_RPC_FALLBACKS", prefix)) {
+        endpoints.extend(
+            fallbacks
+                .split(',')
+                .map(|url| url.trim().to_string())
+                .filter(|url| !url.is_empty())
+        );
+    }
+    match ResilientTransport::connect(&endpoints, RetryConfig::from_env()?).await {
+        Ok(transport) => Ok(Provider::new(transport)),
+        Err(e) => Err(eyre::eyre!("Failed to get provider: {}", e))
+    }
+}
+
+/// Create an instance of a provider that supports subscriptions for the given chain id.
+/// The WebSocket URL (or IPC socket path) is read from the environment:
+/// `MAINNET_WS_RPC` for mainnet, `SEPOLIA_WS_RPC` for sepolia, `LOCAL_WS_RPC` for local devnets, and defaults to
+/// `MAINNET_RPC`/`SEPOLIA_RPC` if that is a WebSocket or IPC endpoint.
+/// ### Arguments
+/// * `chain_id` - Chain ID
+/// 
+/// ### Returns
+/// * `Provider<RpcTransport>` - A new instance of `Provider<RpcTransport>`, connected
+pub async fn get_chain_ws_provider(chain_id: u32) -> eyre::Result<Provider<RpcTransport>> {
+    let prefix = chain_env_prefix(chain_id)?;
+    let ws_var = format!("{}_WS_RPC", prefix);
+    let rpc_var = format!("{}_RPC", prefix);
+    let (var, ws_url) = match (std::env::var(&ws_var), std::env::var(&rpc_var)) {
+        (Ok(ws_url), _) => (ws_var, ws_url),
+        (Err(_), Ok(rpc_url)) if !rpc_url.starts_with("http://") && !rpc_url.starts_with("https://") => (rpc_var, rpc_url),
+        _ => return Err(eyre::eyre!("{} is not set", ws_var))
+    };
+    match RpcTransport::connect(&ws_url).await {
+        Ok(transport) if transport.supports_pubsub() => Ok(Provider::new(transport)),
+        Ok(_) => Err(eyre::eyre!("{} must be a WebSocket URL or IPC socket path", var)),
+        Err(e) => Err(eyre::eyre!("Failed to connect to {}: {}", var, e))
+    }
+}
+
+/// Name of the environment variable overriding the Purse404 address on the given chain id,
+/// eg: `SEPOLIA_PURSE_ADDRESS`, see `get_purse_address`
+/// ### Arguments
+/// * `chain_id` - Chain ID
+/// 
+/// ### Returns
+/// * `Result<String>` - The variable name
+pub fn purse_address_var(chain_id: u32) -> eyre::Result<String> {
+    Ok(format!("{}_PURSE_ADDRESS", chain_env_prefix(chain_id)?))
+}
+
+/// Get the address of the Purse404 contract on the given chain id.
+/// Read from `<CHAIN>_PURSE_ADDRESS` in the environment (see `purse_address_var`), then from the deployment
+/// registry written by the `deploy` command, and defaults to the `PURSE_ETH_ADDRESS` deployment.
+/// ### Arguments
+/// * `chain_id` - Chain ID
+/// 
+/// ### Returns
+/// * `Result<Address>` - The Purse404 address
+pub fn get_purse_address(chain_id: u32) -> eyre::Result<Address> {
+    let var = purse_address_var(chain_id)?;
+    if let Ok(address) = std::env::var(&var) {
+        return address
+            .parse()
+            .map_err(|_| eyre::eyre!("Invalid {}: {}", var, address))
+    }
+    if let Some(deployment) = DeploymentRegistry::load(&registry_path())?.get(u64::from(chain_id)) {
+        return Ok(deployment.proxy)
+    }
+    Ok(to_address_type(PURSE_ETH_ADDRESS))
+}
+
+/// Get the address of the Multicall3 contract used for batched reads.
+/// Read from `MULTICALL_ADDRESS` in the environment, eg: for a Multicall3 deployed on a local devnet,
+/// and defaults to the address Multicall3 is deployed at on mainnet and sepolia.
+/// ### Returns
+/// * `Result<Address>` - The Multicall3 address
+pub fn get_multicall_address() -> eyre::Result<Address> {
+    match std::env::var("MULTICALL_ADDRESS") {
+        Ok(address) => address
+            .parse()
+            .map_err(|_| eyre::eyre!("Invalid MULTICALL_ADDRESS: {}", address)),
+        Err(_) => Ok(MULTICALL_ADDRESS),
+    }
+}
+
+/// Get the address of the MultiSendCallOnly contract Safe transactions are batched through.
+/// Read from `MULTISEND_ADDRESS` in the environment, and defaults to the address it is deployed at
+/// on mainnet and sepolia.
+/// ### Returns
+/// * `Result<Address>` - The MultiSendCallOnly address
+pub fn get_multisend_address() -> eyre::Result<Address> {
+    match std::env::var("MULTISEND_ADDRESS") {
+        Ok(address) => address
+            .parse()
+            .map_err(|_| eyre::eyre!("Invalid MULTISEND_ADDRESS: {}", address)),
+        Err(_) => Ok(to_address_type(MULTISEND_CALL_ONLY_ADDRESS)),
+    }
+}
+
 /// Get the balance (native) of the given address.
 /// This is NOT the ERC20 balance.
 /// ### Arguments
@@ -31,14 +171,46 @@ pub async fn get_provider(rpc_url: &str) -> eyre::Result<Provider<Http>> {
 /// 
 /// ### Returns
 /// * `Result<U256>` - Result
-pub async fn get_native_balance(prov: &Provider<Http>, address: &Address) -> Result<U256> {
-    let balance = prov.clone().get_balance(*address, None).await;
+pub async fn get_native_balance<M: Middleware>(prov: &M, address: &Address) -> Result<U256> {
+    let balance = prov.get_balance(*address, None).await;
     match balance {
         Ok(bal) => Ok(bal),
         Err(e) => Err(eyre::eyre!("Failed to get balance: {}", e))
     }
 }
 
+/// Get the balance (native) of the given address, at the given block.
+/// This is NOT the ERC20 balance.
+/// ### Arguments
+/// * `prov` - Provider
+/// * `address` - Address
+/// * `block` - Block to read the balance at
+/// 
+/// ### Returns
+/// * `Result<U256>` - Result
+pub async fn get_native_balance_at<M: Middleware>(prov: &M, address: &Address, block: BlockId) -> Result<U256> {
+    let balance = prov.get_balance(*address, Some(block)).await;
+    match balance {
+        Ok(bal) => Ok(bal),
+        Err(e) => Err(eyre::eyre!("Failed to get balance at block {:?}: {}", block, e))
+    }
+}
+
+/// Get the timestamp of the given block
+/// ### Arguments
+/// * `prov` - Provider
+/// * `block_number` - Block number
+/// 
+/// ### Returns
+/// * `Result<u64>` - Unix timestamp (seconds) of the block
+pub async fn get_block_timestamp<M: Middleware>(prov: &M, block_number: u64) -> Result<u64> {
+    match prov.get_block(block_number).await {
+        Ok(Some(block)) => Ok(block.timestamp.as_u64()),
+        Ok(None) => Err(eyre::eyre!("Block {} not found", block_number)),
+        Err(e) => Err(eyre::eyre!("Failed to get block {}: {}", block_number, e))
+    }
+}
+
 /// Converts the given string slice to an `Address` (H160) type
 /// ### Arguments
 /// * `str_slice` - String slice
@@ -69,6 +241,178 @@ pub fn parse_u256(s: &str) -> Result<U256, String> {
     U256::from_str_radix(s, 10).map_err(|_| format!("String {s} is not a valid U256"))
 }
 
+/// Parses an amount into wei. The amount may be given in wei, eg: "1000", in scientific notation, eg: "100e18",
+/// or with a unit: "250gwei", "1.5ether" (or "eth"), and the token symbol, eg: "100 PURSE", for token amounts.
+/// ### Arguments
+/// * `s` - String slice of the amount
+/// * `token` - Symbol and decimals of the token, `None` if token amounts are not accepted
+/// 
+/// ### Returns
+/// * `Result<U256>` - The amount in wei
+pub fn parse_amount(s: &str, token: Option<(&str, u8)>) -> Result<U256> {
+    let s = s.trim();
+    let number_end = s.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(s.len());
+    let (number, mut unit) = s.split_at(number_end);
+    if number.is_empty() {
+        return Err(eyre::eyre!("Amount {} does not start with a number", s))
+    }
+    let mut exponent = 0;
+    if let Some(rest) = unit.strip_prefix(['e', 'E']) {
+        let digits_end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        if digits_end > 0 {
+            exponent = rest[..digits_end].parse::<u32>()?;
+            unit = &rest[digits_end..];
+        }
+    }
+    let unit = unit.trim().to_lowercase();
+    let unit_decimals = match (unit.as_str(), token) {
+        ("" | "wei", _) => 0,
+        ("gwei", _) => 9,
+        ("ether" | "eth", _) => 18,
+        (unit, Some((symbol, decimals))) if unit == symbol.to_lowercase() => u32::from(decimals),
+        (unit, Some((symbol, _))) => {
+            return Err(eyre::eyre!("Unknown unit {} in {}, expected wei, gwei, ether or {}", unit, s, symbol))
+        },
+        (unit, None) => return Err(eyre::eyre!("Unknown unit {} in {}, expected wei, gwei or ether", unit, s)),
+    };
+    let decimals = unit_decimals
+        .checked_add(exponent)
+        .ok_or_else(|| eyre::eyre!("Amount {} is out of range", s))?;
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+    // Digits past the decimals would be truncated, refuse them instead of dropping them
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(eyre::eyre!("Amount {} is not a whole number of wei", s))
+    }
+    if integer.is_empty() && fraction.is_empty() {
+        return Err(eyre::eyre!("Amount {} does not start with a number", s))
+    }
+    let digits = U256::from_dec_str(&format!("{}{}", integer, fraction))
+        .map_err(|_| eyre::eyre!("Invalid amount {}: out of range", s))?;
+    if digits.is_zero() {
+        return Ok(digits)
+    }
+    U256::from(10)
+        .checked_pow(U256::from(decimals - fraction.len() as u32))
+        .and_then(|scale| digits.checked_mul(scale))
+        .ok_or_else(|| eyre::eyre!("Amount {} is out of range", s))
+}
+
+/// Parses an ETH amount into wei, see `parse_amount`
+/// ### Arguments
+/// * `s` - String slice of the amount, eg: "0.01ether"
+/// 
+/// ### Returns
+/// * `Result<U256, String>` - Result
+pub fn parse_eth_amount(s: &str) -> Result<U256, String> {
+    parse_amount(s, None).map_err(|e| e.to_string())
+}
+
+/// Formats a wei amount in whole units, without trailing zeros, eg: "1.5"
+/// ### Arguments
+/// * `amount` - Amount in wei
+/// * `decimals` - Decimals of the unit, 18 for ETH
+/// 
+/// ### Returns
+/// * `String` - The amount in units
+pub fn format_amount(amount: U256, decimals: u8) -> String {
+    let formatted = ethers::utils::format_units(amount, u32::from(decimals)).unwrap_or_else(|_| amount.to_string());
+    match formatted.contains('.') {
+        true => formatted.trim_end_matches('0').trim_end_matches('.').to_string(),
+        false => formatted,
+    }
+}
+
+/// Formats a decoded ABI token the way it is passed as calldata on the command line
+/// ### Arguments
+/// * `token` - ABI token
+/// 
+/// ### Returns
+/// * `String` - Addresses and bytes as 0x prefixed hex, integers in decimal
+pub fn token_to_string(token: &Token) -> String {
+    match token {
+        Token::Address(addr) => format!("{:?}", addr),
+        Token::Uint(val) | Token::Int(val) => val.to_string(),
+        Token::Bool(val) => val.to_string(),
+        Token::String(val) => val.clone(),
+        Token::Bytes(val) | Token::FixedBytes(val) => format!("0x{}", hex::encode(val)),
+        Token::Array(tokens) | Token::FixedArray(tokens) | Token::Tuple(tokens) => {
+            let items: Vec<String> = tokens.iter().map(token_to_string).collect();
+            format!("[{}]", items.join(","))
+        }
+    }
+}
+
+/// Parses a UTC date from the given `&str`
+/// ### Arguments
+/// * `s` - String slice of the date, formatted as YYYY-MM-DD
+/// 
+/// ### Returns
+/// * `Result<NaiveDate, String>` - Result
+pub fn parse_date(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| format!("String {s} is not a valid YYYY-MM-DD date"))
+}
+
+/// Returns the current unix timestamp in seconds
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Formats rows of values as a plain text table, with left aligned columns
+/// ### Arguments
+/// * `headers` - Column headers
+/// * `rows` - Rows of values, each with as many values as there are headers
+/// 
+/// ### Returns
+/// * `String` - The table
+pub fn format_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, value) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    fn format_row<S: AsRef<str>>(values: &[S], widths: &[usize]) -> String {
+        values
+            .iter()
+            .zip(widths.iter())
+            .map(|(value, width)| format!("{:<width$}", value.as_ref(), width = *width))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    }
+
+    let separator: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    let mut lines = vec![
+        format_row(headers, &widths),
+        format_row(&separator, &widths),
+    ];
+    for row in rows {
+        lines.push(format_row(row, &widths));
+    }
+
+    lines.join("\n")
+}
+
+/// Asks for confirmation on the terminal before an action, only "y" or "yes" confirms
+/// ### Arguments
+/// * `question` - Question printed before the `[y/N]` prompt
+/// 
+/// ### Returns
+/// * `Result<bool>` - `true` if confirmed
+pub fn confirm_prompt(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 /// Converts the given string slice of a WEI value to an ETH value
 /// ### Arguments
 /// * `wei` - WEI value as a string slice
@@ -99,6 +443,24 @@ pub fn get_tx_hash(receipt_json: &str) -> String {
     }
 }
 
+/// Extracts the block number from the transaction receipt JSON
+/// ### Arguments
+/// * `receipt_json` - Transaction receipt JSON
+/// 
+/// ### Returns
+/// * `u64` - Block number
+pub fn get_block_number(receipt_json: &str) -> u64 {
+    let receipt: Value = serde_json::from_str(
+        receipt_json
+    ).expect("Failed to parse receipt JSON");
+    if let Some(block_number) = receipt["blockNumber"].as_str() {
+        let hexa = block_number.trim_start_matches("0x");
+        u64::from_str_radix(hexa, 16).unwrap()
+    } else {
+        panic!("Failed to get block number from receipt: Not found");
+    }
+}
+
 /// Extracts the gas used from the transaction receipt JSON
 /// ### Arguments
 /// * `receipt_json` - Transaction receipt JSON
@@ -165,4 +527,41 @@ pub fn calc_tx_fee(receipt_json: &str) -> String {
     let tx_fee_eth = gas_used_val * gas_price_wei / 1_000_000_000_000_000_000.0;
 
     tx_fee_eth.to_string()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_amount() {
+        let purse = Some(("PURSE", 18));
+        assert_eq!(parse_amount("1000", None).unwrap(), U256::from(1000));
+        assert_eq!(parse_amount("250gwei", None).unwrap(), U256::from(250_000_000_000u64));
+        assert_eq!(parse_amount("1.5ether", None).unwrap(), U256::from(1_500_000_000_000_000_000u64));
+        assert_eq!(parse_amount("1.5 eth", None).unwrap(), U256::from(1_500_000_000_000_000_000u64));
+        assert_eq!(parse_amount("100e18", None).unwrap(), U256::exp10(20));
+        assert_eq!(parse_amount("1.5e3gwei", None).unwrap(), U256::from(1_500_000_000_000u64));
+        assert_eq!(parse_amount("100 PURSE", purse).unwrap(), U256::exp10(20));
+        assert_eq!(parse_amount("0.25purse", purse).unwrap(), U256::from(250_000_000_000_000_000u64));
+
+        assert!(parse_amount("100 PURSE", None).is_err());
+        assert!(parse_amount("1.5", None).is_err());
+        assert!(parse_amount("1.5wei", None).is_err());
+        assert!(parse_amount("ether", None).is_err());
+        assert!(parse_amount("100 USDC", purse).is_err());
+        assert!(parse_amount("1.2.3", None).is_err());
+
+        // Amounts past the range of U256 are refused instead of overflowing
+        assert!(parse_amount("2e77", None).is_err());
+        assert!(parse_amount("2e59 PURSE", purse).is_err());
+        assert!(parse_amount("1e4294967295 ether", None).is_err());
+        assert!(parse_amount("1000000000000000000000000000000000000000000000000000000000000000000000000000000", None).is_err());
+        assert_eq!(parse_amount("0e100", None).unwrap(), U256::zero());
+        assert_eq!(parse_amount("1e77", None).unwrap(), U256::exp10(77));
+
+        assert_eq!(format_amount(U256::from(1_500_000_000_000_000_000u64), 18), "1.5");
+        assert_eq!(format_amount(U256::exp10(20), 18), "100");
+        assert_eq!(format_amount(U256::from(100), 18), "0.0000000000000001");
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashSet;
+use ethers::{
+    providers::Middleware,
+    types::{Address, BlockId, H256, U256},
+};
+use serde::Serialize;
+use crate::{
+    contract::{
+        purse_contract::Purse404Contract,
+        purse_executor::{
+            decode_purse_calldata,
+            transfer_or_mint_recipient_n_calldata,
+        },
+    },
+    file::Record,
+    utils::{
+        get_native_balance_at,
+        str_wei_to_eth,
+    },
+};
+
+/// A logged value that does not match the chain state
+#[derive(Debug, Clone, Serialize)]
+pub struct Discrepancy {
+    /// Column of the csv log
+    pub field: String,
+    /// Value in the csv log
+    pub logged: String,
+    /// Value on chain
+    pub on_chain: String,
+}
+
+/// Result of verifying one record of the csv log against the chain state
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordVerification {
+    pub transaction_hash: String,
+    pub derivation: u32,
+    pub function: String,
+    /// Block the transaction was included in, `None` if it was not found
+    pub block_number: Option<u64>,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl RecordVerification {
+    /// Returns `true` if the record matches the chain state
+    pub fn is_valid(&self) -> bool {
+        self.block_number.is_some() && self.discrepancies.is_empty()
+    }
+}
+
+/// Checks whether a logged address matches the given address.
+/// Logs written before full addresses were recorded hold abbreviated addresses, eg: "0xf775…8d82",
+/// which are compared in their abbreviated form.
+/// ### Arguments
+/// * `logged` - Address as recorded in the csv log
+/// * `address` - Address to compare with
+/// 
+/// ### Returns
+/// * `bool` - `true` if the addresses match
+pub fn logged_address_matches(logged: &str, address: &Address) -> bool {
+    if logged.contains('…') {
+        logged == address.to_string()
+    } else {
+        logged.parse::<Address>().map(|a| a == *address).unwrap_or(false)
+    }
+}
+
+/// Checks whether a logged ETH denominated value matches the given WEI value
+fn logged_eth_matches(logged: f64, wei: U256) -> bool {
+    str_wei_to_eth(&wei.to_string()).parse::<f64>().map(|v| v == logged).unwrap_or(false)
+}
+
+/// Checks whether a logged raw value matches the given value
+fn logged_raw_matches(logged: f64, value: U256) -> bool {
+    value.to_string().parse::<f64>().map(|v| v == logged).unwrap_or(false)
+}
+
+/// Verifies one record of the csv log against the chain state.
+/// 
+/// The transaction is fetched by hash, and its sender, recipient, function selector, msg value and
+/// calldata value are compared with the record. The logged balances are compared with the balances at
+/// the block before (`block - 1`) and the block of (`block`) the transaction, and the logged owned
+/// token IDs with those at the block of the transaction.
+/// 
+/// #### Note
+/// Balances logged from reads at the latest block, instead of at the block of the transaction,
+/// are reported as discrepancies when other transactions changed them in between.
+/// ### Arguments
+/// * `prov` - Provider
+/// * `purse_token` - Purse404 contract
+/// * `record` - Record of the csv log
+/// 
+/// ### Returns
+/// * `Result<RecordVerification>` - The verification result
+pub async fn verify_record<M: Middleware + 'static>(
+    prov: &M,
+    purse_token: &Purse404Contract<M>,
+    record: &Record,
+) -> eyre::Result<RecordVerification> {
+    let mut verification = RecordVerification {
+        transaction_hash: record.transaction_hash.clone(),
+        derivation: record.derivation,
+        function: record.function.clone(),
+        block_number: None,
+        discrepancies: Vec::new(),
+    };
+    let mut discrepancies = Vec::new();
+    let mut mismatch = |field: &str, logged: String, on_chain: String| {
+        discrepancies.push(Discrepancy { field: field.to_string(), logged, on_chain });
+    };
+
+    let tx_hash = record.transaction_hash.parse::<H256>()?;
+    let tx = match prov.get_transaction(tx_hash).await? {
+        Some(tx) => tx,
+        None => return Ok(verification),
+    };
+    let receipt = match prov.get_transaction_receipt(tx_hash).await? {
+        Some(receipt) => receipt,
+        None => return Ok(verification),
+    };
+    let block_number = match receipt.block_number {
+        Some(n) => n.as_u64(),
+        None => return Ok(verification),
+    };
+
+    if !logged_address_matches(&record.sender, &tx.from) {
+        mismatch("Sender", record.sender.clone(), format!("{:?}", tx.from));
+    }
+    if tx.to != Some(purse_token.address()) {
+        mismatch("To", format!("{:?}", purse_token.address()), format!("{:?}", tx.to));
+    }
+    if !logged_raw_matches(record.msg_value, tx.value) {
+        mismatch("Msg Value (Raw)", record.msg_value.to_string(), tx.value.to_string());
+    }
+    if !logged_raw_matches(record.gas_used as f64, receipt.gas_used.unwrap_or_default()) {
+        mismatch("Gas Used", record.gas_used.to_string(), receipt.gas_used.unwrap_or_default().to_string());
+    }
+
+    let (call_fn, calldata) = match decode_purse_calldata(&tx.input) {
+        Ok(decoded) => decoded,
+        Err(e) => (format!("unknown ({})", e), Vec::new()),
+    };
+    if call_fn != record.function {
+        mismatch("Function", record.function.clone(), call_fn.clone());
+    }
+    let (recipient, calldata_value) = transfer_or_mint_recipient_n_calldata(&call_fn, &calldata)?;
+    if !logged_address_matches(&record.recipient, &recipient) {
+        mismatch("Recipient", record.recipient.clone(), format!("{:?}", recipient));
+    }
+    if !logged_raw_matches(record.calldata_value, calldata_value) {
+        mismatch("Calldata Value (Raw)", record.calldata_value.to_string(), calldata_value.to_string());
+    }
+
+    let block_bef = BlockId::from(block_number.saturating_sub(1));
+    let block_aft = BlockId::from(block_number);
+    let balances = [
+        ("Sender Balance Before (ETH)", record.sender_balance_before_eth,
+            get_native_balance_at(prov, &tx.from, block_bef).await?),
+        ("Sender Balance After (ETH)", record.sender_balance_after_eth,
+            get_native_balance_at(prov, &tx.from, block_aft).await?),
+        ("Sender Balance Before (ERC20)", record.sender_balance_before_erc20,
+            purse_token.balance_of_at(&tx.from, block_bef).await?),
+        ("Sender Balance After (ERC20)", record.sender_balance_after_erc20,
+            purse_token.balance_of_at(&tx.from, block_aft).await?),
+        ("Recipient Balance Before (ETH)", record.recipient_balance_before_eth,
+            get_native_balance_at(prov, &recipient, block_bef).await?),
+        ("Recipient Balance After (ETH)", record.recipient_balance_after_eth,
+            get_native_balance_at(prov, &recipient, block_aft).await?),
+        ("Recipient Balance Before (ERC20)", record.recipient_balance_before_erc20,
+            purse_token.balance_of_at(&recipient, block_bef).await?),
+        ("Recipient Balance After (ERC20)", record.recipient_balance_after_erc20,
+            purse_token.balance_of_at(&recipient, block_aft).await?),
+    ];
+    for (field, logged, on_chain) in balances {
+        if !logged_eth_matches(logged, on_chain) {
+            mismatch(field, logged.to_string(), str_wei_to_eth(&on_chain.to_string()));
+        }
+    }
+
+    let owned: Vec<String> = purse_token
+        .owned_at(&tx.from, block_aft)
+        .await?
+        .iter()
+        .map(|id| id.to_string())
+        .collect();
+    let logged_owned: HashSet<&str> = record.owned_token_ids().into_iter().collect();
+    if logged_owned != owned.iter().map(|id| id.as_str()).collect::<HashSet<&str>>() {
+        mismatch("Msg.sender Owned Token IDs", record.msg_sender_owned_token_ids.clone(), owned.join(","));
+    }
+
+    verification.block_number = Some(block_number);
+    verification.discrepancies = discrepancies;
+    Ok(verification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logged_address_matches() {
+        let address: Address = "0xf775000000000000000000000000000000008d82".parse().unwrap();
+        let other: Address = "0xf775000000000000000000000000000000018d82".parse().unwrap();
+
+        // Full addresses are compared in full, abbreviated addresses in their abbreviated form
+        assert!(logged_address_matches("0xf775000000000000000000000000000000008d82", &address));
+        assert!(logged_address_matches("0xF775000000000000000000000000000000008D82", &address));
+        assert!(!logged_address_matches("0xf775000000000000000000000000000000008d82", &other));
+        assert!(logged_address_matches("0xf775…8d82", &address));
+        assert!(logged_address_matches("0xf775…8d82", &other));
+        assert!(!logged_address_matches("0xdf7e…6527", &address));
+
+        assert!(!logged_address_matches("", &address));
+        assert!(!logged_address_matches("", &Address::zero()));
+    }
+
+    #[test]
+    fn test_logged_values_match() {
+        let wei = U256::from_dec_str("3401571268146085288").unwrap();
+        assert!(logged_eth_matches("3.401571268146085288".parse().unwrap(), wei));
+        assert!(logged_eth_matches("3.40157126814608528800".parse().unwrap(), wei));
+        assert!(!logged_eth_matches("3.4015712681".parse().unwrap(), wei));
+
+        let wei = U256::exp10(18);
+        assert!(logged_eth_matches("1".parse().unwrap(), wei));
+        assert!(logged_eth_matches("1.000000000000000000".parse().unwrap(), wei));
+
+        // Values logged as zero for empty balances and calldata
+        assert!(logged_eth_matches(0.0, U256::zero()));
+        assert!(logged_raw_matches(0.0, U256::zero()));
+        assert!(!logged_raw_matches(0.0, U256::from(1)));
+
+        assert!(logged_raw_matches(1000.0, U256::from(1000)));
+        assert!(!logged_raw_matches(1000.0, U256::from(100)));
+    }
+}
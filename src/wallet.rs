@@ -13,7 +13,9 @@ use ethers::{
 #[derive(Clone, Debug)]
 pub struct Wallet {
     /// Signing key of the wallet
-    pub signer: ethers::signers::Wallet<SigningKey>
+    pub signer: ethers::signers::Wallet<SigningKey>,
+    /// Derivation path number the wallet was derived with
+    pub derivation: u32,
 }
 
 /// Implementation (Methods) for Wallet
@@ -37,7 +39,10 @@ impl Wallet {
             )
             .build()?;
 
-        Ok(Self { signer: wallet.with_chain_id(chain_id) })
+        Ok(Self { 
+            signer: wallet.with_chain_id(chain_id),
+            derivation: derivation_path_number,
+        })
     }
 
     /// Generate wallets from the given number of wallets and chain ID